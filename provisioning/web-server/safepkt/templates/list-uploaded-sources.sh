@@ -3,4 +3,4 @@
 cd /uploaded-sources || exit
 
 # shellcheck disable=SC2012
-ls ./ | sed -e 's/.rs.b64$//g'
\ No newline at end of file
+ls ./ | grep -E '.rs.b64$' | sed -e 's/.rs.b64$//g'
\ No newline at end of file
@@ -18,11 +18,15 @@ function setup_verifier() {
   git clone https://github.com/paritytech/ink /safepkt-ink
   cd /safepkt-ink && git checkout v2.1.0
 
+  # templates of ink! 3 are scaffolded from another checkout
+  git clone https://github.com/paritytech/ink /safepkt-ink-3.0.0-rc4
+  cd /safepkt-ink-3.0.0-rc4 && git checkout v3.0.0-rc4
+
   git clone https://github.com/thierrymarianne/safepkt-rust-smallvec /safepkt-rust-smallvec
   cd /safepkt-rust-smallvec && git checkout v1.7.1
 
-  chown -R ${UID_GID} /safepkt-ink
-  chmod -R ug+rwx /safepkt-ink
+  chown -R ${UID_GID} /safepkt-ink /safepkt-ink-3.0.0-rc4
+  chmod -R ug+rwx /safepkt-ink /safepkt-ink-3.0.0-rc4
   chmod a+x /usr/local/bin/verify
 
   cd "${RVT_DIR}" || exit
@@ -41,6 +45,35 @@ function setup_verifier() {
   cp -R "${RVT_DIR}/simd_emulation" /safepkt-simd_emulation
   cp -R "${RVT_DIR}/runtime" /safepkt-runtime
 
+  # SeaHorn (LLVM 10 branch) runs `cargo verify --backend=seahorn`,
+  # along with the Yices and Z3 solvers it is linked against
+  apt install -y cmake ninja-build gperf libgmp-dev libboost-dev lld-10
+
+  git clone https://github.com/SRI-CSL/yices2 /safepkt-yices2
+  cd /safepkt-yices2 && git checkout Yices-2.6.2 && \
+    autoconf && ./configure --prefix=/opt/yices-2.6.2 && make && make install
+
+  git clone https://github.com/Z3Prover/z3 /safepkt-z3
+  cd /safepkt-z3 && git checkout z3-4.8.9 && \
+    python3 scripts/mk_make.py --prefix=/opt/z3-4.8.9 && cd build && make && make install
+
+  git clone https://github.com/seahorn/seahorn /safepkt-seahorn
+  cd /safepkt-seahorn && git checkout dev10 && mkdir build && cd build && \
+    cmake -GNinja -DCMAKE_BUILD_TYPE=Release -DCMAKE_INSTALL_PREFIX=/opt/seahorn \
+      -DCMAKE_C_COMPILER=clang-${LLVM_VERSION} -DCMAKE_CXX_COMPILER=clang++-${LLVM_VERSION} \
+      -DZ3_ROOT=/opt/z3-4.8.9 -DYICES2_HOME=/opt/yices-2.6.2 -DSEA_ENABLE_LLD=ON .. && \
+    ninja clam-seadsa && ninja clam-seallvm && ninja crab && ninja extra && ninja && ninja install
+  ln -s /opt/seahorn/bin/sea /usr/local/bin/sea
+  rm -rf /safepkt-yices2 /safepkt-z3 /safepkt-seahorn
+
+  # fetch dependencies of smart contracts ahead of time
+  # for verification containers to run without network
+  mkdir /safepkt-cargo-home && chown -R "${UID_GID}" /safepkt-cargo-home
+  sudo -urvt /bin/bash -xc "source ~rvt/.cargo/env && cd /safepkt-ink/examples/multisig_plain && \
+    CARGO_HOME=/safepkt-cargo-home cargo fetch"
+  sudo -urvt /bin/bash -xc "source ~rvt/.cargo/env && cd /safepkt-ink-3.0.0-rc4/examples/erc20 && \
+    CARGO_HOME=/safepkt-cargo-home cargo fetch"
+
   cd "${workdir}" || exit
 }
 setup_verifier
\ No newline at end of file
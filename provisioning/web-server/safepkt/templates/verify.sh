@@ -2,6 +2,59 @@
 
 set -e
 
+# containers without network rely on dependencies fetched when building the image
+function cargo_home_needs_seeding() {
+    local cargo_home
+    cargo_home="${1}"
+
+    [ ! -d "${cargo_home}" ] || \
+      { [ -n "${CARGO_NET_OFFLINE}" ] && [ -z "$(ls -A "${cargo_home}")" ] && [ -d /safepkt-cargo-home ]; }
+}
+
+function seed_cargo_home() {
+    local cargo_home
+    cargo_home="${1}"
+
+    if [ ! -d "${cargo_home}" ];
+    then
+      mkdir "${cargo_home}"
+    fi
+
+    if [ -n "${CARGO_NET_OFFLINE}" ] && [ -z "$(ls -A "${cargo_home}")" ] && [ -d /safepkt-cargo-home ];
+    then
+      cp -R /safepkt-cargo-home/. "${cargo_home}"
+    fi
+}
+
+# hold a lock on an entry of the build cache (on fd 9) until the step exits:
+# exclusive only while seeding an empty entry,
+# shared while building so that steps using the same entry run concurrently
+function lock_build_cache() {
+    local build_cache
+    build_cache="${1}"
+
+    local cargo_home
+    cargo_home="${build_cache}/cargo-home"
+
+    exec 9>>"${build_cache}/lock"
+
+    # waiting for an exclusive lock would wait for other steps to finish building
+    if cargo_home_needs_seeding "${cargo_home}" && flock -x -n 9;
+    then
+      seed_cargo_home "${cargo_home}"
+    fi
+
+    flock -s 9
+
+    # the entry was being seeded (or used) by another step
+    if cargo_home_needs_seeding "${cargo_home}";
+    then
+      flock -x 9
+      seed_cargo_home "${cargo_home}"
+      flock -s 9
+    fi
+}
+
 function verify() {
     local package_name
     package_name="${1}"
@@ -12,46 +65,150 @@ function verify() {
     local smart_contract_example
     smart_contract_example="${3}"
 
+    # optional parameters are declared in the environment
+    # (positional arguments being still supported)
     local proptest_cases
-    proptest_cases="${4}"
+    proptest_cases="${4:-${PROPTEST_CASES}}"
 
     local quiet
-    quiet="${5}"
+    quiet="${5:-${VERIFY_QUIET}}"
+    if [ "${quiet}" = 'false' ];
+    then
+      quiet=
+    fi
+
+    # only the test declared in the environment is run (all tests by default)
+    local test_filter=
+    if [ -n "${VERIFY_TEST}" ];
+    then
+      test_filter="--test=${VERIFY_TEST}"
+    fi
 
     local cargo_home=
     cargo_home='/safepkt-ink/examples/source/deps'
 
-    if [ ! -d "${cargo_home}" ];
+    # projects having the same dependencies share a cache entry,
+    # locked for as long as the step runs so that the backend never evicts it
+    if [ -n "${SAFEPKT_BUILD_CACHE}" ];
     then
-      mkdir "${cargo_home}"
+      cargo_home="${SAFEPKT_BUILD_CACHE}/cargo-home"
+      export CARGO_TARGET_DIR="${SAFEPKT_BUILD_CACHE}/target"
+      lock_build_cache "${SAFEPKT_BUILD_CACHE}"
+    else
+      seed_cargo_home "${cargo_home}"
     fi
 
     export CARGO_HOME="${cargo_home}"
 
-    test -d /home/rust-verification-tools/simd_emulation && \
-      rm -rf /home/rust-verification-tools/simd_emulation
-    cp -R /safepkt-simd_emulation /home/rust-verification-tools/simd_emulation && \
-      echo '=> Successfully copied LLVM bitcode generated from ./simd_emulation' >> /safepkt-ink/examples/source/verification.log
+    # the RVT directory is mounted read-only,
+    # in which case it is expected to contain LLVM bitcode already
+    if [ -w /home/rust-verification-tools ];
+    then
+      test -d /home/rust-verification-tools/simd_emulation && \
+        rm -rf /home/rust-verification-tools/simd_emulation
+      cp -R /safepkt-simd_emulation /home/rust-verification-tools/simd_emulation && \
+        echo '=> Successfully copied LLVM bitcode generated from ./simd_emulation' >> /safepkt-ink/examples/source/verification.log
+
+      test -d /home/rust-verification-tools/runtime && \
+        rm -rf /home/rust-verification-tools/runtime
+      cp -R /safepkt-runtime /home/rust-verification-tools/runtime && \
+        echo '=> Successfully copied LLVM bitcode generated from RVT ./runtime' >> /safepkt-ink/examples/source/verification.log
+    fi
 
-    test -d /home/rust-verification-tools/runtime && \
-      rm -rf /home/rust-verification-tools/runtime
-    cp -R /safepkt-runtime /home/rust-verification-tools/runtime && \
-      echo '=> Successfully copied LLVM bitcode generated from RVT ./runtime' >> /safepkt-ink/examples/source/verification.log
+    # the template is an example of the checkout of ink! declared in the environment (INK_DIRECTORY),
+    # only examples of ink! 2 shipping a crate generating their ABI (.ink/abi_gen)
+    local ink_directory
+    ink_directory="${INK_DIRECTORY:-/safepkt-ink}"
 
-    cp -R "/safepkt-ink/examples/${smart_contract_example}/.ink" /safepkt-ink/examples/source && \
-    sed -i 's/'"${smart_contract_example}"'/'"${package_name}"'/g' /safepkt-ink/examples/source/.ink/abi_gen/Cargo.toml && \
-    sed -i 's/'"${smart_contract_example}"'/'"${package_name}"'/g' /safepkt-ink/examples/source/src/lib.rs
+    # plain libraries and binaries are not scaffolded from a template
+    if [ -n "${smart_contract_example}" ];
+    then
+      if [ -d "${ink_directory}/examples/${smart_contract_example}/.ink" ];
+      then
+        cp -R "${ink_directory}/examples/${smart_contract_example}/.ink" /safepkt-ink/examples/source && \
+        sed -i 's/"'"${smart_contract_example}"'"/"'"${package_name}"'"/g' /safepkt-ink/examples/source/.ink/abi_gen/Cargo.toml
+      fi
+      # only the contract module is renamed, identifiers merely containing the name of the template being left as is
+      sed -i 's/\bmod '"${smart_contract_example}"'\b/mod '"${package_name}"'/g' /safepkt-ink/examples/source/src/lib.rs
+    fi
 
     local panic_occurrences
 
-    if [ ! -z "${proptest_cases}" ] && [ -n "${proptest_cases}" ];
+    # exit code of cargo verify, returned once results have been printed
+    local verification_exit_code=0
+
+    # the backend of cargo verify is declared in the environment (VERIFY_BACKEND),
+    # proptest being otherwise implied by a number of cases
+    local backend
+    backend="${VERIFY_BACKEND}"
+    if [ -z "${backend}" ];
     then
-        export PROPTEST_CASES=${proptest_cases}
-        cargo verify --backend='proptest' --script=./commands.sh --tests -vvv || true
-    else
-        cargo verify --backend='klee' --script=./commands.sh --tests -vvvv 2> /safepkt-ink/examples/source/raw_err || true
+      if [ -n "${proptest_cases}" ];
+      then
+        backend='proptest'
+      else
+        backend='klee'
+      fi
+    fi
+
+    # verbosity of cargo verify, KLEE being more verbose by default
+    local verbosity
+    verbosity="${VERIFY_VERBOSITY}"
+    if [ -z "${verbosity}" ];
+    then
+      if [ "${backend}" = 'klee' ];
+      then
+        verbosity=4
+      else
+        verbosity=3
+      fi
     fi
 
+    local verbosity_flag=
+    if [ "${verbosity}" -gt 0 ];
+    then
+      verbosity_flag="-$(printf 'v%.0s' $(seq 1 "${verbosity}"))"
+    fi
+
+    case "${backend}" in
+      proptest)
+        export PROPTEST_CASES=${proptest_cases:-5}
+        cargo verify --backend='proptest' --script=./commands.sh --tests ${test_filter} ${verbosity_flag} || verification_exit_code=$?
+        ;;
+      seahorn)
+        cargo verify --backend='seahorn' --script=./commands.sh --tests ${test_filter} ${verbosity_flag} 2> /safepkt-ink/examples/source/raw_err || verification_exit_code=$?
+        ;;
+      klee)
+        local klee_flags=()
+        # stop exploring paths before the time budget of the step is exceeded
+        # so that KLEE can write partial statistics
+        if [ -n "${KLEE_MAX_TIME}" ];
+        then
+          klee_flags+=("--max-time=${KLEE_MAX_TIME}s")
+        fi
+        if [ -n "${KLEE_SEARCH}" ];
+        then
+          klee_flags+=("--search=${KLEE_SEARCH}")
+        fi
+        if [ -n "${KLEE_MAX_MEMORY}" ];
+        then
+          klee_flags+=("--max-memory=${KLEE_MAX_MEMORY}")
+        fi
+
+        local backend_flags=
+        if [ ${#klee_flags[@]} -gt 0 ];
+        then
+          backend_flags="--backend-flags=$(IFS=,; echo "${klee_flags[*]}")"
+        fi
+
+        cargo verify --backend='klee' ${backend_flags} --script=./commands.sh --tests ${test_filter} ${verbosity_flag} 2> /safepkt-ink/examples/source/raw_err || verification_exit_code=$?
+        ;;
+      *)
+        echo "Unknown backend \"${backend}\"" >&2
+        return 1
+        ;;
+    esac
+
     if [ -z "${quiet}" ];
       then
       echo '__BEGIN_EXPECTED_PANICS__'
@@ -82,7 +239,11 @@ function verify() {
         continue;
       fi
 
-      \grep 'KLEE:' "${entry_point}/info" | sed -E 's/^/\t/g'
+      \grep -E '^(KLEE:|Elapsed:)' "${entry_point}/info" | sed -E 's/^/\t/g'
+
+      echo -e '\t__BEGIN_KLEE_STATS__'
+      klee-stats --table-format=csv --print-more "${entry_point}" 2> /dev/null | sed -E 's/^/\t/g' || true
+      echo -e '\t__END_KLEE_STATS__'
     done
 
     if [ -z "${quiet}" ];
@@ -98,5 +259,11 @@ function verify() {
       cat /safepkt-ink/examples/source/raw_err
       echo '__END_RAW_STDERR__'
     fi
+
+    return "${verification_exit_code}"
 }
-verify "${1}" "${2}" "${3}" "${4}" "${5}"
+# functions can be sourced without verifying anything
+if [ "${BASH_SOURCE[0]}" = "${0}" ];
+then
+  verify "${1}" "${2}" "${3}" "${4}" "${5}"
+fi
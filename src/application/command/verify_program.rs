@@ -2,23 +2,35 @@ use crate::domain;
 use crate::infra;
 use anyhow::Result;
 use clap::{App, Arg, ArgMatches};
+use color_eyre::eyre::eyre;
 use color_eyre::Report;
 use domain::program_verification::*;
+use domain::value_object::{Interruption, LogCursor};
 use infra::display;
 use infra::file_system::save_content_in_file_system;
+use infra::program_verification::format_run_id;
+use infra::report::{html, parser};
+use infra::scaffold;
 use infra::PROGRAM_FUZZING;
 use infra::PROGRAM_VERIFICATION;
 use std::fs;
 use std::path::Path;
-use std::{thread, time};
+use std::time;
 
 pub const ARGUMENT_SOURCE: &str = "source";
 pub const OPTION_WITH_FUZZING: &str = "fuzz";
+pub const OPTION_HTML_REPORT: &str = "html-report";
+pub const OPTION_TIMEOUT: &str = "timeout";
+pub const OPTION_FRESH: &str = "fresh";
+pub const OPTION_BACKEND: &str = "backend";
+pub const OPTION_KIND: &str = "kind";
+pub const OPTION_INK_VERSION: &str = "ink-version";
+pub const OPTION_TEMPLATE: &str = "template";
 
 pub const SUBCOMMAND_NAME_VERIFY_PROGRAM: &str = "verify_program";
 
-pub fn verify_program_subcommand(version: &str) -> App {
-    return App::new(SUBCOMMAND_NAME_VERIFY_PROGRAM)
+pub fn verify_program_subcommand(version: &str) -> App<'_> {
+    App::new(SUBCOMMAND_NAME_VERIFY_PROGRAM)
         .about("Verify program")
         .version(version)
         .arg(
@@ -34,44 +46,143 @@ pub fn verify_program_subcommand(version: &str) -> App {
                 .long(ARGUMENT_SOURCE)
                 .about("Path to rust-based smart contract (e.g. https://github.com/paritytech/ink/blob/v2.1.0/examples/erc721/src/lib.rs)")
                 .takes_value(true),
-        );
+        )
+        .arg(
+            Arg::new(OPTION_HTML_REPORT)
+                .long(OPTION_HTML_REPORT)
+                .about("Path to a file where a self-contained HTML report is to be written")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new(OPTION_TIMEOUT)
+                .short('t')
+                .long(OPTION_TIMEOUT)
+                .about("Time budget of the verification in seconds (capped by MAX_STEP_TIMEOUT)")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new(OPTION_FRESH)
+                .long(OPTION_FRESH)
+                .about("Verify program even when results of a previous verification have been cached")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::new(OPTION_BACKEND)
+                .short('b')
+                .long(OPTION_BACKEND)
+                .about("Backend of cargo verify (klee, proptest or seahorn) replacing the default one of the step")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new(OPTION_KIND)
+                .long(OPTION_KIND)
+                .about("Kind of project (ink_contract, library or binary, detected from the source by default)")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new(OPTION_INK_VERSION)
+                .long(OPTION_INK_VERSION)
+                .about("Version of ink! the smart contract depends on (2.1.0 or 3.0.0-rc4, detected from its source by default)")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new(OPTION_TEMPLATE)
+                .long(OPTION_TEMPLATE)
+                .about("Example of ink! the project is scaffolded from (e.g. multisig_plain, the first example of the ink! version by default)")
+                .takes_value(true),
+        )
 }
 
-async fn verify_program(source_path: &str, optional_fuzzing: Option<bool>) -> Result<(), Report> {
+async fn verify_program(
+    source_path: &str,
+    optional_fuzzing: Option<bool>,
+    html_report_path: Option<&str>,
+    timeout: Option<u64>,
+    fresh_run: bool,
+    backend: Option<&str>,
+    settings: (Option<&str>, Option<&str>, Option<&str>),
+) -> Result<(), Report> {
     let content = fs::read_to_string(source_path)?;
 
-    let (_, project_id) = save_content_in_file_system(base64::encode(content).as_bytes())
+    let (_, project_id) = save_content_in_file_system(base64::encode(&content).as_bytes())
         .expect("Can not save rust-based source in the file system.");
 
+    let (kind, ink_version, template) = settings;
+    let settings =
+        scaffold::select_project_settings(project_id.as_str(), kind, ink_version, template)?;
+
     let with_fuzzing = optional_fuzzing.unwrap();
-    let mut step: String = String::from(PROGRAM_VERIFICATION).clone();
+    let mut step: String = String::from(PROGRAM_VERIFICATION);
     if with_fuzzing {
-        step = String::from(PROGRAM_FUZZING).clone()
+        step = String::from(PROGRAM_FUZZING)
     }
 
-    let target = VerificationTarget::new(step.as_str(), project_id.as_str());
+    let target = VerificationTarget::new(step.as_str(), project_id.as_str())
+        .with_timeout(timeout)
+        .with_fresh_run(fresh_run)
+        .with_backend(backend);
     let verification = SmartContractVerification::new(target);
 
-    verification.run_step().await?;
+    let started = verification.run_step().await?;
+    if started.get("cached").map(String::as_str) == Some("true") {
+        display::output::print(
+            "Results of a previous verification have been found in cache (run with --{} to verify again).",
+            vec![OPTION_FRESH],
+            None,
+        );
+    }
 
     display::output::print("{}", vec![""], None);
 
-    loop {
+    let mut cursor: Option<LogCursor> = None;
+    let mut raw_log = String::new();
+
+    let interruption = loop {
         let progress = verification.step_progress().await?;
+        let status = progress.get("raw_status").unwrap();
+
+        // Display only logs written since the previous poll
+        let logs = verification.step_report(cursor.as_ref()).await?;
+        raw_log.push_str(logs.get("raw_log").map(String::as_str).unwrap_or_default());
+        if let Some(next_cursor) = logs.get("cursor") {
+            cursor = Some(LogCursor::parse(next_cursor.as_str())?);
+        }
 
-        if progress.get("raw_status").unwrap() != "running" {
+        if status != "running" {
             display::output::print("{}", vec![""], None);
 
-            break;
-        } else {
-            display::output::print("{}", vec!["."], Some(true));
+            let interruption = Interruption::from_status(status);
+            if interruption.is_some() {
+                display::output::eprint("Verification ended with status {}", vec![status], None);
+            }
+            if let Some(outcome) = progress.get("outcome") {
+                display::output::print("Outcome: {}", vec![outcome.as_str()], None);
+            }
+
+            break interruption;
         }
 
         let duration = time::Duration::from_millis(2000);
-        thread::sleep(duration);
-    }
+        tokio::time::sleep(duration).await;
+    };
+
+    if let Some(html_report_path) = html_report_path {
+        let run_id = format_run_id(step.as_str(), project_id.as_str());
+        let mut report = parser::parse_report(
+            run_id.as_str(),
+            step.as_str(),
+            project_id.as_str(),
+            raw_log.as_str(),
+            settings.kind().entry_point(),
+        );
+        report.set_interruption(interruption);
 
-    verification.step_report().await?;
+        fs::write(
+            html_report_path,
+            html::render_report(&report, content.as_str(), settings.kind().entry_point()),
+        )?;
+        display::output::print("HTML report written to {}", vec![html_report_path], None);
+    }
 
     Ok(())
 }
@@ -86,13 +197,38 @@ pub async fn run_verify_program_subcommand(source_path_matches: &ArgMatches) ->
     }
 
     let with_fuzzing = source_path_matches.is_present(OPTION_WITH_FUZZING);
+    let timeout = source_path_matches
+        .value_of(OPTION_TIMEOUT)
+        .map(|timeout| {
+            timeout.parse::<u64>().map_err(|_| {
+                eyre!(
+                    "Invalid --{} \"{}\" (expecting seconds)",
+                    OPTION_TIMEOUT,
+                    timeout
+                )
+            })
+        })
+        .transpose()?;
 
     if let Some(source_path) = source_path_matches.value_of(ARGUMENT_SOURCE) {
         let source = Path::new(source_path);
         if !source.exists() || source.is_dir() {
             display::output::eprint("Invalid path to rust-based smart contract.", vec![], None);
         } else {
-            verify_program(source_path, Some(with_fuzzing)).await?;
+            verify_program(
+                source_path,
+                Some(with_fuzzing),
+                source_path_matches.value_of(OPTION_HTML_REPORT),
+                timeout,
+                source_path_matches.is_present(OPTION_FRESH),
+                source_path_matches.value_of(OPTION_BACKEND),
+                (
+                    source_path_matches.value_of(OPTION_KIND),
+                    source_path_matches.value_of(OPTION_INK_VERSION),
+                    source_path_matches.value_of(OPTION_TEMPLATE),
+                ),
+            )
+            .await?;
         }
     }
 
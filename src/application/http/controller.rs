@@ -1,10 +1,37 @@
+use crate::infra;
 use hyper::header::{CONTENT_TYPE, X_CONTENT_TYPE_OPTIONS};
 use hyper::{Body, Response, StatusCode};
+use infra::{default_container_api_client_factory, ContainerAPIClientFactory};
+use routerify::ext::RequestExt;
+use std::collections::HashMap;
 use std::convert::Infallible;
+use std::str;
+use url::form_urlencoded;
 
+pub mod garbage_collection;
+pub mod metrics;
+pub mod pipeline;
+pub mod report;
 pub mod source;
 pub mod verification_step;
 
+/// Get the factory of container API clients shared by the router,
+/// or fall back to the runtime selected by configuration.
+fn container_api_client_factory<T: RequestExt>(req: &T) -> ContainerAPIClientFactory {
+    req.data::<ContainerAPIClientFactory>()
+        .cloned()
+        .unwrap_or_else(default_container_api_client_factory)
+}
+
+/// Get the value of a parameter of the query string of a request
+fn query_param<T>(req: &hyper::Request<T>, name: &str) -> Option<String> {
+    req.uri().query().and_then(|query| {
+        form_urlencoded::parse(query.as_bytes())
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.into_owned())
+    })
+}
+
 fn build_response(body: Vec<u8>, status_code: StatusCode) -> Result<Response<Body>, Infallible> {
     Ok(Response::builder()
         .header(CONTENT_TYPE, "application/json")
@@ -14,6 +41,59 @@ fn build_response(body: Vec<u8>, status_code: StatusCode) -> Result<Response<Bod
         .unwrap())
 }
 
+/// Respond with an error message (e.g. an invalid request or a missing resource)
+fn error_response(message: String, status_code: StatusCode) -> Result<Response<Body>, Infallible> {
+    let mut error = HashMap::<String, String>::new();
+    error.insert("error".to_string(), message);
+
+    build_response(serde_json::to_vec(&error).unwrap(), status_code)
+}
+
+/// Respond with an error of the backend itself (e.g. an invalid step registry)
+fn server_error_response(message: String) -> Result<Response<Body>, Infallible> {
+    error_response(message, StatusCode::INTERNAL_SERVER_ERROR)
+}
+
 fn ok_response(body: Vec<u8>, status_code: StatusCode) -> Result<Response<Body>, Infallible> {
     build_response(body, status_code)
 }
+
+fn html_response(body: String, status_code: StatusCode) -> Result<Response<Body>, Infallible> {
+    Ok(Response::builder()
+        .header(CONTENT_TYPE, "text/html; charset=utf-8")
+        .header(X_CONTENT_TYPE_OPTIONS, "nosniff")
+        .status(status_code)
+        .body(Body::from(body))
+        .unwrap())
+}
+
+/// Respond with metrics in the Prometheus text format
+fn text_response(body: String, status_code: StatusCode) -> Result<Response<Body>, Infallible> {
+    Ok(Response::builder()
+        .header(CONTENT_TYPE, "text/plain; version=0.0.4; charset=utf-8")
+        .header(X_CONTENT_TYPE_OPTIONS, "nosniff")
+        .status(status_code)
+        .body(Body::from(body))
+        .unwrap())
+}
+
+#[test]
+fn it_gets_decoded_parameters_of_query_strings() {
+    let request = hyper::Request::builder()
+        .uri(
+            "/program-verification/47a9690570/report?since=time%3A2021-11-02T10%3A12%3A45.123456789Z&tail&name=safe+transfer",
+        )
+        .body(())
+        .unwrap();
+
+    assert_eq!(
+        Some("time:2021-11-02T10:12:45.123456789Z".to_string()),
+        query_param(&request, "since")
+    );
+    assert_eq!(
+        Some("safe transfer".to_string()),
+        query_param(&request, "name")
+    );
+    assert_eq!(Some(String::new()), query_param(&request, "tail"));
+    assert_eq!(None, query_param(&request, "cursor"));
+}
@@ -0,0 +1,40 @@
+use crate::app;
+use crate::infra;
+use app::controller;
+use hyper::{Body, Request, Response, StatusCode};
+use infra::garbage_collection::{GarbageCollector, RetentionPolicy};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use tracing::error;
+
+/// List what garbage collection would remove, without removing anything
+pub async fn list_garbage(req: Request<Body>) -> Result<Response<Body>, Infallible> {
+    let factory = controller::container_api_client_factory(&req);
+
+    let removals = match RetentionPolicy::from_env()
+        .and_then(|policy| GarbageCollector::new(factory, policy))
+    {
+        Ok(collector) => collector.collect_now(true).await,
+        Err(report) => Err(report),
+    };
+
+    match removals {
+        Ok(removals) => {
+            let mut body = HashMap::new();
+            body.insert("removals", removals);
+
+            controller::ok_response(serde_json::to_vec(&body).unwrap(), StatusCode::OK)
+        }
+        Err(report) => {
+            error!("{}", report);
+
+            let mut error = HashMap::<String, String>::new();
+            error.insert("error".to_string(), report.to_string());
+
+            controller::build_response(
+                serde_json::to_vec(&error).unwrap(),
+                StatusCode::INTERNAL_SERVER_ERROR,
+            )
+        }
+    }
+}
@@ -0,0 +1,19 @@
+use crate::app;
+use crate::domain;
+use crate::infra;
+use app::controller;
+use domain::value_object::KLEE_PARSER;
+use hyper::{Body, Request, Response, StatusCode};
+use infra::program_verification;
+use infra::report::metrics;
+use std::convert::Infallible;
+
+/// Expose the KLEE statistics of the runs of this backend instance to Prometheus
+pub async fn get_metrics(req: Request<Body>) -> Result<Response<Body>, Infallible> {
+    let factory = controller::container_api_client_factory(&req);
+
+    match program_verification::get_run_reports(&factory, KLEE_PARSER).await {
+        Ok(reports) => controller::text_response(metrics::render_metrics(&reports), StatusCode::OK),
+        Err(report) => controller::server_error_response(report.to_string()),
+    }
+}
@@ -0,0 +1,92 @@
+use crate::app;
+use crate::domain;
+use crate::infra;
+use app::controller;
+use domain::value_object::{Pipeline, PipelineDefinition};
+use domain::verification_runtime::{VerificationRuntime, VerificationStepRunner};
+use hyper::{body, Body, Request, Response, StatusCode};
+use infra::pipeline;
+use routerify::prelude::*;
+use std::convert::Infallible;
+
+fn missing_pipeline_response(project_id: &str) -> Result<Response<Body>, Infallible> {
+    controller::error_response(
+        format!(
+            "There is no pipeline for project having id \"{}\"",
+            project_id
+        ),
+        StatusCode::NOT_FOUND,
+    )
+}
+
+pub async fn start_pipeline(req: Request<Body>) -> Result<Response<Body>, Infallible> {
+    let factory = controller::container_api_client_factory(&req);
+    let (head, request_body) = req.into_parts();
+    let project_id = head.param("projectId").unwrap();
+
+    let body_bytes = body::to_bytes(request_body).await.unwrap().to_vec();
+    let definition: PipelineDefinition = match serde_json::from_slice(body_bytes.as_slice()) {
+        Ok(definition) => definition,
+        Err(error) => {
+            return controller::error_response(
+                format!("Invalid pipeline: {}", error),
+                StatusCode::BAD_REQUEST,
+            )
+        }
+    };
+
+    let steps_names = match VerificationRuntime::steps_names() {
+        Ok(steps_names) => steps_names,
+        Err(report) => return controller::server_error_response(report.to_string()),
+    };
+
+    let pipeline = match Pipeline::new(project_id.clone(), definition, &steps_names) {
+        Ok(pipeline) => pipeline,
+        Err(message) => return controller::error_response(message, StatusCode::BAD_REQUEST),
+    };
+
+    match pipeline::start_pipeline(factory, &pipeline, pipeline::DEFAULT_POLL_INTERVAL) {
+        Ok(progress) => {
+            controller::ok_response(serde_json::to_vec(&progress).unwrap(), StatusCode::OK)
+        }
+        Err(report) => controller::error_response(report.to_string(), StatusCode::BAD_REQUEST),
+    }
+}
+
+pub async fn get_pipeline_progress(req: Request<Body>) -> Result<Response<Body>, Infallible> {
+    let project_id = req.param("projectId").unwrap();
+
+    match pipeline::get_pipeline_progress(project_id) {
+        Ok(Some(progress)) => {
+            controller::ok_response(serde_json::to_vec(&progress).unwrap(), StatusCode::OK)
+        }
+        Ok(None) => missing_pipeline_response(project_id),
+        Err(report) => controller::server_error_response(report.to_string()),
+    }
+}
+
+pub async fn get_pipeline_report(req: Request<Body>) -> Result<Response<Body>, Infallible> {
+    let factory = controller::container_api_client_factory(&req);
+    let project_id = req.param("projectId").unwrap();
+
+    match pipeline::get_pipeline_report(&factory, project_id).await {
+        Ok(Some(report)) => {
+            controller::ok_response(serde_json::to_vec(&report).unwrap(), StatusCode::OK)
+        }
+        Ok(None) => missing_pipeline_response(project_id),
+        Err(report) => controller::server_error_response(report.to_string()),
+    }
+}
+
+pub async fn stop_pipeline(req: Request<Body>) -> Result<Response<Body>, Infallible> {
+    let factory = controller::container_api_client_factory(&req);
+    let project_id = req.param("projectId").unwrap();
+
+    match pipeline::stop_pipeline(&factory, project_id).await {
+        Ok(Some(progress)) => {
+            controller::ok_response(serde_json::to_vec(&progress).unwrap(), StatusCode::OK)
+        }
+        Ok(None) => missing_pipeline_response(project_id),
+        Err(report) => controller::server_error_response(report.to_string()),
+    }
+}
@@ -0,0 +1,52 @@
+use crate::app;
+use crate::infra;
+use app::controller;
+use hyper::{Body, Request, Response, StatusCode};
+use infra::program_verification;
+use infra::report::html;
+use infra::scaffold;
+use routerify::prelude::*;
+use std::convert::Infallible;
+use tracing::error;
+
+pub async fn get_run_report(req: Request<Body>) -> Result<Response<Body>, Infallible> {
+    let factory = controller::container_api_client_factory(&req);
+    let run_id = req.param("runId").unwrap();
+
+    match program_verification::get_run_report(&factory, run_id).await {
+        Ok(report) => controller::ok_response(serde_json::to_vec(&report).unwrap(), StatusCode::OK),
+        Err(report) => controller::error_response(report.to_string(), StatusCode::NOT_FOUND),
+    }
+}
+
+pub async fn get_run_report_html(req: Request<Body>) -> Result<Response<Body>, Infallible> {
+    let factory = controller::container_api_client_factory(&req);
+    let run_id = req.param("runId").unwrap();
+
+    let report = match program_verification::get_run_report(&factory, run_id).await {
+        Ok(report) => report,
+        Err(report) => {
+            return controller::error_response(report.to_string(), StatusCode::NOT_FOUND)
+        }
+    };
+
+    let settings = match scaffold::find_project_settings(report.project_id()) {
+        Ok(settings) => settings,
+        Err(report) => {
+            return controller::error_response(report.to_string(), StatusCode::NOT_FOUND)
+        }
+    };
+
+    let source = match scaffold::find_decoded_source_by_project_id(report.project_id()) {
+        Ok(source) => source,
+        Err(report) => {
+            error!("{}", report.to_string());
+            String::new()
+        }
+    };
+
+    controller::html_response(
+        html::render_report(&report, source.as_str(), settings.kind().entry_point()),
+        StatusCode::OK,
+    )
+}
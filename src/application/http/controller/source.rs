@@ -3,6 +3,7 @@ use crate::infra;
 use app::controller;
 use hyper::{body, Body, Request, Response, StatusCode};
 use infra::file_system::save_content_in_file_system;
+use infra::scaffold;
 use infra::serializer;
 use std::collections::HashMap;
 use std::convert::Infallible;
@@ -12,9 +13,7 @@ pub async fn save_source(req: Request<Body>) -> Result<Response<Body>, Infallibl
     let (_, request_body) = req.into_parts();
     let body_bytes = &body::to_bytes(request_body).await.unwrap().to_vec()[..];
 
-    let json = str::from_utf8(&body_bytes[..].to_vec())
-        .unwrap()
-        .to_string();
+    let json = str::from_utf8(body_bytes).unwrap().to_string();
     let deserialized_json = serializer::deserialize_source(json.as_str())
         .expect("Can not deserialize request body (expecting valid JSON).");
     let source = deserialized_json.source();
@@ -22,8 +21,29 @@ pub async fn save_source(req: Request<Body>) -> Result<Response<Body>, Infallibl
     let (_, project_id) =
         save_content_in_file_system(source).expect("Can not save content in the file system.");
 
+    // The kind of project (and its ink! template) is chosen when uploading its source or detected from it
+    let settings = match scaffold::select_project_settings(
+        project_id.as_str(),
+        deserialized_json.kind(),
+        deserialized_json.ink_version(),
+        deserialized_json.template(),
+    ) {
+        Ok(settings) => settings,
+        Err(report) => {
+            return controller::error_response(report.to_string(), StatusCode::BAD_REQUEST)
+        }
+    };
+
     let mut response = HashMap::<String, String>::new();
     response.insert("project_id".to_string(), project_id);
+    response.insert("kind".to_string(), settings.kind().as_str().to_string());
+    if let Some(ink_template) = settings.ink_template() {
+        response.insert(
+            "ink_version".to_string(),
+            ink_template.ink_version().to_string(),
+        );
+        response.insert("template".to_string(), ink_template.name().to_string());
+    }
 
     controller::build_response(serde_json::to_vec(&response).unwrap(), StatusCode::OK)
 }
@@ -3,19 +3,49 @@ use crate::domain;
 use crate::infra;
 use anyhow::Result;
 use app::controller;
-use domain::value_object::{Flags, Step};
+use domain::value_object::{
+    BackendSelection, Flags, LogCursor, StepInVerificationPlan, VerificationOptions,
+};
 use domain::verification_runtime::{VerificationRuntime, VerificationStepRunner};
 use hyper::{body, Body, Request, Response, StatusCode};
 use infra::program_verification;
 use infra::serializer;
+use infra::time_budget;
 use routerify::prelude::*;
 use std::collections::HashMap;
 use std::convert::Infallible;
-use std::str;
 use tracing::error;
 
+/// Select the backend requested for a step,
+/// options passed without backend applying to the backend the step runs with by default.
+fn select_backend(
+    step_in_verification_plan: &StepInVerificationPlan<'_>,
+    backend: Option<&str>,
+    options: &VerificationOptions,
+) -> Result<Option<BackendSelection>, String> {
+    if backend.is_none() && options.is_empty() {
+        return Ok(None);
+    }
+
+    let step = step_in_verification_plan.step();
+    let default_backend = match step.definition().backend() {
+        Some(default_backend) => default_backend,
+        None => {
+            return Err(format!(
+                "Step \"{}\" does not run cargo verify (no backend can be selected)",
+                step.name()
+            ))
+        }
+    };
+
+    BackendSelection::new(backend.unwrap_or(default_backend), options).map(Some)
+}
+
 pub async fn get_steps(_: Request<Body>) -> Result<Response<Body>, Infallible> {
-    let steps = VerificationRuntime::steps_names();
+    let steps = match VerificationRuntime::steps_names() {
+        Ok(steps) => steps,
+        Err(report) => return controller::server_error_response(report.to_string()),
+    };
     let mut steps_names = HashMap::<String, Vec<&str>>::new();
     steps_names.insert("steps".to_string(), steps);
 
@@ -23,119 +53,205 @@ pub async fn get_steps(_: Request<Body>) -> Result<Response<Body>, Infallible> {
 }
 
 pub async fn start_running_step(req: Request<Body>) -> Result<Response<Body>, Infallible> {
+    let factory = controller::container_api_client_factory(&req);
     let (head, request_body) = req.into_parts();
 
     let step_param = head.param("stepName").unwrap();
     let project_id = head.param("projectId").unwrap();
 
     let body_bytes = &body::to_bytes(request_body).await.unwrap().to_vec()[..];
-    let json = str::from_utf8(&body_bytes[..].to_vec())
-        .unwrap()
-        .to_string();
-
-    let steps: HashMap<String, Step>;
-    let deserialized_json: Flags;
-
-    if json.len() > 0 {
-        deserialized_json = serializer::deserialize_flags(json.as_str()).unwrap();
-        let flags = deserialized_json.flags();
-        let additional_flags = str::from_utf8(flags).unwrap();
-        steps = VerificationRuntime::build_steps(Some(additional_flags));
+    let json = String::from_utf8_lossy(body_bytes).to_string();
+
+    let flags = if json.trim().is_empty() {
+        Flags::default()
     } else {
-        steps = VerificationRuntime::build_steps(None);
-    }
+        match serializer::deserialize_flags(json.as_str()) {
+            Ok(flags) => flags,
+            Err(error) => {
+                return controller::error_response(
+                    format!("Invalid request: {}", error),
+                    StatusCode::BAD_REQUEST,
+                )
+            }
+        }
+    };
+
+    let steps = match VerificationRuntime::build_steps() {
+        Ok(steps) => steps,
+        Err(report) => return controller::server_error_response(report.to_string()),
+    };
     let step_in_verification_plan =
-        program_verification::which_step(&steps, step_param.clone(), project_id.clone());
+        match program_verification::which_step(&steps, step_param.clone(), project_id.clone()) {
+            Ok(step_in_verification_plan) => step_in_verification_plan,
+            Err(report) => {
+                return controller::error_response(report.to_string(), StatusCode::NOT_FOUND)
+            }
+        };
 
-    let step = step_in_verification_plan.step();
-    let step = step.clone();
-    let step_name = step.name().to_string().clone();
+    let step_name = step_in_verification_plan.step().name().to_string();
+
+    let time_budget = match time_budget::get_time_budget(step_name.as_str(), flags.timeout()) {
+        Ok(time_budget) => time_budget,
+        Err(report) => {
+            return controller::error_response(report.to_string(), StatusCode::BAD_REQUEST)
+        }
+    };
+    let backend = match select_backend(&step_in_verification_plan, flags.backend(), flags.options())
+    {
+        Ok(backend) => backend,
+        Err(message) => return controller::error_response(message, StatusCode::BAD_REQUEST),
+    };
+    let step_in_verification_plan = step_in_verification_plan
+        .with_time_budget(time_budget)
+        .with_fresh_run(flags.fresh())
+        .with_backend(backend);
 
-    let runtime = VerificationRuntime::new(step_in_verification_plan, steps).unwrap();
+    let runtime = match VerificationRuntime::with_container_api_client_factory(
+        &factory,
+        step_in_verification_plan,
+        steps,
+    ) {
+        Ok(runtime) => runtime,
+        Err(report) => {
+            return controller::error_response(report.to_string(), StatusCode::BAD_REQUEST)
+        }
+    };
 
     match runtime.start_running().await {
-        Ok(result) => controller::ok_response(serde_json::to_vec(&result).unwrap(), StatusCode::OK),
+        Ok(result) => controller::ok_response(
+            serializer::serialize_step_message(&result).unwrap(),
+            StatusCode::OK,
+        ),
         Err(report) => {
             error!("{}", report.to_string());
 
-            let mut error = HashMap::<String, String>::new();
-
-            let error_message = format!(
-                "Could not run \"{}\" step for project having id \"{}\"",
-                step_name, project_id
-            );
-            error.insert("error".to_string(), error_message);
-
-            controller::build_response(serde_json::to_vec(&error).unwrap(), StatusCode::BAD_REQUEST)
+            controller::error_response(
+                format!(
+                    "Could not run \"{}\" step for project having id \"{}\"",
+                    step_name, project_id
+                ),
+                StatusCode::BAD_REQUEST,
+            )
         }
     }
 }
 
 pub async fn stop_running_step(req: Request<Body>) -> Result<Response<Body>, Infallible> {
+    let factory = controller::container_api_client_factory(&req);
     let step_param = req.param("stepName").unwrap().clone();
     let project_id = req.param("projectId").unwrap().clone();
 
-    let steps = VerificationRuntime::build_steps(None);
-    let step = program_verification::which_step(
+    let steps = match VerificationRuntime::build_steps() {
+        Ok(steps) => steps,
+        Err(report) => return controller::server_error_response(report.to_string()),
+    };
+    let step = match program_verification::which_step(
         &steps,
         program_verification::change_case(step_param),
-        project_id.clone(),
-    );
-    let runtime = VerificationRuntime::new(step, steps).unwrap();
-
-    match runtime.stop_running().await {
-        Ok(logs) => controller::ok_response(serde_json::to_vec(&logs).unwrap(), StatusCode::OK),
+        project_id,
+    ) {
+        Ok(step) => step,
         Err(report) => {
-            let mut error = HashMap::<String, String>::new();
-            error.insert("error".to_string(), report.to_string());
-
-            controller::build_response(serde_json::to_vec(&error).unwrap(), StatusCode::BAD_REQUEST)
+            return controller::error_response(report.to_string(), StatusCode::NOT_FOUND)
         }
+    };
+    let runtime =
+        match VerificationRuntime::with_container_api_client_factory(&factory, step, steps) {
+            Ok(runtime) => runtime,
+            Err(report) => {
+                return controller::error_response(report.to_string(), StatusCode::BAD_REQUEST)
+            }
+        };
+
+    match runtime.stop_running().await {
+        Ok(logs) => controller::ok_response(
+            serializer::serialize_step_message(&logs).unwrap(),
+            StatusCode::OK,
+        ),
+        Err(report) => controller::error_response(report.to_string(), StatusCode::BAD_REQUEST),
     }
 }
 
 pub async fn get_step_report(req: Request<Body>) -> Result<Response<Body>, Infallible> {
+    let factory = controller::container_api_client_factory(&req);
     let step_param = req.param("stepName").unwrap().clone();
     let project_id = req.param("projectId").unwrap().clone();
 
-    let steps = VerificationRuntime::build_steps(None);
-    let step = program_verification::which_step(
+    let steps = match VerificationRuntime::build_steps() {
+        Ok(steps) => steps,
+        Err(report) => return controller::server_error_response(report.to_string()),
+    };
+    let step = match program_verification::which_step(
         &steps,
         program_verification::change_case(step_param),
-        project_id.clone(),
-    );
-    let runtime = VerificationRuntime::new(step, steps).unwrap();
-
-    match runtime.get_report().await {
-        Ok(logs) => controller::ok_response(serde_json::to_vec(&logs).unwrap(), StatusCode::OK),
+        project_id,
+    ) {
+        Ok(step) => step,
         Err(report) => {
-            let mut error = HashMap::<String, String>::new();
-            error.insert("error".to_string(), report.to_string());
+            return controller::error_response(report.to_string(), StatusCode::NOT_FOUND)
+        }
+    };
+    let runtime =
+        match VerificationRuntime::with_container_api_client_factory(&factory, step, steps) {
+            Ok(runtime) => runtime,
+            Err(report) => {
+                return controller::error_response(report.to_string(), StatusCode::BAD_REQUEST)
+            }
+        };
 
-            controller::build_response(serde_json::to_vec(&error).unwrap(), StatusCode::BAD_REQUEST)
+    // Only logs written after the cursor are returned, along with the next cursor
+    let cursor = match controller::query_param(&req, "since")
+        .filter(|cursor| !cursor.is_empty())
+        .map(|cursor| LogCursor::parse(cursor.as_str()))
+        .transpose()
+    {
+        Ok(cursor) => cursor,
+        Err(report) => {
+            return controller::error_response(report.to_string(), StatusCode::BAD_REQUEST)
         }
+    };
+
+    match runtime.get_report(cursor.as_ref()).await {
+        Ok(logs) => controller::ok_response(
+            serializer::serialize_step_message(&logs).unwrap(),
+            StatusCode::OK,
+        ),
+        Err(report) => controller::error_response(report.to_string(), StatusCode::BAD_REQUEST),
     }
 }
 
 pub async fn get_step_progress(req: Request<Body>) -> Result<Response<Body>, Infallible> {
+    let factory = controller::container_api_client_factory(&req);
     let step_param = req.param("stepName").unwrap().clone();
     let project_id = req.param("projectId").unwrap().clone();
 
-    let steps = VerificationRuntime::build_steps(None);
-    let step = program_verification::which_step(
+    let steps = match VerificationRuntime::build_steps() {
+        Ok(steps) => steps,
+        Err(report) => return controller::server_error_response(report.to_string()),
+    };
+    let step = match program_verification::which_step(
         &steps,
         program_verification::change_case(step_param),
-        project_id.clone(),
-    );
-    let runtime = VerificationRuntime::new(step, steps).unwrap();
-
-    match runtime.get_progress().await {
-        Ok(status) => controller::ok_response(serde_json::to_vec(&status).unwrap(), StatusCode::OK),
+        project_id,
+    ) {
+        Ok(step) => step,
         Err(report) => {
-            let mut error = HashMap::<String, String>::new();
-            error.insert("error".to_string(), report.to_string());
-
-            controller::build_response(serde_json::to_vec(&error).unwrap(), StatusCode::BAD_REQUEST)
+            return controller::error_response(report.to_string(), StatusCode::NOT_FOUND)
         }
+    };
+    let runtime =
+        match VerificationRuntime::with_container_api_client_factory(&factory, step, steps) {
+            Ok(runtime) => runtime,
+            Err(report) => {
+                return controller::error_response(report.to_string(), StatusCode::BAD_REQUEST)
+            }
+        };
+
+    match runtime.get_progress().await {
+        Ok(status) => controller::ok_response(
+            serializer::serialize_step_message(&status).unwrap(),
+            StatusCode::OK,
+        ),
+        Err(report) => controller::error_response(report.to_string(), StatusCode::BAD_REQUEST),
     }
 }
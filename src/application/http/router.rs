@@ -1,7 +1,9 @@
 use crate::app;
+use crate::infra;
 use app::controller;
 use app::middleware;
 use hyper::{Body, Response, StatusCode};
+use infra::{default_container_api_client_factory, ContainerAPIClientFactory};
 use routerify::{Middleware, RequestInfo, Result, Router, RouterService};
 use routerify_cors::enable_cors_all;
 use std::convert::Infallible;
@@ -11,16 +13,43 @@ async fn error_handler(err: routerify::RouteError, _: RequestInfo) -> Response<B
     error!("Routing error: {}", err);
     Response::builder()
         .status(StatusCode::INTERNAL_SERVER_ERROR)
-        .body(Body::from(format!("Sorry, something went wrong.")))
+        .body(Body::from("Sorry, something went wrong."))
         .unwrap()
 }
 
-pub fn new_router() -> Result<RouterService<Body, Infallible>> {
-    let router = Router::builder()
+/// Build a router sharing a factory of container API clients with controllers
+pub fn build_router(
+    container_api_client_factory: ContainerAPIClientFactory,
+) -> Result<Router<Body, Infallible>> {
+    Router::builder()
+        .data(container_api_client_factory)
         .middleware(Middleware::pre(middleware::logger::log_handler))
         .middleware(enable_cors_all())
         .post("/source", controller::source::save_source)
         .get("/steps", controller::verification_step::get_steps)
+        .get("/metrics", controller::metrics::get_metrics)
+        .get(
+            "/garbage-collection",
+            controller::garbage_collection::list_garbage,
+        )
+        .post(
+            "/pipelines/:projectId",
+            controller::pipeline::start_pipeline,
+        )
+        .get(
+            "/pipelines/:projectId/progress",
+            controller::pipeline::get_pipeline_progress,
+        )
+        .get(
+            "/pipelines/:projectId/report",
+            controller::pipeline::get_pipeline_report,
+        )
+        .delete("/pipelines/:projectId", controller::pipeline::stop_pipeline)
+        .get("/runs/:runId/report", controller::report::get_run_report)
+        .get(
+            "/runs/:runId/report.html",
+            controller::report::get_run_report_html,
+        )
         .post(
             "/:stepName/:projectId",
             controller::verification_step::start_running_step,
@@ -39,7 +68,470 @@ pub fn new_router() -> Result<RouterService<Body, Infallible>> {
         )
         .err_handler_with_info(error_handler)
         .build()
-        .unwrap();
+}
+
+pub fn new_router() -> Result<RouterService<Body, Infallible>> {
+    let router = build_router(default_container_api_client_factory())?;
 
     RouterService::new(router)
 }
+
+#[cfg(test)]
+async fn send_request(
+    router: Router<Body, Infallible>,
+    method: hyper::Method,
+    uri: &str,
+) -> (StatusCode, String, String) {
+    send_request_with_body(router, method, uri, Body::empty()).await
+}
+
+#[cfg(test)]
+async fn send_request_with_body(
+    router: Router<Body, Infallible>,
+    method: hyper::Method,
+    uri: &str,
+    body: Body,
+) -> (StatusCode, String, String) {
+    use hyper::header::CONTENT_TYPE;
+    use hyper::service::Service;
+    use hyper::Request;
+    use routerify::RequestServiceBuilder;
+
+    let mut service = RequestServiceBuilder::new(router)
+        .unwrap()
+        .build("127.0.0.1:3000".parse().unwrap());
+
+    let request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .body(body)
+        .unwrap();
+    let response = service.call(request).await.unwrap();
+
+    let status = response.status();
+    let content_type = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .map(|c| c.to_str().unwrap().to_string())
+        .unwrap_or_default();
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+
+    (
+        status,
+        content_type,
+        String::from_utf8(body.to_vec()).unwrap(),
+    )
+}
+
+#[tokio::test]
+async fn it_runs_a_step_through_controllers() {
+    use crate::infra::PROGRAM_VERIFICATION;
+    use crate::test;
+    use hyper::Method;
+
+    let project = test::InMemoryProject::upload();
+    let project_id = &project.project_id;
+    let run_id = project.run_id(PROGRAM_VERIFICATION);
+
+    let client = &project.client;
+    client
+        .script_statuses(run_id.as_str(), vec!["running", "exited"])
+        .script_logs(
+            run_id.as_str(),
+            "Tests results for \"safe_overflow\"\n\tKLEE: ERROR: src/lib.rs:1: abort failure\n",
+        );
+
+    let step_uri = format!("/program-verification/{}", project_id);
+
+    let (status, _, body) = send_request(
+        build_router(client.factory()).unwrap(),
+        Method::POST,
+        step_uri.as_str(),
+    )
+    .await;
+    assert_eq!(StatusCode::OK, status);
+    assert!(body.contains(run_id.as_str()));
+    assert!(body.contains("\"backend\":\"klee\""));
+
+    let progress_uri = format!("{}/progress", step_uri);
+    for expected_status in &["running", "exited"] {
+        let (status, _, body) = send_request(
+            build_router(client.factory()).unwrap(),
+            Method::GET,
+            progress_uri.as_str(),
+        )
+        .await;
+        assert_eq!(StatusCode::OK, status);
+        assert!(body.contains(format!("\"raw_status\":\"{}\"", expected_status).as_str()));
+    }
+
+    let (status, _, body) = send_request(
+        build_router(client.factory()).unwrap(),
+        Method::GET,
+        format!("{}/report", step_uri).as_str(),
+    )
+    .await;
+    assert_eq!(StatusCode::OK, status);
+    assert!(body.contains("KLEE: ERROR"));
+
+    let (status, _, body) = send_request(
+        build_router(client.factory()).unwrap(),
+        Method::GET,
+        format!("/runs/{}/report", run_id).as_str(),
+    )
+    .await;
+    assert_eq!(StatusCode::OK, status);
+    assert!(body.contains("\"verdict\":\"failed\""));
+
+    let (status, content_type, body) = send_request(
+        build_router(client.factory()).unwrap(),
+        Method::GET,
+        format!("/runs/{}/report.html", run_id).as_str(),
+    )
+    .await;
+    assert_eq!(StatusCode::OK, status);
+    assert!(content_type.starts_with("text/html"));
+    assert!(body.contains("<li id=\"L1\" class=\"failing\">"));
+
+    let (status, _, _) = send_request(
+        build_router(client.factory()).unwrap(),
+        Method::DELETE,
+        step_uri.as_str(),
+    )
+    .await;
+    assert_eq!(StatusCode::OK, status);
+}
+
+#[tokio::test]
+async fn it_responds_with_errors_for_missing_containers() {
+    use crate::infra::verification_runtime::in_memory::InMemoryContainerAPIClient;
+    use hyper::Method;
+
+    let client = InMemoryContainerAPIClient::new();
+
+    for (method, uri) in [
+        (Method::GET, "/program-verification/47a9690570/progress"),
+        (Method::GET, "/program-verification/47a9690570/report"),
+        (Method::DELETE, "/program-verification/47a9690570"),
+    ] {
+        let (status, _, body) =
+            send_request(build_router(client.factory()).unwrap(), method, uri).await;
+
+        assert_eq!(StatusCode::BAD_REQUEST, status);
+        assert!(body.contains("There is no container having name"));
+    }
+
+    let (status, _, _) = send_request(
+        build_router(client.factory()).unwrap(),
+        Method::GET,
+        "/runs/47a9690570/report.html",
+    )
+    .await;
+    assert_eq!(StatusCode::NOT_FOUND, status);
+}
+
+#[tokio::test]
+async fn it_rejects_invalid_backends() {
+    use crate::infra::verification_runtime::in_memory::InMemoryContainerAPIClient;
+    use hyper::Method;
+
+    let client = InMemoryContainerAPIClient::new();
+
+    for (uri, request_body, error) in [
+        (
+            "/program-verification/47a9690570",
+            r#"{"backend": "cbmc"}"#,
+            r#"Unknown backend \"cbmc\" (expecting one of klee, proptest, seahorn)"#,
+        ),
+        (
+            "/program-fuzzing/47a9690570",
+            r#"{"options": {"cases": 0}}"#,
+            r#"Invalid value \"0\" for option \"cases\" of backend \"proptest\""#,
+        ),
+        (
+            "/program-fuzzing/47a9690570",
+            r#"{"options": {"max_time": 60}}"#,
+            r#"Option \"max_time\" is not accepted by backend \"proptest\""#,
+        ),
+        (
+            "/program-verification/47a9690570",
+            r#"{"flags": "LS1oZWxw"}"#,
+            "unknown field `flags`",
+        ),
+        (
+            "/source-restoration/47a9690570",
+            r#"{"backend": "klee"}"#,
+            r#"Step \"source_restoration\" does not run cargo verify"#,
+        ),
+    ] {
+        let (status, _, body) = send_request_with_body(
+            build_router(client.factory()).unwrap(),
+            Method::POST,
+            uri,
+            Body::from(request_body),
+        )
+        .await;
+
+        assert_eq!(StatusCode::BAD_REQUEST, status);
+        assert!(body.contains(error), "{}", body);
+    }
+
+    assert!(client.calls().is_empty());
+}
+
+#[tokio::test]
+async fn it_responds_with_not_found_for_unknown_steps() {
+    use crate::infra::verification_runtime::in_memory::InMemoryContainerAPIClient;
+    use hyper::Method;
+
+    let client = InMemoryContainerAPIClient::new();
+
+    for (method, uri) in [
+        (Method::POST, "/program-mining/47a9690570"),
+        (Method::GET, "/program-mining/47a9690570/report"),
+        (Method::GET, "/program-mining/47a9690570/progress"),
+        (Method::DELETE, "/program-mining/47a9690570"),
+    ] {
+        let (status, _, body) =
+            send_request(build_router(client.factory()).unwrap(), method, uri).await;
+
+        assert_eq!(StatusCode::NOT_FOUND, status);
+        assert!(
+            body.contains(r#"Unknown step \"program_mining\""#),
+            "{}",
+            body
+        );
+    }
+
+    assert!(client.calls().is_empty());
+}
+
+#[tokio::test]
+async fn it_selects_settings_of_projects_when_uploading_sources() {
+    use crate::test;
+    use hyper::Method;
+    use std::collections::HashMap;
+
+    let project = test::InMemoryProject::upload();
+    let client = &project.client;
+
+    let source = base64::encode(format!(
+        "#[ink::contract]\nmod {} {{}}",
+        test::generate_random_letters()
+    ));
+
+    let (status, _, body) = send_request_with_body(
+        build_router(client.factory()).unwrap(),
+        Method::POST,
+        "/source",
+        Body::from(format!(r#"{{"source": "{}"}}"#, source)),
+    )
+    .await;
+    assert_eq!(StatusCode::OK, status);
+
+    let uploaded: HashMap<String, String> = serde_json::from_str(body.as_str()).unwrap();
+    assert_eq!("ink_contract", uploaded["kind"]);
+    assert_eq!("3.0.0-rc4", uploaded["ink_version"]);
+    assert_eq!("erc20", uploaded["template"]);
+
+    let (status, _, body) = send_request_with_body(
+        build_router(client.factory()).unwrap(),
+        Method::POST,
+        "/source",
+        Body::from(format!(
+            r#"{{"source": "{}", "ink_version": "2.1.0", "template": "flipper"}}"#,
+            source
+        )),
+    )
+    .await;
+    assert_eq!(StatusCode::BAD_REQUEST, status);
+    assert!(body.contains(r#"Template \"flipper\" is not installed for ink! 2.1.0"#));
+    test::remove_project(uploaded["project_id"].as_str());
+
+    // Plain Rust code is verified without depending on ink!
+    let source = base64::encode(format!(
+        "fn main() {{\n    println!(\"{}\");\n}}",
+        test::generate_random_letters()
+    ));
+    let (status, _, body) = send_request_with_body(
+        build_router(client.factory()).unwrap(),
+        Method::POST,
+        "/source",
+        Body::from(format!(r#"{{"source": "{}"}}"#, source)),
+    )
+    .await;
+    assert_eq!(StatusCode::OK, status);
+
+    let uploaded: HashMap<String, String> = serde_json::from_str(body.as_str()).unwrap();
+    assert_eq!("binary", uploaded["kind"]);
+    assert!(!uploaded.contains_key("template"));
+    test::remove_project(uploaded["project_id"].as_str());
+}
+
+#[tokio::test]
+async fn it_scaffolds_contracts_from_their_template() {
+    use crate::infra::scaffold;
+    use crate::infra::verification_runtime::in_memory::InMemoryContainerAPIClient;
+    use crate::test;
+    use hyper::Method;
+    use std::collections::HashMap;
+    use std::fs;
+    use std::path::Path;
+
+    test::configure_env();
+
+    let client = InMemoryContainerAPIClient::new();
+    let source = base64::encode(format!("mod {} {{}}", test::generate_random_letters()));
+
+    let (status, _, body) = send_request_with_body(
+        build_router(client.factory()).unwrap(),
+        Method::POST,
+        "/source",
+        Body::from(format!(
+            r#"{{"source": "{}", "ink_version": "3.0.0-rc4", "template": "multisig"}}"#,
+            source
+        )),
+    )
+    .await;
+    assert_eq!(StatusCode::OK, status);
+    let uploaded: HashMap<String, String> = serde_json::from_str(body.as_str()).unwrap();
+    let project_id = uploaded["project_id"].as_str();
+
+    let (status, _, _) = send_request(
+        build_router(client.factory()).unwrap(),
+        Method::POST,
+        format!("/program-verification/{}", project_id).as_str(),
+    )
+    .await;
+    assert_eq!(StatusCode::OK, status);
+
+    // Contracts depend on the checkout of ink! their template belongs to
+    let manifest = fs::read_to_string(
+        Path::new(&scaffold::format_directory_path_to_scaffold(project_id)).join("Cargo.toml"),
+    )
+    .unwrap();
+    assert!(manifest.contains(
+        r#"ink_env = { version = "3.0.0-rc4", path = "/safepkt-ink-3.0.0-rc4/crates/env""#
+    ));
+    assert!(!manifest.contains("../../primitives"));
+
+    test::remove_project(project_id);
+}
+
+#[tokio::test]
+async fn it_exposes_klee_statistics_as_metrics() {
+    use crate::infra::{PROGRAM_LINTING, PROGRAM_VERIFICATION};
+    use crate::test;
+    use hyper::Method;
+
+    let project = test::InMemoryProject::upload();
+    let project_id = &project.project_id;
+    let run_id = project.run_id(PROGRAM_VERIFICATION);
+
+    let client = &project.client;
+    client
+        .script_statuses(run_id.as_str(), vec!["exited"])
+        .script_logs(
+            run_id.as_str(),
+            "Tests results for \"safe_transfer\"\n\tKLEE: done: completed paths = 4\n",
+        )
+        .script_statuses(project.run_id(PROGRAM_LINTING).as_str(), vec!["exited"]);
+
+    for step_uri in &["program-verification", "program-linting"] {
+        let (status, _, _) = send_request(
+            build_router(client.factory()).unwrap(),
+            Method::POST,
+            format!("/{}/{}", step_uri, project_id).as_str(),
+        )
+        .await;
+        assert_eq!(StatusCode::OK, status);
+    }
+
+    let (status, content_type, body) = send_request(
+        build_router(client.factory()).unwrap(),
+        Method::GET,
+        "/metrics",
+    )
+    .await;
+    assert_eq!(StatusCode::OK, status);
+    assert!(content_type.starts_with("text/plain; version=0.0.4"));
+    assert!(body.contains(
+        format!(
+            "safepkt_klee_completed_paths{{run_id=\"{}\",step=\"program_verification\",project_id=\"{}\",entry_point=\"safe_transfer\"}} 4\n",
+            run_id, project_id
+        )
+        .as_str()
+    ));
+    assert!(!body.contains("program_linting"));
+}
+
+#[tokio::test]
+async fn it_runs_pipelines_through_controllers() {
+    use crate::infra::PROGRAM_VERIFICATION;
+    use crate::test;
+    use hyper::Method;
+
+    let project = test::InMemoryProject::upload();
+    let project_id = &project.project_id;
+    let run_id = project.run_id(PROGRAM_VERIFICATION);
+
+    let client = &project.client;
+    client.script_statuses(run_id.as_str(), vec!["running"]);
+
+    let pipeline_uri = format!("/pipelines/{}", project_id);
+
+    let (status, _, body) = send_request_with_body(
+        build_router(client.factory()).unwrap(),
+        Method::POST,
+        pipeline_uri.as_str(),
+        Body::from(r#"{"steps": ["program_verification", "program_mining"]}"#),
+    )
+    .await;
+    assert_eq!(StatusCode::BAD_REQUEST, status);
+    assert!(body.contains(r#"There is no step named \"program_mining\""#));
+
+    let (status, _, body) = send_request_with_body(
+        build_router(client.factory()).unwrap(),
+        Method::POST,
+        pipeline_uri.as_str(),
+        Body::from(r#"{"steps": ["program_verification", "program_fuzzing"]}"#),
+    )
+    .await;
+    assert_eq!(StatusCode::OK, status);
+    assert!(body.contains(run_id.as_str()));
+
+    let (status, _, body) = send_request(
+        build_router(client.factory()).unwrap(),
+        Method::GET,
+        format!("{}/progress", pipeline_uri).as_str(),
+    )
+    .await;
+    assert_eq!(StatusCode::OK, status);
+    assert!(body.contains("\"status\":\"running\""));
+
+    let (status, _, body) = send_request(
+        build_router(client.factory()).unwrap(),
+        Method::DELETE,
+        pipeline_uri.as_str(),
+    )
+    .await;
+    assert_eq!(StatusCode::OK, status);
+    assert!(body.contains("\"status\":\"stopped\""));
+
+    let (status, _, body) = send_request(
+        build_router(client.factory()).unwrap(),
+        Method::GET,
+        format!("{}/report", pipeline_uri).as_str(),
+    )
+    .await;
+    assert_eq!(StatusCode::OK, status);
+    assert!(body.contains("\"reports\":["));
+
+    let (status, _, _) = send_request(
+        build_router(client.factory()).unwrap(),
+        Method::GET,
+        "/pipelines/47a9690570/progress",
+    )
+    .await;
+    assert_eq!(StatusCode::NOT_FOUND, status);
+}
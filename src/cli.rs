@@ -5,6 +5,7 @@ use safepkt_backend::app::command;
 use safepkt_backend::app::middleware;
 use safepkt_backend::infra::display;
 use safepkt_backend::infra::sigpipe;
+use safepkt_backend::infra::step_registry;
 use std::env;
 
 pub const VERSION: &str = "0.2.1";
@@ -29,6 +30,8 @@ async fn main() -> Result<(), Report> {
 
     env::set_var("CLI", "true");
 
+    step_registry::step_registry()?;
+
     let matches = configure();
 
     if let Some(source_path_matches) =
@@ -1 +1,3 @@
+pub mod ink_template;
 pub mod manifest;
+pub mod settings;
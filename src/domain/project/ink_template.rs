@@ -0,0 +1,210 @@
+use color_eyre::{eyre::eyre, Report};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// Checkout of ink! installed in the verifier image (see setup-verifier.sh)
+struct InkCheckout {
+    version: &'static str,
+    directory: &'static str,
+    /// Path to the crates of ink!, relative to the scaffolded project
+    /// (mounted in the examples of the first checkout) or absolute
+    crates: &'static str,
+    /// Examples of the checkout projects can be scaffolded from, the first one by default
+    examples: &'static [&'static str],
+}
+
+static INK_CHECKOUTS: [InkCheckout; 2] = [
+    InkCheckout {
+        version: "2.1.0",
+        directory: "/safepkt-ink",
+        crates: "../..",
+        examples: &["multisig_plain", "erc20", "erc721"],
+    },
+    InkCheckout {
+        version: "3.0.0-rc4",
+        directory: "/safepkt-ink-3.0.0-rc4",
+        crates: "/safepkt-ink-3.0.0-rc4/crates",
+        examples: &["erc20", "erc721", "multisig"],
+    },
+];
+
+/// Example of ink! a project is scaffolded from, along with the version of ink! it depends on
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct InkTemplate {
+    ink_version: String,
+    template: String,
+}
+
+impl Default for InkTemplate {
+    fn default() -> Self {
+        InkTemplate::of(&INK_CHECKOUTS[0], INK_CHECKOUTS[0].examples[0])
+    }
+}
+
+impl InkTemplate {
+    fn of(checkout: &InkCheckout, example: &str) -> InkTemplate {
+        InkTemplate {
+            ink_version: checkout.version.to_string(),
+            template: example.to_string(),
+        }
+    }
+
+    /// Templates installed in the verifier image (e.g. "2.1.0/multisig_plain")
+    pub fn installed() -> Vec<InkTemplate> {
+        INK_CHECKOUTS
+            .iter()
+            .flat_map(|checkout| {
+                checkout
+                    .examples
+                    .iter()
+                    .map(move |example| InkTemplate::of(checkout, example))
+            })
+            .collect()
+    }
+
+    /// Detect the version of ink! a contract is written for from its `#[ink::contract]` attribute,
+    /// which requires a `version` argument up to ink! 2 and has none from ink! 3 on.
+    /// Sources without such attribute fall back to the default version.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use safepkt_backend::app::domain::ink_template::InkTemplate;
+    ///
+    /// let source = "#[ink::contract(version = \"0.1.0\")]\nmod erc20 {}";
+    /// assert_eq!("2.1.0", InkTemplate::detect_ink_version(source));
+    ///
+    /// let source = "#[ink::contract]\nmod erc20 {}";
+    /// assert_eq!("3.0.0-rc4", InkTemplate::detect_ink_version(source));
+    ///
+    /// assert_eq!("2.1.0", InkTemplate::detect_ink_version("pub fn main() {}"));
+    /// ```
+    ///
+    pub fn detect_ink_version(source: &str) -> &'static str {
+        let attribute = Regex::new(r"#\[\s*(?:ink|ink_lang)::contract\s*(\(([^)]*)\))?\s*\]")
+            .expect("Invalid pattern of ink! contract attributes");
+        let version_argument = Regex::new(r"\bversion\s*=").expect("Invalid pattern of versions");
+
+        match attribute.captures(source) {
+            Some(captures) => {
+                let arguments = captures.get(2).map_or("", |arguments| arguments.as_str());
+                if version_argument.is_match(arguments) {
+                    INK_CHECKOUTS[0].version
+                } else {
+                    INK_CHECKOUTS[1].version
+                }
+            }
+            None => INK_CHECKOUTS[0].version,
+        }
+    }
+
+    /// Select one of the installed templates, the version of ink! being detected from the source
+    /// and the template defaulting to the first example of this version unless they are chosen.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use safepkt_backend::app::domain::ink_template::InkTemplate;
+    ///
+    /// let source = "#[ink::contract]\nmod erc20 {}";
+    ///
+    /// let template = InkTemplate::select(None, None, source).unwrap();
+    /// assert_eq!(("3.0.0-rc4", "erc20"), (template.ink_version(), template.name()));
+    ///
+    /// let template = InkTemplate::select(Some("2.1.0"), Some("erc721"), source).unwrap();
+    /// assert_eq!(("2.1.0", "erc721"), (template.ink_version(), template.name()));
+    ///
+    /// assert!(InkTemplate::select(Some("4.0.0"), None, source).is_err());
+    /// assert!(InkTemplate::select(None, Some("flipper"), source).is_err());
+    /// ```
+    ///
+    pub fn select(
+        ink_version: Option<&str>,
+        template: Option<&str>,
+        source: &str,
+    ) -> Result<InkTemplate, Report> {
+        let ink_version = ink_version.unwrap_or_else(|| InkTemplate::detect_ink_version(source));
+        let checkout = INK_CHECKOUTS
+            .iter()
+            .find(|checkout| checkout.version == ink_version)
+            .ok_or_else(|| {
+                eyre!(
+                    "Unknown ink! version \"{}\" (expecting one of {})",
+                    ink_version,
+                    INK_CHECKOUTS
+                        .iter()
+                        .map(|checkout| checkout.version)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            })?;
+
+        let template = template.unwrap_or(checkout.examples[0]);
+        if !checkout.examples.contains(&template) {
+            return Err(eyre!(
+                "Template \"{}\" is not installed for ink! {} (expecting one of {})",
+                template,
+                ink_version,
+                checkout.examples.join(", ")
+            ));
+        }
+
+        Ok(InkTemplate::of(checkout, template))
+    }
+
+    fn checkout(&self) -> &'static InkCheckout {
+        INK_CHECKOUTS
+            .iter()
+            .find(|checkout| checkout.version == self.ink_version)
+            .unwrap_or(&INK_CHECKOUTS[0])
+    }
+
+    pub fn ink_version(&self) -> &str {
+        self.ink_version.as_str()
+    }
+
+    pub fn name(&self) -> &str {
+        self.template.as_str()
+    }
+
+    /// Directory of the checkout of ink! in the verifier image
+    pub fn ink_directory(&self) -> &'static str {
+        self.checkout().directory
+    }
+
+    /// Path to the crates of ink! as declared in the manifest of a project
+    pub fn crates_path(&self) -> &'static str {
+        self.checkout().crates
+    }
+
+    /// Whether the template targets ink! 2, which generates ABIs with a dedicated crate (.ink/abi_gen)
+    pub fn generates_abi(&self) -> bool {
+        self.ink_version.starts_with("2.")
+    }
+}
+
+#[test]
+fn it_lists_installed_templates() {
+    let installed = InkTemplate::installed();
+
+    assert_eq!(InkTemplate::default(), installed[0]);
+    assert_eq!(
+        ("2.1.0", "multisig_plain", "/safepkt-ink"),
+        (
+            installed[0].ink_version(),
+            installed[0].name(),
+            installed[0].ink_directory()
+        )
+    );
+    assert!(installed
+        .iter()
+        .any(|template| template.ink_version() == "3.0.0-rc4" && !template.generates_abi()));
+
+    let template = serde_json::from_str::<InkTemplate>(
+        r#"{"ink_version": "3.0.0-rc4", "template": "multisig"}"#,
+    )
+    .unwrap();
+    assert_eq!("/safepkt-ink-3.0.0-rc4/crates", template.crates_path());
+    assert!(serde_json::from_str::<InkTemplate>(r#"{"version": "2.1.0"}"#).is_err());
+}
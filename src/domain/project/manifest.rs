@@ -1,22 +1,62 @@
-/// Make a manifest from a package name and
+use crate::domain::project::settings::{ProjectKind, ProjectSettings};
+
+/// Make a manifest from a package name,
 /// a path to Rust Verification Tools
+/// and the settings of the project (its kind and ink! template)
 ///
 /// # Examples
 ///
 /// ```
 /// use safepkt_backend::app::domain::manifest;
+/// use safepkt_backend::app::domain::settings::ProjectSettings;
 ///
 /// let package_name = "safepkt_backend";
 /// let rust_verification_tools_directory = "/home/rvt";
 ///
-/// let manifest = manifest::make_manifest(package_name, rust_verification_tools_directory);
+/// let manifest = manifest::make_manifest(
+///     package_name,
+///     rust_verification_tools_directory,
+///     &ProjectSettings::default(),
+/// );
 ///
 /// assert!(manifest.contains(package_name));
 /// assert!(manifest.contains(rust_verification_tools_directory));
+/// assert!(manifest.contains(r#"ink_lang = { version = "2.1.0", path = "../../lang""#));
+///
+/// let settings = ProjectSettings::select(None, Some("3.0.0-rc4"), None, "").unwrap();
+/// let manifest = manifest::make_manifest(package_name, rust_verification_tools_directory, &settings);
+/// assert!(manifest.contains(r#"path = "/safepkt-ink-3.0.0-rc4/crates/lang""#));
+///
+/// let settings = ProjectSettings::select(Some("binary"), None, None, "").unwrap();
+/// let manifest = manifest::make_manifest(package_name, rust_verification_tools_directory, &settings);
+/// assert!(manifest.contains("[[bin]]"));
+/// assert!(!manifest.contains("ink_"));
 /// ```
 ///
-pub fn make_manifest(package_name: &str, rvt_dir_path: &str) -> String {
-    let template = r#"
+pub fn make_manifest(package_name: &str, rvt_dir_path: &str, settings: &ProjectSettings) -> String {
+    let template = match (settings.kind(), settings.ink_template()) {
+        (ProjectKind::InkContract, Some(ink_template)) => {
+            let template = if ink_template.generates_abi() {
+                INK_2_MANIFEST
+            } else {
+                INK_3_MANIFEST
+            };
+
+            template
+                .replace("{{ ink_version }}", ink_template.ink_version())
+                .replace("{{ ink_crates }}", ink_template.crates_path())
+        }
+        (ProjectKind::Binary, _) => PLAIN_MANIFEST.replace("{{ target }}", BINARY_TARGET),
+        _ => PLAIN_MANIFEST.replace("{{ target }}", LIBRARY_TARGET),
+    };
+
+    template
+        .replace("{{ name }}", package_name)
+        .replace("{{ rust_verification_tools }}", rvt_dir_path)
+}
+
+/// Manifest of projects depending on ink! 2, which ABI is generated by a dedicated crate
+static INK_2_MANIFEST: &str = r#"
 [package]
 name = "{{ name }}"
 version = "0.1.0"
@@ -26,11 +66,11 @@ edition = "2018"
 [dependencies]
 verification-annotations = { path="{{ rust_verification_tools }}/verification-annotations" }
 safepkt_assert = { git="https://github.com/LedgerProject/safepkt_assert", rev="ddd0f25f291244508b5e32a16c811e1bcda1920b", features=["verifier-klee"] }
-ink_primitives = { version = "2.1.0", path = "../../primitives", default-features = false }
-ink_abi = { version = "2.1.0", path = "../../abi", default-features = false, features = ["derive"], optional = true }
-ink_core = { version = "2.1.0", path = "../../core", default-features = false }
-ink_lang = { version = "2.1.0", path = "../../lang", default-features = false }
-ink_prelude = { version = "2.1.0", path = "../../prelude", default-features = false }
+ink_primitives = { version = "{{ ink_version }}", path = "{{ ink_crates }}/primitives", default-features = false }
+ink_abi = { version = "{{ ink_version }}", path = "{{ ink_crates }}/abi", default-features = false, features = ["derive"], optional = true }
+ink_core = { version = "{{ ink_version }}", path = "{{ ink_crates }}/core", default-features = false }
+ink_lang = { version = "{{ ink_version }}", path = "{{ ink_crates }}/lang", default-features = false }
+ink_prelude = { version = "{{ ink_version }}", path = "{{ ink_crates }}/prelude", default-features = false }
 
 scale = { package = "parity-scale-codec", version = "1.2", default-features = false, features = ["derive"] }
 
@@ -95,14 +135,98 @@ exclude = [
 ]
 "#;
 
-    template
-        .replace("{{ name }}", package_name)
-        .replace("{{ rust_verification_tools }}", rvt_dir_path)
-}
+/// Manifest of projects depending on ink! 3, which metadata are generated by ink_metadata
+static INK_3_MANIFEST: &str = r#"
+[package]
+name = "{{ name }}"
+version = "0.1.0"
+authors = ["Parity Technologies <admin@parity.io>", "CJDNS SASU"]
+edition = "2018"
+
+[dependencies]
+verification-annotations = { path="{{ rust_verification_tools }}/verification-annotations" }
+safepkt_assert = { git="https://github.com/LedgerProject/safepkt_assert", rev="ddd0f25f291244508b5e32a16c811e1bcda1920b", features=["verifier-klee"] }
+ink_primitives = { version = "{{ ink_version }}", path = "{{ ink_crates }}/primitives", default-features = false }
+ink_metadata = { version = "{{ ink_version }}", path = "{{ ink_crates }}/metadata", default-features = false, features = ["derive"], optional = true }
+ink_env = { version = "{{ ink_version }}", path = "{{ ink_crates }}/env", default-features = false }
+ink_storage = { version = "{{ ink_version }}", path = "{{ ink_crates }}/storage", default-features = false }
+ink_lang = { version = "{{ ink_version }}", path = "{{ ink_crates }}/lang", default-features = false }
+ink_prelude = { version = "{{ ink_version }}", path = "{{ ink_crates }}/prelude", default-features = false }
+
+scale = { package = "parity-scale-codec", version = "2.0.1", default-features = false, features = ["derive"] }
+scale-info = { version = "0.6.0", default-features = false, features = ["derive"], optional = true }
+
+[patch.crates-io]
+rand = { version = "0.7.3", path = "/safepkt-rand" }
+smallvec = { version = "1.7.0", path = "/safepkt-rust-smallvec" }
+
+[lib]
+name = "{{ name }}"
+path = "src/lib.rs"
+crate-type = [
+	# Used for normal contract Wasm blobs.
+	"cdylib",
+	# Used for metadata generation.
+	"rlib",
+]
+
+[features]
+verifier-klee = ["verification-annotations/verifier-klee"]
+default = ["std"]
+std = [
+    "ink_metadata/std",
+    "ink_env/std",
+    "ink_storage/std",
+    "ink_primitives/std",
+    "ink_prelude/std",
+    "scale/std",
+    "scale-info/std",
+]
+ink-as-dependency = []
+
+[profile.release]
+panic = "abort"
+lto = true
+opt-level = "z"
+overflow-checks = true
+
+[workspace]
+"#;
+
+/// Manifest of plain libraries and binaries, only depending on verification crates
+static PLAIN_MANIFEST: &str = r#"
+[package]
+name = "{{ name }}"
+version = "0.1.0"
+authors = ["CJDNS SASU"]
+edition = "2018"
+
+[dependencies]
+verification-annotations = { path="{{ rust_verification_tools }}/verification-annotations" }
+safepkt_assert = { git="https://github.com/LedgerProject/safepkt_assert", rev="ddd0f25f291244508b5e32a16c811e1bcda1920b", features=["verifier-klee"] }
+{{ target }}
+[features]
+verifier-klee = ["verification-annotations/verifier-klee"]
+
+[workspace]
+"#;
+
+static LIBRARY_TARGET: &str = r#"
+[lib]
+name = "{{ name }}"
+path = "src/lib.rs"
+"#;
+
+static BINARY_TARGET: &str = r#"
+[[bin]]
+name = "{{ name }}"
+path = "src/main.rs"
+"#;
 
 #[test]
 fn it_makes_a_manifest() {
     use crate::domain::project::manifest;
+    use crate::domain::project::settings::ProjectSettings;
 
     let expected_manifest = r#"
 [package]
@@ -183,5 +307,41 @@ exclude = [
 ]
 "#;
 
-    assert_eq!(expected_manifest, manifest::make_manifest("test", "/rvt"))
+    assert_eq!(
+        expected_manifest,
+        manifest::make_manifest("test", "/rvt", &ProjectSettings::default())
+    )
+}
+
+#[test]
+fn it_makes_a_manifest_of_plain_libraries() {
+    use crate::domain::project::manifest;
+    use crate::domain::project::settings::ProjectSettings;
+
+    let expected_manifest = r#"
+[package]
+name = "test"
+version = "0.1.0"
+authors = ["CJDNS SASU"]
+edition = "2018"
+
+[dependencies]
+verification-annotations = { path="/rvt/verification-annotations" }
+safepkt_assert = { git="https://github.com/LedgerProject/safepkt_assert", rev="ddd0f25f291244508b5e32a16c811e1bcda1920b", features=["verifier-klee"] }
+
+[lib]
+name = "test"
+path = "src/lib.rs"
+
+[features]
+verifier-klee = ["verification-annotations/verifier-klee"]
+
+[workspace]
+"#;
+
+    let settings = ProjectSettings::select(None, None, None, "pub fn add() {}").unwrap();
+    assert_eq!(
+        expected_manifest,
+        manifest::make_manifest("test", "/rvt", &settings)
+    )
 }
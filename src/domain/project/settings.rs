@@ -0,0 +1,199 @@
+use crate::domain::project::ink_template::InkTemplate;
+use color_eyre::{eyre::eyre, Report};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// Kind of project a source is scaffolded into
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProjectKind {
+    InkContract,
+    Library,
+    Binary,
+}
+
+impl ProjectKind {
+    pub fn names() -> Vec<&'static str> {
+        vec!["ink_contract", "library", "binary"]
+    }
+
+    /// Parse a kind of project as chosen when uploading a source (e.g. "ink_contract")
+    pub fn parse(kind: &str) -> Result<ProjectKind, Report> {
+        match kind {
+            "ink_contract" => Ok(ProjectKind::InkContract),
+            "library" => Ok(ProjectKind::Library),
+            "binary" => Ok(ProjectKind::Binary),
+            _ => Err(eyre!(
+                "Unknown project kind \"{}\" (expecting one of {})",
+                kind,
+                ProjectKind::names().join(", ")
+            )),
+        }
+    }
+
+    /// Detect the kind of project of a source,
+    /// sources importing ink! (or declaring a contract with it) being contracts
+    /// and sources declaring a main function binaries,
+    /// mentions of ink! in comments or string literals being left out.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use safepkt_backend::app::domain::settings::ProjectKind;
+    ///
+    /// let source = "use ink_lang as ink;\n#[ink::contract]\nmod erc20 {}";
+    /// assert_eq!(ProjectKind::InkContract, ProjectKind::detect(source));
+    ///
+    /// assert_eq!(ProjectKind::Binary, ProjectKind::detect("fn main() {}"));
+    /// assert_eq!(ProjectKind::Library, ProjectKind::detect("pub fn add(a: u8, b: u8) -> u8 { a + b }"));
+    /// ```
+    ///
+    pub fn detect(source: &str) -> ProjectKind {
+        let ink = Regex::new(
+            r"(?m)^\s*(?:#\[\s*(?:ink|ink_lang)::contract\b|(?:pub\s+)?(?:use|extern\s+crate)\s+(?:::)?ink_lang\b)",
+        )
+        .expect("Invalid pattern of ink! contracts");
+        let main = Regex::new(r"(?m)^\s*(?:pub\s+)?(?:async\s+)?fn\s+main\s*\(")
+            .expect("Invalid pattern of main functions");
+
+        if ink.is_match(source) {
+            ProjectKind::InkContract
+        } else if main.is_match(source) {
+            ProjectKind::Binary
+        } else {
+            ProjectKind::Library
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProjectKind::InkContract => "ink_contract",
+            ProjectKind::Library => "library",
+            ProjectKind::Binary => "binary",
+        }
+    }
+
+    /// File of the scaffolded project the source is written to (in ./src)
+    pub fn entry_point(&self) -> &'static str {
+        match self {
+            ProjectKind::Binary => "main.rs",
+            _ => "lib.rs",
+        }
+    }
+}
+
+/// Kind of a project and, for ink! contracts, the template it is scaffolded from
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProjectSettings {
+    kind: ProjectKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ink_template: Option<InkTemplate>,
+}
+
+impl Default for ProjectSettings {
+    fn default() -> Self {
+        ProjectSettings {
+            kind: ProjectKind::InkContract,
+            ink_template: Some(InkTemplate::default()),
+        }
+    }
+}
+
+impl ProjectSettings {
+    /// Select the settings of a project, its kind being detected from its source unless chosen
+    /// (choosing a version of ink! or a template implying an ink! contract).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use safepkt_backend::app::domain::settings::{ProjectKind, ProjectSettings};
+    ///
+    /// let settings = ProjectSettings::select(None, None, None, "fn main() {}").unwrap();
+    /// assert_eq!(ProjectKind::Binary, settings.kind());
+    /// assert!(settings.ink_template().is_none());
+    ///
+    /// let settings = ProjectSettings::select(None, None, Some("erc721"), "fn main() {}").unwrap();
+    /// assert_eq!(ProjectKind::InkContract, settings.kind());
+    /// assert_eq!("erc721", settings.ink_template().unwrap().name());
+    ///
+    /// assert!(ProjectSettings::select(Some("library"), Some("2.1.0"), None, "").is_err());
+    /// assert!(ProjectSettings::select(Some("workspace"), None, None, "").is_err());
+    /// ```
+    ///
+    pub fn select(
+        kind: Option<&str>,
+        ink_version: Option<&str>,
+        template: Option<&str>,
+        source: &str,
+    ) -> Result<ProjectSettings, Report> {
+        let kind = match kind {
+            Some(kind) => ProjectKind::parse(kind)?,
+            None if ink_version.is_some() || template.is_some() => ProjectKind::InkContract,
+            None => ProjectKind::detect(source),
+        };
+
+        if kind != ProjectKind::InkContract {
+            if ink_version.is_some() || template.is_some() {
+                return Err(eyre!(
+                    "A version of ink! or a template can only be chosen for ink! contracts (not for a {})",
+                    kind.as_str()
+                ));
+            }
+
+            return Ok(ProjectSettings {
+                kind,
+                ink_template: None,
+            });
+        }
+
+        Ok(ProjectSettings {
+            kind,
+            ink_template: Some(InkTemplate::select(ink_version, template, source)?),
+        })
+    }
+
+    pub fn kind(&self) -> ProjectKind {
+        self.kind
+    }
+
+    pub fn ink_template(&self) -> Option<&InkTemplate> {
+        self.ink_template.as_ref()
+    }
+}
+
+#[test]
+fn it_detects_kinds_of_projects() {
+    assert_eq!(
+        ProjectKind::InkContract,
+        ProjectKind::detect("#[ink::contract(version = \"0.1.0\")]\nmod multisig_plain {}")
+    );
+    assert_eq!(
+        ProjectKind::Binary,
+        ProjectKind::detect("use std::env;\n\npub fn main() {\n}")
+    );
+    assert_eq!(
+        ProjectKind::Library,
+        ProjectKind::detect("pub fn domain() {}\n// fn main() is not declared")
+    );
+    assert_eq!(
+        ProjectKind::InkContract,
+        ProjectKind::detect("extern crate ink_lang;\n\npub fn domain() {}")
+    );
+    assert_eq!(
+        ProjectKind::Library,
+        ProjectKind::detect(
+            "// unlike ink_lang, this crate does not declare #[ink::contract]\npub fn domain() -> &'static str {\n    \"use ink_lang as ink;\"\n}"
+        )
+    );
+
+    let settings = serde_json::to_string(&ProjectSettings::default()).unwrap();
+    assert_eq!(
+        r#"{"kind":"ink_contract","ink_template":{"ink_version":"2.1.0","template":"multisig_plain"}}"#,
+        settings
+    );
+    assert_eq!(
+        r#"{"kind":"library"}"#,
+        serde_json::to_string(&ProjectSettings::select(None, None, None, "").unwrap()).unwrap()
+    );
+}
@@ -1,3 +1,4 @@
+use crate::domain::value_object::LogCursor;
 use async_trait::async_trait;
 
 #[async_trait]
@@ -8,7 +9,7 @@ pub trait ProgramVerification {
     fn new(target: Self::A) -> Self;
 
     async fn run_step(&self) -> Self::R;
-    async fn step_report(&self) -> Self::R;
+    async fn step_report(&self, cursor: Option<&LogCursor>) -> Self::R;
     async fn step_progress(&self) -> Self::R;
 }
 
@@ -16,8 +17,12 @@ pub trait ProgramVerification {
 pub struct VerificationTarget<'a> {
     pub step: &'a str,
     pub project_id: &'a str,
+    pub timeout: Option<u64>,
+    pub fresh_run: bool,
+    pub backend: Option<&'a str>,
 }
 
-pub struct SmartContractVerification<'a> {
+pub struct SmartContractVerification<'a, F> {
     pub target: VerificationTarget<'a>,
+    pub container_api_client_factory: F,
 }
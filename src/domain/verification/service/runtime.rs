@@ -1,5 +1,6 @@
-use crate::domain::value_object::{StepInVerificationPlan, VerificationStepsCollection};
+use crate::domain::value_object::{LogCursor, StepInVerificationPlan, VerificationStepsCollection};
 use async_trait::async_trait;
+use color_eyre::Report;
 
 #[async_trait]
 pub trait ContainerAPIClient {
@@ -7,10 +8,19 @@ pub trait ContainerAPIClient {
     type P;
 
     async fn inspect_container_status(&self, project_step: &StepInVerificationPlan) -> Self::R;
+    async fn inspect_image(&self, project_step: &StepInVerificationPlan) -> Self::R;
     async fn remove_existing_container(&self, project_step: &StepInVerificationPlan) -> Self::P;
-    async fn start_container(&self, project_step: &StepInVerificationPlan) -> Self::P;
+    async fn start_container(&self, project_step: &StepInVerificationPlan) -> Self::R;
     async fn stop_container(&self, project_step: &StepInVerificationPlan) -> Self::P;
-    async fn tail_container_logs(&self, project_step: &StepInVerificationPlan) -> Self::R;
+    /// Tail logs written after a cursor (all logs without cursor),
+    /// along with the cursor to read the next ones from
+    async fn tail_container_logs(
+        &self,
+        project_step: &StepInVerificationPlan,
+        cursor: Option<&LogCursor>,
+    ) -> Self::R;
+    /// List the runs managed by this backend instance by run id, along with their raw status
+    async fn list_runs(&self) -> Self::R;
 }
 
 pub struct VerificationRuntime<'a, T> {
@@ -21,10 +31,10 @@ pub struct VerificationRuntime<'a, T> {
 
 #[async_trait]
 pub trait VerificationStepRunner<R> {
-    fn steps_names() -> Vec<&'static str>;
+    fn steps_names() -> Result<Vec<&'static str>, Report>;
 
     async fn get_progress(&self) -> R;
-    async fn get_report(&self) -> R;
+    async fn get_report(&self, cursor: Option<&LogCursor>) -> R;
     async fn start_running(&self) -> R;
     async fn stop_running(&self) -> R;
 }
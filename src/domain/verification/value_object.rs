@@ -1,7 +1,21 @@
+mod backend;
+mod command;
 mod flags;
+mod log_cursor;
+mod outcome;
+mod pipeline;
+mod report;
 mod source;
 mod step;
+mod step_definition;
 
+pub use backend::*;
+pub use command::*;
 pub use flags::*;
+pub use log_cursor::*;
+pub use outcome::*;
+pub use pipeline::*;
+pub use report::*;
 pub use source::*;
 pub use step::*;
+pub use step_definition::*;
@@ -0,0 +1,361 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Cases run by proptest unless requested otherwise
+pub static DEFAULT_PROPTEST_CASES: &str = "5";
+
+static KLEE_SEARCHES: [&str; 6] = [
+    "dfs",
+    "bfs",
+    "random-state",
+    "random-path",
+    "nurs:covnew",
+    "nurs:md2u",
+];
+
+/// Backends `cargo verify` (rust-verification-tools) can verify programs with
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum VerifierBackend {
+    Klee,
+    Proptest,
+    Seahorn,
+}
+
+/// Option of a backend, passed to the verification script as an environment variable
+struct BackendOption {
+    name: &'static str,
+    variable: &'static str,
+    expected: &'static str,
+    is_valid: fn(&str) -> bool,
+}
+
+fn is_positive_integer(value: &str, max: u64) -> bool {
+    value
+        .parse::<u64>()
+        .is_ok_and(|value| value > 0 && value <= max)
+}
+
+fn is_test_name(value: &str) -> bool {
+    !value.is_empty()
+        && value.len() <= 128
+        && value.chars().all(|character| {
+            character.is_ascii_alphanumeric() || character == '_' || character == ':'
+        })
+}
+
+/// Options accepted by all backends
+fn common_options() -> Vec<BackendOption> {
+    vec![
+        BackendOption {
+            name: "test",
+            variable: "VERIFY_TEST",
+            expected: "a test name made of letters, digits, underscores and colons",
+            is_valid: is_test_name,
+        },
+        BackendOption {
+            name: "verbosity",
+            variable: "VERIFY_VERBOSITY",
+            expected: "a level between 0 and 4",
+            is_valid: |value| value.parse::<u8>().is_ok_and(|level| level <= 4),
+        },
+        BackendOption {
+            name: "quiet",
+            variable: "VERIFY_QUIET",
+            expected: "a boolean",
+            is_valid: |value| value == "true" || value == "false",
+        },
+    ]
+}
+
+/// Options of a verification, typed so that only known options
+/// (and values of the expected type) are accepted in place of raw flags
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct VerificationOptions {
+    pub cases: Option<u64>,
+    pub max_time: Option<u64>,
+    pub max_memory: Option<u64>,
+    pub search: Option<String>,
+    pub test: Option<String>,
+    pub verbosity: Option<u8>,
+    pub quiet: Option<bool>,
+}
+
+impl VerificationOptions {
+    /// Options which have been passed, by name
+    fn to_map(&self) -> BTreeMap<String, String> {
+        let mut options = BTreeMap::new();
+        let mut insert = |name: &str, value: Option<String>| {
+            if let Some(value) = value {
+                options.insert(name.to_string(), value);
+            }
+        };
+
+        insert("cases", self.cases.map(|cases| cases.to_string()));
+        insert("max_time", self.max_time.map(|seconds| seconds.to_string()));
+        insert(
+            "max_memory",
+            self.max_memory.map(|megabytes| megabytes.to_string()),
+        );
+        insert("search", self.search.clone());
+        insert("test", self.test.clone());
+        insert("verbosity", self.verbosity.map(|level| level.to_string()));
+        insert("quiet", self.quiet.map(|quiet| quiet.to_string()));
+
+        options
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.to_map().is_empty()
+    }
+}
+
+impl VerifierBackend {
+    pub fn names() -> Vec<&'static str> {
+        vec!["klee", "proptest", "seahorn"]
+    }
+
+    pub fn parse(name: &str) -> Result<VerifierBackend, String> {
+        match name {
+            "klee" => Ok(VerifierBackend::Klee),
+            "proptest" => Ok(VerifierBackend::Proptest),
+            "seahorn" => Ok(VerifierBackend::Seahorn),
+            _ => Err(format!(
+                "Unknown backend \"{}\" (expecting one of {})",
+                name,
+                VerifierBackend::names().join(", ")
+            )),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            VerifierBackend::Klee => "klee",
+            VerifierBackend::Proptest => "proptest",
+            VerifierBackend::Seahorn => "seahorn",
+        }
+    }
+
+    fn options(&self) -> Vec<BackendOption> {
+        let mut options = match self {
+            VerifierBackend::Klee => vec![
+                BackendOption {
+                    name: "search",
+                    variable: "KLEE_SEARCH",
+                    expected: "one of dfs, bfs, random-state, random-path, nurs:covnew, nurs:md2u",
+                    is_valid: |value| KLEE_SEARCHES.contains(&value),
+                },
+                BackendOption {
+                    name: "max_memory",
+                    variable: "KLEE_MAX_MEMORY",
+                    expected: "a number of megabytes between 1 and 65536",
+                    is_valid: |value| is_positive_integer(value, 65536),
+                },
+                BackendOption {
+                    name: "max_time",
+                    variable: "KLEE_MAX_TIME",
+                    expected: "a number of seconds between 1 and 86400",
+                    is_valid: |value| is_positive_integer(value, 86400),
+                },
+            ],
+            VerifierBackend::Proptest => vec![BackendOption {
+                name: "cases",
+                variable: "PROPTEST_CASES",
+                expected: "a number of cases between 1 and 1000000",
+                is_valid: |value| is_positive_integer(value, 1_000_000),
+            }],
+            // SeaHorn is only passed the options common to all backends
+            VerifierBackend::Seahorn => vec![],
+        };
+        options.extend(common_options());
+
+        options
+    }
+
+    fn default_options(&self) -> Vec<(&'static str, &'static str)> {
+        match self {
+            VerifierBackend::Proptest => vec![("cases", DEFAULT_PROPTEST_CASES)],
+            _ => vec![],
+        }
+    }
+}
+
+/// Backend a verification step is run with, along with its validated options
+#[derive(Clone, Debug, PartialEq)]
+pub struct BackendSelection {
+    backend: VerifierBackend,
+    options: BTreeMap<String, String>,
+}
+
+impl BackendSelection {
+    /// Select a backend after validating its options against the ones it accepts,
+    /// options which are not passed falling back to their default value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use safepkt_backend::app::domain::value_object::{BackendSelection, VerificationOptions};
+    ///
+    /// let options = VerificationOptions {
+    ///     cases: Some(20),
+    ///     quiet: Some(true),
+    ///     ..Default::default()
+    /// };
+    ///
+    /// let selection = BackendSelection::new("proptest", &options).unwrap();
+    /// assert_eq!("cases=20,quiet=true", selection.format_options());
+    ///
+    /// assert_eq!(
+    ///     "Option \"cases\" is not accepted by backend \"klee\" (expecting one of search, max_memory, max_time, test, verbosity, quiet)",
+    ///     BackendSelection::new("klee", &options).unwrap_err()
+    /// );
+    /// ```
+    ///
+    pub fn new(backend: &str, options: &VerificationOptions) -> Result<Self, String> {
+        let backend = VerifierBackend::parse(backend)?;
+        let known_options = backend.options();
+        let options = options.to_map();
+
+        for (name, value) in options.iter() {
+            let option = known_options
+                .iter()
+                .find(|option| option.name == name)
+                .ok_or_else(|| {
+                    let names: Vec<&str> = known_options.iter().map(|option| option.name).collect();
+
+                    format!(
+                        "Option \"{}\" is not accepted by backend \"{}\" (expecting one of {})",
+                        name,
+                        backend.as_str(),
+                        names.join(", ")
+                    )
+                })?;
+
+            if !(option.is_valid)(value.as_str()) {
+                return Err(format!(
+                    "Invalid value \"{}\" for option \"{}\" of backend \"{}\" (expecting {})",
+                    value,
+                    name,
+                    backend.as_str(),
+                    option.expected
+                ));
+            }
+        }
+
+        let mut selected_options = options;
+        for (name, value) in backend.default_options() {
+            selected_options
+                .entry(name.to_string())
+                .or_insert_with(|| value.to_string());
+        }
+
+        Ok(BackendSelection {
+            backend,
+            options: selected_options,
+        })
+    }
+
+    pub fn backend(&self) -> VerifierBackend {
+        self.backend
+    }
+
+    pub fn options(&self) -> &BTreeMap<String, String> {
+        &self.options
+    }
+
+    /// Options formatted as "name=value" pairs (e.g. "cases=20"), sorted by name
+    pub fn format_options(&self) -> String {
+        self.options
+            .iter()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect::<Vec<String>>()
+            .join(",")
+    }
+
+    /// Variables declaring the backend and its options to the verification script
+    pub fn environment(&self) -> Vec<(String, String)> {
+        let known_options = self.backend.options();
+        let mut environment = vec![(
+            "VERIFY_BACKEND".to_string(),
+            self.backend.as_str().to_string(),
+        )];
+
+        for (name, value) in self.options.iter() {
+            if let Some(option) = known_options.iter().find(|option| option.name == name) {
+                environment.push((option.variable.to_string(), value.clone()));
+            }
+        }
+
+        environment
+    }
+}
+
+#[test]
+fn it_validates_options_of_backends() {
+    let klee = BackendSelection::new(
+        "klee",
+        &VerificationOptions {
+            search: Some("bfs".to_string()),
+            test: Some("tests::safe_transfer".to_string()),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    assert_eq!(VerifierBackend::Klee, klee.backend());
+    assert_eq!(
+        vec![
+            ("VERIFY_BACKEND".to_string(), "klee".to_string()),
+            ("KLEE_SEARCH".to_string(), "bfs".to_string()),
+            (
+                "VERIFY_TEST".to_string(),
+                "tests::safe_transfer".to_string()
+            )
+        ],
+        klee.environment()
+    );
+
+    let proptest = BackendSelection::new("proptest", &VerificationOptions::default()).unwrap();
+    assert_eq!("cases=5", proptest.format_options());
+
+    let invalid = |backend: &str, options: VerificationOptions| {
+        BackendSelection::new(backend, &options).unwrap_err()
+    };
+
+    assert_eq!(
+        "Invalid value \"0\" for option \"cases\" of backend \"proptest\" (expecting a number of cases between 1 and 1000000)",
+        invalid("proptest", VerificationOptions { cases: Some(0), ..Default::default() })
+    );
+    assert_eq!(
+        "Invalid value \"safe_transfer --nocapture\" for option \"test\" of backend \"seahorn\" (expecting a test name made of letters, digits, underscores and colons)",
+        invalid(
+            "seahorn",
+            VerificationOptions {
+                test: Some("safe_transfer --nocapture".to_string()),
+                ..Default::default()
+            }
+        )
+    );
+    assert_eq!(
+        "Option \"search\" is not accepted by backend \"seahorn\" (expecting one of test, verbosity, quiet)",
+        invalid(
+            "seahorn",
+            VerificationOptions {
+                search: Some("dfs".to_string()),
+                ..Default::default()
+            }
+        )
+    );
+    assert_eq!(
+        "Invalid value \"5\" for option \"verbosity\" of backend \"klee\" (expecting a level between 0 and 4)",
+        invalid("klee", VerificationOptions { verbosity: Some(5), ..Default::default() })
+    );
+    assert_eq!(
+        "Unknown backend \"cbmc\" (expecting one of klee, proptest, seahorn)",
+        invalid("cbmc", VerificationOptions::default())
+    );
+
+    let error = serde_json::from_str::<VerificationOptions>(r#"{"flags": "--help"}"#)
+        .unwrap_err()
+        .to_string();
+    assert!(error.starts_with("unknown field `flags`"));
+}
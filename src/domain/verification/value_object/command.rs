@@ -0,0 +1,69 @@
+/// Command run by a step, described as a program, its arguments
+/// and variables to be declared in its environment,
+/// so that no argument is ever split or interpreted by a shell.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct StepCommand {
+    program: String,
+    arguments: Vec<String>,
+    environment: Vec<(String, String)>,
+}
+
+impl StepCommand {
+    /// # Examples
+    ///
+    /// ```
+    /// use safepkt_backend::app::domain::value_object::StepCommand;
+    ///
+    /// let command = StepCommand::new("/usr/local/bin/verify")
+    ///     .arg("safepkt_47a9690570")
+    ///     .env("VERIFY_TEST", "tests::with spaces");
+    ///
+    /// assert_eq!(vec!["/usr/local/bin/verify", "safepkt_47a9690570"], command.argv());
+    /// assert_eq!(vec!["VERIFY_TEST=tests::with spaces"], command.env_vars());
+    /// ```
+    ///
+    pub fn new(program: &str) -> StepCommand {
+        StepCommand {
+            program: program.to_string(),
+            ..Default::default()
+        }
+    }
+
+    pub fn arg(mut self, argument: &str) -> StepCommand {
+        self.arguments.push(argument.to_string());
+        self
+    }
+
+    pub fn env(mut self, name: &str, value: &str) -> StepCommand {
+        self.environment.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn program(&self) -> &str {
+        self.program.as_str()
+    }
+
+    pub fn arguments(&self) -> &[String] {
+        self.arguments.as_slice()
+    }
+
+    pub fn environment(&self) -> &[(String, String)] {
+        self.environment.as_slice()
+    }
+
+    /// Program followed by its arguments
+    pub fn argv(&self) -> Vec<String> {
+        let mut argv = vec![self.program.clone()];
+        argv.extend(self.arguments.iter().cloned());
+
+        argv
+    }
+
+    /// Environment variables formatted as "NAME=value"
+    pub fn env_vars(&self) -> Vec<String> {
+        self.environment
+            .iter()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect()
+    }
+}
@@ -1,16 +1,46 @@
+use super::VerificationOptions;
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize)]
-pub struct Flags<'a> {
-    flags: &'a [u8],
+/// Body of requests starting a step,
+/// verification options being typed rather than passed as raw flags
+#[derive(Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Flags {
+    #[serde(default)]
+    timeout: Option<u64>,
+    #[serde(default)]
+    fresh: bool,
+    #[serde(default)]
+    backend: Option<String>,
+    #[serde(default)]
+    options: VerificationOptions,
 }
 
-impl Flags<'_> {
-    pub fn new(&self, flags: &[u8]) {
-        Flags { flags };
+impl Flags {
+    pub fn new(timeout: Option<u64>) -> Flags {
+        Flags {
+            timeout,
+            ..Default::default()
+        }
     }
 
-    pub fn flags(&self) -> &[u8] {
-        self.flags
+    /// Time budget requested for a run in seconds
+    pub fn timeout(&self) -> Option<u64> {
+        self.timeout
+    }
+
+    /// Whether a run is requested even when results have been cached
+    pub fn fresh(&self) -> bool {
+        self.fresh
+    }
+
+    /// Backend `cargo verify` is requested to run with
+    pub fn backend(&self) -> Option<&str> {
+        self.backend.as_deref()
+    }
+
+    /// Options of the verification, validated against the backend it runs with
+    pub fn options(&self) -> &VerificationOptions {
+        &self.options
     }
 }
@@ -0,0 +1,101 @@
+use chrono::{DateTime, SecondsFormat, Utc};
+use color_eyre::{eyre::eyre, Report};
+use std::fmt;
+
+/// Position in the logs of a run, returned along with the logs read so far
+/// for the next ones to be read from.
+/// Cursors are opaque to clients, which pass them back as they have been returned.
+#[derive(Clone, Debug, PartialEq)]
+pub enum LogCursor {
+    /// Byte offset in logs written to a file or kept in memory
+    Offset(u64),
+    /// Time of the last log read from the Docker Engine API
+    Time(DateTime<Utc>),
+}
+
+impl LogCursor {
+    /// Parse a cursor as formatted when it has been returned
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use safepkt_backend::app::domain::value_object::LogCursor;
+    ///
+    /// assert_eq!(LogCursor::Offset(42), LogCursor::parse("offset:42").unwrap());
+    ///
+    /// let cursor = LogCursor::parse("time:2021-11-02T10:12:45.123456789Z").unwrap();
+    /// assert_eq!("time:2021-11-02T10:12:45.123456789Z", cursor.to_string());
+    ///
+    /// assert!(LogCursor::parse("42").is_err());
+    /// assert!(LogCursor::parse("time:yesterday").is_err());
+    /// ```
+    ///
+    pub fn parse(cursor: &str) -> Result<LogCursor, Report> {
+        let invalid = || eyre!("Invalid log cursor \"{}\"", cursor);
+
+        match cursor.split_once(':') {
+            Some(("offset", offset)) => offset
+                .parse::<u64>()
+                .map(LogCursor::Offset)
+                .map_err(|_| invalid()),
+            Some(("time", time)) => DateTime::parse_from_rfc3339(time)
+                .map(|time| LogCursor::Time(time.with_timezone(&Utc)))
+                .map_err(|_| invalid()),
+            _ => Err(invalid()),
+        }
+    }
+
+    /// Byte offset of a cursor returned by runtimes reading logs from a file or from memory
+    pub fn offset(cursor: Option<&LogCursor>) -> Result<u64, Report> {
+        match cursor {
+            None => Ok(0),
+            Some(LogCursor::Offset(offset)) => Ok(*offset),
+            Some(cursor) => Err(eyre!(
+                "Invalid log cursor \"{}\" (expecting a byte offset)",
+                cursor
+            )),
+        }
+    }
+
+    /// Time of a cursor returned by the Docker runtime
+    pub fn time(cursor: Option<&LogCursor>) -> Result<Option<DateTime<Utc>>, Report> {
+        match cursor {
+            None => Ok(None),
+            Some(LogCursor::Time(time)) => Ok(Some(*time)),
+            Some(cursor) => Err(eyre!(
+                "Invalid log cursor \"{}\" (expecting a time)",
+                cursor
+            )),
+        }
+    }
+}
+
+impl fmt::Display for LogCursor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LogCursor::Offset(offset) => write!(f, "offset:{}", offset),
+            LogCursor::Time(time) => write!(
+                f,
+                "time:{}",
+                time.to_rfc3339_opts(SecondsFormat::AutoSi, true)
+            ),
+        }
+    }
+}
+
+#[test]
+fn it_rejects_cursors_of_another_runtime() {
+    let time = LogCursor::parse("time:2021-11-02T10:12:45Z").unwrap();
+
+    assert_eq!(0, LogCursor::offset(None).unwrap());
+    assert_eq!(15, LogCursor::offset(Some(&LogCursor::Offset(15))).unwrap());
+    assert_eq!(
+        "Invalid log cursor \"time:2021-11-02T10:12:45Z\" (expecting a byte offset)",
+        LogCursor::offset(Some(&time)).unwrap_err().to_string()
+    );
+    assert_eq!(
+        1_635_847_965,
+        LogCursor::time(Some(&time)).unwrap().unwrap().timestamp()
+    );
+    assert!(LogCursor::time(Some(&LogCursor::Offset(15))).is_err());
+}
@@ -0,0 +1,70 @@
+use serde::{Deserialize, Serialize};
+
+/// Interpretation of how a step ended
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    Succeeded,
+    VerificationFailed,
+    InfrastructureError,
+}
+
+impl Outcome {
+    /// Interpret the status, exit code and OOM state of a step,
+    /// which has no outcome as long as it is running.
+    ///
+    /// Exit codes from 125 up are reserved to failures of the runtime
+    /// (container not started, command not executable or not found, process killed by a signal),
+    /// other non-zero exit codes being returned by failing verifications.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use safepkt_backend::app::domain::value_object::Outcome;
+    ///
+    /// assert_eq!(Some(Outcome::Succeeded), Outcome::interpret("exited", Some(0), false));
+    /// assert_eq!(Some(Outcome::VerificationFailed), Outcome::interpret("exited", Some(1), false));
+    /// assert_eq!(Some(Outcome::InfrastructureError), Outcome::interpret("exited", Some(137), true));
+    /// assert_eq!(None, Outcome::interpret("running", None, false));
+    /// ```
+    ///
+    pub fn interpret(status: &str, exit_code: Option<i64>, oom_killed: bool) -> Option<Outcome> {
+        match status {
+            "created" | "running" | "restarting" | "paused" => return None,
+            "oom_killed" | "timed_out" | "dead" => return Some(Outcome::InfrastructureError),
+            _ => {}
+        }
+
+        if oom_killed {
+            return Some(Outcome::InfrastructureError);
+        }
+
+        match exit_code {
+            Some(0) => Some(Outcome::Succeeded),
+            Some(exit_code) if exit_code > 0 && exit_code < 125 => {
+                Some(Outcome::VerificationFailed)
+            }
+            _ => Some(Outcome::InfrastructureError),
+        }
+    }
+
+    /// Parse an outcome as reported by the progress of a step (e.g. "verification_failed")
+    pub fn parse(outcome: &str) -> Option<Outcome> {
+        [
+            Outcome::Succeeded,
+            Outcome::VerificationFailed,
+            Outcome::InfrastructureError,
+        ]
+        .iter()
+        .copied()
+        .find(|candidate| candidate.as_str() == outcome)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Outcome::Succeeded => "succeeded",
+            Outcome::VerificationFailed => "verification_failed",
+            Outcome::InfrastructureError => "infrastructure_error",
+        }
+    }
+}
@@ -0,0 +1,436 @@
+use crate::domain::value_object::{Outcome, StepReport};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// Step of a pipeline, started once the steps it depends on have ended
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct PipelineStage {
+    pub step: String,
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StageDefinition {
+    Step(String),
+    Stage(PipelineStage),
+}
+
+fn default_stop_on() -> Vec<Outcome> {
+    vec![Outcome::VerificationFailed, Outcome::InfrastructureError]
+}
+
+/// Pipeline requested for a project, declared either as an ordered list of step names
+/// (each step depending on the previous one) or as stages declaring their dependencies,
+/// along with the outcomes stopping the pipeline (failures by default).
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PipelineDefinition {
+    steps: Vec<StageDefinition>,
+    #[serde(default = "default_stop_on")]
+    stop_on: Vec<Outcome>,
+}
+
+/// Validated pipeline of steps run against a project
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pipeline {
+    project_id: String,
+    stages: Vec<PipelineStage>,
+    stop_on: Vec<Outcome>,
+}
+
+impl Pipeline {
+    /// Validate a pipeline against the names of the steps known by the backend,
+    /// rejecting duplicate steps, unknown dependencies and dependency cycles.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use safepkt_backend::app::domain::value_object::{Pipeline, PipelineDefinition};
+    ///
+    /// let steps = ["program_verification", "program_fuzzing"];
+    ///
+    /// let definition: PipelineDefinition =
+    ///     serde_json::from_str(r#"{"steps": ["program_verification", "program_fuzzing"]}"#).unwrap();
+    /// let pipeline = Pipeline::new("47a9690570".to_string(), definition, &steps).unwrap();
+    /// assert_eq!(vec!["program_verification"], pipeline.stages()[1].depends_on);
+    ///
+    /// let definition: PipelineDefinition =
+    ///     serde_json::from_str(r#"{"steps": ["program_verification", "program_miri"]}"#).unwrap();
+    /// assert_eq!(
+    ///     "There is no step named \"program_miri\"",
+    ///     Pipeline::new("47a9690570".to_string(), definition, &steps).unwrap_err()
+    /// );
+    /// ```
+    ///
+    pub fn new(
+        project_id: String,
+        definition: PipelineDefinition,
+        step_names: &[&str],
+    ) -> Result<Self, String> {
+        if definition.steps.is_empty() {
+            return Err("A pipeline requires at least one step".to_string());
+        }
+
+        let mut stages: Vec<PipelineStage> = vec![];
+        for stage_definition in definition.steps {
+            let stage = match stage_definition {
+                StageDefinition::Step(step) => PipelineStage {
+                    step,
+                    depends_on: stages
+                        .last()
+                        .map(|previous| vec![previous.step.clone()])
+                        .unwrap_or_default(),
+                },
+                StageDefinition::Stage(stage) => stage,
+            };
+
+            if !step_names.contains(&stage.step.as_str()) {
+                return Err(format!("There is no step named \"{}\"", stage.step));
+            }
+            if stages.iter().any(|other| other.step == stage.step) {
+                return Err(format!(
+                    "Step \"{}\" appears more than once in the pipeline",
+                    stage.step
+                ));
+            }
+
+            stages.push(stage);
+        }
+
+        let declared: HashSet<&str> = stages.iter().map(|stage| stage.step.as_str()).collect();
+        for stage in stages.iter() {
+            if let Some(dependency) = stage
+                .depends_on
+                .iter()
+                .find(|dependency| !declared.contains(dependency.as_str()))
+            {
+                return Err(format!(
+                    "Step \"{}\" depends on \"{}\", which is not part of the pipeline",
+                    stage.step, dependency
+                ));
+            }
+        }
+
+        // Steps are removed once their dependencies have been,
+        // steps left over being part of a cycle
+        let mut remaining: Vec<&PipelineStage> = stages.iter().collect();
+        let mut ordered: HashSet<&str> = HashSet::new();
+        while !remaining.is_empty() {
+            let (ready, blocked): (Vec<&PipelineStage>, Vec<&PipelineStage>) =
+                remaining.into_iter().partition(|stage| {
+                    stage
+                        .depends_on
+                        .iter()
+                        .all(|dependency| ordered.contains(dependency.as_str()))
+                });
+
+            if ready.is_empty() {
+                let steps: Vec<&str> = blocked.iter().map(|stage| stage.step.as_str()).collect();
+
+                return Err(format!(
+                    "Steps {} depend on each other",
+                    steps
+                        .iter()
+                        .map(|step| format!("\"{}\"", step))
+                        .collect::<Vec<String>>()
+                        .join(", ")
+                ));
+            }
+
+            ordered.extend(ready.iter().map(|stage| stage.step.as_str()));
+            remaining = blocked;
+        }
+
+        Ok(Pipeline {
+            project_id,
+            stages,
+            stop_on: definition.stop_on,
+        })
+    }
+
+    pub fn project_id(&self) -> &str {
+        self.project_id.as_str()
+    }
+
+    pub fn stages(&self) -> &[PipelineStage] {
+        &self.stages
+    }
+
+    pub fn stop_on(&self) -> &[Outcome] {
+        &self.stop_on
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StageStatus {
+    Pending,
+    Running,
+    Ended,
+    Skipped,
+    Stopped,
+}
+
+/// Progress of a step run as part of a pipeline
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct StageProgress {
+    pub step: String,
+    pub run_id: String,
+    pub depends_on: Vec<String>,
+    pub status: StageStatus,
+    pub outcome: Option<Outcome>,
+    pub message: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PipelineStatus {
+    Running,
+    Completed,
+    Stopped,
+}
+
+/// Aggregate progress of a pipeline,
+/// its outcome being the worst outcome of its steps once it is no longer running
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct PipelineProgress {
+    pub project_id: String,
+    pub status: PipelineStatus,
+    pub outcome: Option<Outcome>,
+    pub stop_on: Vec<Outcome>,
+    pub stages: Vec<StageProgress>,
+}
+
+fn severity(outcome: Outcome) -> u8 {
+    match outcome {
+        Outcome::Succeeded => 0,
+        Outcome::VerificationFailed => 1,
+        Outcome::InfrastructureError => 2,
+    }
+}
+
+impl PipelineProgress {
+    /// Start following up a pipeline, the id of the run of each step being formatted by the caller
+    pub fn new<F: Fn(&str) -> String>(pipeline: &Pipeline, format_run_id: F) -> Self {
+        PipelineProgress {
+            project_id: pipeline.project_id().to_string(),
+            status: PipelineStatus::Running,
+            outcome: None,
+            stop_on: pipeline.stop_on().to_vec(),
+            stages: pipeline
+                .stages()
+                .iter()
+                .map(|stage| StageProgress {
+                    step: stage.step.clone(),
+                    run_id: format_run_id(stage.step.as_str()),
+                    depends_on: stage.depends_on.clone(),
+                    status: StageStatus::Pending,
+                    outcome: None,
+                    message: None,
+                })
+                .collect(),
+        }
+    }
+
+    fn statuses(&self) -> HashMap<&str, StageStatus> {
+        self.stages
+            .iter()
+            .map(|stage| (stage.step.as_str(), stage.status))
+            .collect()
+    }
+
+    /// Indices of the stages having a given status
+    pub fn stages_with_status(&self, status: StageStatus) -> Vec<usize> {
+        self.stages
+            .iter()
+            .enumerate()
+            .filter(|(_, stage)| stage.status == status)
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// Indices of the pending stages which dependencies have all ended,
+    /// pending stages depending on a skipped or stopped step being skipped.
+    pub fn ready_stages(&mut self) -> Vec<usize> {
+        if self.status != PipelineStatus::Running {
+            return vec![];
+        }
+
+        loop {
+            let statuses = self.statuses();
+            let unreachable: Vec<usize> = self
+                .stages_with_status(StageStatus::Pending)
+                .into_iter()
+                .filter(|index| {
+                    self.stages[*index].depends_on.iter().any(|dependency| {
+                        matches!(
+                            statuses.get(dependency.as_str()),
+                            Some(StageStatus::Skipped) | Some(StageStatus::Stopped)
+                        )
+                    })
+                })
+                .collect();
+
+            if unreachable.is_empty() {
+                break;
+            }
+
+            for index in unreachable {
+                self.stages[index].status = StageStatus::Skipped;
+            }
+        }
+
+        let statuses = self.statuses();
+        self.stages_with_status(StageStatus::Pending)
+            .into_iter()
+            .filter(|index| {
+                self.stages[*index].depends_on.iter().all(|dependency| {
+                    statuses.get(dependency.as_str()) == Some(&StageStatus::Ended)
+                })
+            })
+            .collect()
+    }
+
+    pub fn start_stage(&mut self, index: usize) {
+        self.stages[index].status = StageStatus::Running;
+    }
+
+    /// End a stage, returning whether its outcome stops the pipeline
+    pub fn end_stage(&mut self, index: usize, outcome: Outcome, message: Option<String>) -> bool {
+        let stage = &mut self.stages[index];
+        stage.status = StageStatus::Ended;
+        stage.outcome = Some(outcome);
+        stage.message = message;
+
+        self.stop_on.contains(&outcome)
+    }
+
+    /// Stop the pipeline, skipping pending stages
+    /// before returning the indices of the running ones, which are to be stopped
+    pub fn stop(&mut self) -> Vec<usize> {
+        for index in self.stages_with_status(StageStatus::Pending) {
+            self.stages[index].status = StageStatus::Skipped;
+        }
+
+        let running = self.stages_with_status(StageStatus::Running);
+        for index in running.iter() {
+            self.stages[*index].status = StageStatus::Stopped;
+        }
+
+        if self.status == PipelineStatus::Running {
+            self.status = PipelineStatus::Stopped;
+        }
+        self.refresh_outcome();
+
+        running
+    }
+
+    /// Complete the pipeline once none of its stages is pending or running
+    pub fn refresh_status(&mut self) -> PipelineStatus {
+        let is_active = self
+            .stages
+            .iter()
+            .any(|stage| matches!(stage.status, StageStatus::Pending | StageStatus::Running));
+
+        if self.status == PipelineStatus::Running && !is_active {
+            self.status = PipelineStatus::Completed;
+            self.refresh_outcome();
+        }
+
+        self.status
+    }
+
+    fn refresh_outcome(&mut self) {
+        self.outcome = self
+            .stages
+            .iter()
+            .filter_map(|stage| stage.outcome)
+            .max_by_key(|outcome| severity(*outcome));
+    }
+}
+
+/// Progress of a pipeline along with the reports of the steps it has started
+#[derive(Debug, Serialize)]
+pub struct PipelineReport {
+    #[serde(flatten)]
+    pub progress: PipelineProgress,
+    pub reports: Vec<StepReport>,
+}
+
+#[test]
+fn it_rejects_invalid_pipelines() {
+    let steps = ["program_verification", "program_fuzzing", "program_linting"];
+    let pipeline = |json: &str| {
+        let definition: PipelineDefinition = serde_json::from_str(json).unwrap();
+
+        Pipeline::new("47a9690570".to_string(), definition, &steps)
+    };
+
+    assert_eq!(
+        "A pipeline requires at least one step",
+        pipeline(r#"{"steps": []}"#).unwrap_err()
+    );
+    assert_eq!(
+        "Step \"program_fuzzing\" appears more than once in the pipeline",
+        pipeline(r#"{"steps": ["program_fuzzing", "program_fuzzing"]}"#).unwrap_err()
+    );
+    assert_eq!(
+        "Step \"program_fuzzing\" depends on \"program_linting\", which is not part of the pipeline",
+        pipeline(r#"{"steps": [{"step": "program_fuzzing", "depends_on": ["program_linting"]}]}"#)
+            .unwrap_err()
+    );
+    assert_eq!(
+        "Steps \"program_verification\", \"program_fuzzing\" depend on each other",
+        pipeline(
+            r#"{"steps": [
+                "program_linting",
+                {"step": "program_verification", "depends_on": ["program_fuzzing"]},
+                {"step": "program_fuzzing", "depends_on": ["program_verification"]}
+            ]}"#
+        )
+        .unwrap_err()
+    );
+
+    let pipeline = pipeline(
+        r#"{"steps": [
+            "program_linting",
+            {"step": "program_verification"},
+            {"step": "program_fuzzing", "depends_on": ["program_linting", "program_verification"]}
+        ], "stop_on": ["infrastructure_error"]}"#,
+    )
+    .unwrap();
+    assert!(pipeline.stages()[1].depends_on.is_empty());
+    assert_eq!(&[Outcome::InfrastructureError], pipeline.stop_on());
+}
+
+#[test]
+fn it_follows_up_stages_of_pipelines() {
+    let steps = ["program_verification", "program_fuzzing", "program_linting"];
+    let definition: PipelineDefinition = serde_json::from_str(
+        r#"{"steps": [
+            "program_verification",
+            "program_fuzzing",
+            {"step": "program_linting"}
+        ]}"#,
+    )
+    .unwrap();
+    let pipeline = Pipeline::new("abc".to_string(), definition, &steps).unwrap();
+
+    let mut progress = PipelineProgress::new(&pipeline, |step| format!("{}-abc", step));
+    assert_eq!("program_fuzzing-abc", progress.stages[1].run_id);
+    assert_eq!(vec![0, 2], progress.ready_stages());
+
+    progress.start_stage(0);
+    progress.start_stage(2);
+    assert!(progress.ready_stages().is_empty());
+    assert!(!progress.end_stage(2, Outcome::Succeeded, None));
+    assert!(progress.end_stage(0, Outcome::VerificationFailed, None));
+
+    assert_eq!(vec![] as Vec<usize>, progress.stop());
+    assert_eq!(StageStatus::Skipped, progress.stages[1].status);
+    assert_eq!(PipelineStatus::Stopped, progress.refresh_status());
+    assert_eq!(Some(Outcome::VerificationFailed), progress.outcome);
+}
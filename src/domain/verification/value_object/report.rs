@@ -0,0 +1,372 @@
+use serde::Serialize;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Verdict {
+    Passed,
+    Failed,
+    Unknown,
+}
+
+/// Reason why a step did not run to completion
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Interruption {
+    OomKilled,
+    TimedOut,
+}
+
+impl Interruption {
+    pub fn from_status(status: &str) -> Option<Interruption> {
+        match status {
+            "oom_killed" => Some(Interruption::OomKilled),
+            "timed_out" => Some(Interruption::TimedOut),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Finding {
+    message: String,
+    line: Option<usize>,
+}
+
+impl Finding {
+    pub fn new(message: String, line: Option<usize>) -> Finding {
+        Finding { message, line }
+    }
+
+    pub fn message(&self) -> &str {
+        self.message.as_str()
+    }
+
+    pub fn line(&self) -> Option<usize> {
+        self.line
+    }
+}
+
+/// Statistics of a KLEE run for an entry point,
+/// telling how much of the program has actually been explored.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct KleeStatistics {
+    pub completed_paths: Option<u64>,
+    pub partially_completed_paths: Option<u64>,
+    pub generated_tests: Option<u64>,
+    pub total_instructions: Option<u64>,
+    pub instruction_coverage: Option<f64>,
+    pub branch_coverage: Option<f64>,
+    pub solver_time: Option<f64>,
+    pub total_time: Option<f64>,
+}
+
+fn add_optional<T: std::ops::Add<Output = T>>(left: Option<T>, right: Option<T>) -> Option<T> {
+    match (left, right) {
+        (Some(left), Some(right)) => Some(left + right),
+        (left, None) => left,
+        (None, right) => right,
+    }
+}
+
+impl KleeStatistics {
+    /// A run is exhaustive when no path has been left partially explored,
+    /// which can only be told when KLEE has reported paths.
+    pub fn is_exhaustive(&self) -> Option<bool> {
+        match (self.completed_paths, self.partially_completed_paths) {
+            (Some(_), Some(partial)) => Some(partial == 0),
+            (Some(_), None) => Some(true),
+            _ => None,
+        }
+    }
+
+    /// Sum counters and durations of two runs.
+    /// Coverage is not additive across entry points,
+    /// the lowest one is kept.
+    pub fn merge(&self, other: &KleeStatistics) -> KleeStatistics {
+        let lowest = |left: Option<f64>, right: Option<f64>| match (left, right) {
+            (Some(left), Some(right)) => Some(left.min(right)),
+            (left, None) => left,
+            (None, right) => right,
+        };
+
+        KleeStatistics {
+            completed_paths: add_optional(self.completed_paths, other.completed_paths),
+            partially_completed_paths: add_optional(
+                self.partially_completed_paths,
+                other.partially_completed_paths,
+            ),
+            generated_tests: add_optional(self.generated_tests, other.generated_tests),
+            total_instructions: add_optional(self.total_instructions, other.total_instructions),
+            instruction_coverage: lowest(self.instruction_coverage, other.instruction_coverage),
+            branch_coverage: lowest(self.branch_coverage, other.branch_coverage),
+            solver_time: add_optional(self.solver_time, other.solver_time),
+            total_time: add_optional(self.total_time, other.total_time),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct TestReport {
+    name: String,
+    verdict: Verdict,
+    summary: Vec<String>,
+    findings: Vec<Finding>,
+    counterexamples: Vec<String>,
+    statistics: Option<KleeStatistics>,
+    exhaustive: Option<bool>,
+}
+
+impl TestReport {
+    pub fn new(name: String) -> TestReport {
+        TestReport {
+            name,
+            verdict: Verdict::Unknown,
+            summary: vec![],
+            findings: vec![],
+            counterexamples: vec![],
+            statistics: None,
+            exhaustive: None,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    pub fn verdict(&self) -> Verdict {
+        self.verdict
+    }
+
+    pub fn set_verdict(&mut self, verdict: Verdict) {
+        self.verdict = verdict;
+    }
+
+    pub fn summary(&self) -> &[String] {
+        &self.summary
+    }
+
+    pub fn add_summary_line(&mut self, line: String) {
+        self.summary.push(line);
+    }
+
+    pub fn findings(&self) -> &[Finding] {
+        &self.findings
+    }
+
+    pub fn add_finding(&mut self, finding: Finding) {
+        self.findings.push(finding);
+    }
+
+    pub fn counterexamples(&self) -> &[String] {
+        &self.counterexamples
+    }
+
+    pub fn add_counterexample(&mut self, counterexample: String) {
+        self.counterexamples.push(counterexample);
+    }
+
+    pub fn statistics(&self) -> Option<&KleeStatistics> {
+        self.statistics.as_ref()
+    }
+
+    pub fn set_statistics(&mut self, statistics: KleeStatistics) {
+        self.exhaustive = statistics.is_exhaustive();
+        self.statistics = Some(statistics);
+    }
+}
+
+/// Location of a diagnostic in the uploaded source
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct SourceSpan {
+    pub file: String,
+    pub line_start: usize,
+    pub line_end: usize,
+    pub column_start: usize,
+    pub column_end: usize,
+}
+
+/// Fix suggested by a linter, as a replacement of a span of the uploaded source
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Suggestion {
+    pub message: String,
+    pub replacement: String,
+    pub span: SourceSpan,
+    pub applicability: Option<String>,
+}
+
+/// Diagnostic reported by static analysis (e.g. clippy lints)
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Diagnostic {
+    pub lint: Option<String>,
+    pub level: String,
+    pub message: String,
+    pub span: SourceSpan,
+    pub suggestion: Option<Suggestion>,
+}
+
+/// Location printed by the Rust compiler or Miri (e.g. src/lib.rs:10:14)
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct SourceLocation {
+    pub file: String,
+    pub line: usize,
+    pub column: usize,
+}
+
+/// Undefined behaviour (or unsupported operation) detected by Miri while running a test
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct UndefinedBehavior {
+    pub kind: String,
+    pub message: String,
+    pub test: Option<String>,
+    pub location: Option<SourceLocation>,
+    pub backtrace: Vec<String>,
+}
+
+/// Advisory of the RustSec database affecting a dependency of the project
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Advisory {
+    pub id: String,
+    pub kind: String,
+    pub package: String,
+    pub version: String,
+    pub title: String,
+    pub date: Option<String>,
+    pub url: Option<String>,
+    pub patched_versions: Vec<String>,
+    pub unaffected_versions: Vec<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct StepReport {
+    run_id: String,
+    step: String,
+    project_id: String,
+    tests: Vec<TestReport>,
+    statistics: Option<KleeStatistics>,
+    diagnostics: Vec<Diagnostic>,
+    undefined_behaviors: Vec<UndefinedBehavior>,
+    advisories: Vec<Advisory>,
+    interruption: Option<Interruption>,
+}
+
+impl StepReport {
+    pub fn new(
+        run_id: String,
+        step: String,
+        project_id: String,
+        tests: Vec<TestReport>,
+    ) -> StepReport {
+        let statistics = tests.iter().filter_map(|test| test.statistics()).fold(
+            None,
+            |total: Option<KleeStatistics>, statistics| match total {
+                Some(total) => Some(total.merge(statistics)),
+                None => Some(statistics.clone()),
+            },
+        );
+
+        StepReport {
+            run_id,
+            step,
+            project_id,
+            tests,
+            statistics,
+            diagnostics: vec![],
+            undefined_behaviors: vec![],
+            advisories: vec![],
+            interruption: None,
+        }
+    }
+
+    pub fn run_id(&self) -> &str {
+        self.run_id.as_str()
+    }
+
+    pub fn step(&self) -> &str {
+        self.step.as_str()
+    }
+
+    pub fn project_id(&self) -> &str {
+        self.project_id.as_str()
+    }
+
+    pub fn tests(&self) -> &[TestReport] {
+        &self.tests
+    }
+
+    /// Statistics aggregated over all entry points
+    pub fn statistics(&self) -> Option<&KleeStatistics> {
+        self.statistics.as_ref()
+    }
+
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    pub fn set_diagnostics(&mut self, diagnostics: Vec<Diagnostic>) {
+        self.diagnostics = diagnostics;
+    }
+
+    pub fn undefined_behaviors(&self) -> &[UndefinedBehavior] {
+        &self.undefined_behaviors
+    }
+
+    pub fn set_undefined_behaviors(&mut self, undefined_behaviors: Vec<UndefinedBehavior>) {
+        self.undefined_behaviors = undefined_behaviors;
+    }
+
+    pub fn advisories(&self) -> &[Advisory] {
+        &self.advisories
+    }
+
+    pub fn set_advisories(&mut self, advisories: Vec<Advisory>) {
+        self.advisories = advisories;
+    }
+
+    /// Partial results are reported when a step has been interrupted
+    pub fn interruption(&self) -> Option<Interruption> {
+        self.interruption
+    }
+
+    pub fn set_interruption(&mut self, interruption: Option<Interruption>) {
+        self.interruption = interruption;
+    }
+
+    /// Lines of the uploaded source referenced by at least one finding
+    pub fn failing_lines(&self) -> Vec<usize> {
+        let mut lines: Vec<usize> = self
+            .tests
+            .iter()
+            .flat_map(|test| test.findings().iter().filter_map(|f| f.line()))
+            .collect();
+        lines.sort_unstable();
+        lines.dedup();
+
+        lines
+    }
+}
+
+#[test]
+fn it_merges_klee_statistics() {
+    let left = KleeStatistics {
+        completed_paths: Some(3),
+        partially_completed_paths: Some(0),
+        instruction_coverage: Some(80.0),
+        total_time: Some(1.5),
+        ..Default::default()
+    };
+    let right = KleeStatistics {
+        completed_paths: Some(1),
+        partially_completed_paths: Some(2),
+        instruction_coverage: Some(20.0),
+        ..Default::default()
+    };
+
+    let merged = left.merge(&right);
+
+    assert_eq!(Some(4), merged.completed_paths);
+    assert_eq!(Some(2), merged.partially_completed_paths);
+    assert_eq!(Some(20.0), merged.instruction_coverage);
+    assert_eq!(Some(1.5), merged.total_time);
+    assert_eq!(Some(true), left.is_exhaustive());
+    assert_eq!(Some(false), merged.is_exhaustive());
+}
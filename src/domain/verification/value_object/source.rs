@@ -3,14 +3,40 @@ use serde::{Deserialize, Serialize};
 #[derive(Serialize, Deserialize)]
 pub struct Source<'a> {
     source: &'a [u8],
+    /// Kind of project ("ink_contract", "library" or "binary", detected from the source unless chosen)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    kind: Option<String>,
+    /// Version of ink! the source depends on (detected from the source unless chosen)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ink_version: Option<String>,
+    /// Example of ink! the project is scaffolded from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    template: Option<String>,
 }
 
 impl Source<'_> {
-    pub fn new(&self, source: &[u8]) {
-        Source { source };
+    pub fn new(source: &[u8]) -> Source<'_> {
+        Source {
+            source,
+            kind: None,
+            ink_version: None,
+            template: None,
+        }
     }
 
     pub fn source(&self) -> &[u8] {
         self.source
     }
+
+    pub fn kind(&self) -> Option<&str> {
+        self.kind.as_deref()
+    }
+
+    pub fn ink_version(&self) -> Option<&str> {
+        self.ink_version.as_deref()
+    }
+
+    pub fn template(&self) -> Option<&str> {
+        self.template.as_deref()
+    }
 }
@@ -1,51 +1,24 @@
 use std::collections::HashMap;
+use std::time::Duration;
 
-pub type StepProvider = for<'a> fn(&'a str, &'a str, Option<&'a str>) -> String;
-pub type FlagsProvider = fn() -> String;
+use super::{BackendSelection, StepDefinition};
 
 #[derive(Copy, Clone)]
 pub struct Step<'a> {
-    name: &'a str,
-    step_provider: StepProvider,
-    flags: Option<&'a str>,
+    definition: &'a StepDefinition,
 }
 
 impl Step<'_> {
-    pub fn new<'a>(name: &'a str, step_provider: StepProvider, flags: Option<&'a str>) -> Step<'a> {
-        match flags {
-            Some(flags) => {
-                if flags.len() == 0 {
-                    return Step {
-                        name,
-                        step_provider,
-                        flags: None,
-                    };
-                }
-
-                Step {
-                    name,
-                    step_provider,
-                    flags: Some(flags),
-                }
-            }
-            None => Step {
-                name,
-                step_provider,
-                flags: None,
-            },
-        }
+    pub fn new(definition: &StepDefinition) -> Step<'_> {
+        Step { definition }
     }
 
     pub fn name(&self) -> &str {
-        &self.name
-    }
-
-    pub fn step_provider(&self) -> &StepProvider {
-        &self.step_provider
+        self.definition.name()
     }
 
-    pub fn flags(&self) -> Option<&str> {
-        self.flags
+    pub fn definition(&self) -> &StepDefinition {
+        self.definition
     }
 }
 
@@ -58,25 +31,72 @@ impl VerificationStepsCollection<'_> {
         VerificationStepsCollection { steps }
     }
 
-    pub fn step(&self, name: &String) -> &Step {
-        &self.steps.get(name.as_str()).unwrap()
+    pub fn step(&self, name: &str) -> &Step<'_> {
+        self.steps.get(name).unwrap()
     }
 }
 
 pub struct StepInVerificationPlan<'a> {
     pub project_id: String,
     pub step: Step<'a>,
+    pub time_budget: Option<Duration>,
+    pub fresh_run: bool,
+    pub backend: Option<BackendSelection>,
 }
 
 impl StepInVerificationPlan<'_> {
     pub fn new(project_id: String, step: Step) -> StepInVerificationPlan {
-        StepInVerificationPlan { project_id, step }
+        StepInVerificationPlan {
+            project_id,
+            step,
+            time_budget: None,
+            fresh_run: false,
+            backend: None,
+        }
+    }
+
+    /// Set the wall-clock time a step is allowed to run for
+    pub fn with_time_budget(mut self, time_budget: Option<Duration>) -> Self {
+        self.time_budget = time_budget;
+        self
+    }
+
+    /// Run a step even when results of a previous run have been cached
+    pub fn with_fresh_run(mut self, fresh_run: bool) -> Self {
+        self.fresh_run = fresh_run;
+        self
+    }
+
+    /// Run a verification step with a backend other than its default one
+    pub fn with_backend(mut self, backend: Option<BackendSelection>) -> Self {
+        self.backend = backend;
+        self
     }
 
     pub fn project_id(&self) -> &String {
         &self.project_id
     }
-    pub fn step(&self) -> &Step {
+
+    pub fn step(&self) -> &Step<'_> {
         &self.step
     }
+
+    pub fn time_budget(&self) -> Option<Duration> {
+        self.time_budget
+    }
+
+    pub fn fresh_run(&self) -> bool {
+        self.fresh_run
+    }
+
+    /// Backend a step runs with: the one requested or the default one of the step,
+    /// steps which do not run `cargo verify` having none.
+    pub fn backend(&self) -> Option<BackendSelection> {
+        self.backend.clone().or_else(|| {
+            self.step
+                .definition()
+                .backend()
+                .and_then(|backend| BackendSelection::new(backend, &Default::default()).ok())
+        })
+    }
 }
@@ -0,0 +1,173 @@
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+use super::StepCommand;
+
+/// Parser applied to the logs of steps running the verification script
+pub static KLEE_PARSER: &str = "klee";
+/// Parser turning clippy JSON messages into diagnostics
+pub static CLIPPY_PARSER: &str = "clippy";
+/// Parser turning Miri reports into undefined behaviours
+pub static MIRI_PARSER: &str = "miri";
+/// Parser turning cargo-audit JSON reports into advisories
+pub static AUDIT_PARSER: &str = "audit";
+/// Parser leaving logs of a step as they are
+pub static NO_PARSER: &str = "none";
+
+fn default_read_only() -> bool {
+    true
+}
+
+fn default_scaffold() -> bool {
+    true
+}
+
+fn default_parser() -> String {
+    NO_PARSER.to_string()
+}
+
+/// Replace the placeholders of a template (e.g. "{project_id}") with their values.
+///
+/// # Examples
+///
+/// ```
+/// use safepkt_backend::app::domain::value_object::render_template;
+///
+/// assert_eq!(
+///     "/uploaded-sources/47a9690570.rs.b64",
+///     render_template("/uploaded-sources/{project_id}.rs.b64", &[("project_id", "47a9690570")])
+/// );
+/// ```
+///
+pub fn render_template(template: &str, variables: &[(&str, &str)]) -> String {
+    variables
+        .iter()
+        .fold(template.to_string(), |rendered, (name, value)| {
+            rendered.replace(format!("{{{}}}", name).as_str(), value)
+        })
+}
+
+/// Host path mounted in the container of a step
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct MountDefinition {
+    source: String,
+    target: String,
+    #[serde(default = "default_read_only")]
+    read_only: bool,
+}
+
+impl MountDefinition {
+    pub fn source(&self) -> &str {
+        self.source.as_str()
+    }
+
+    pub fn target(&self) -> &str {
+        self.target.as_str()
+    }
+
+    pub fn read_only(&self) -> bool {
+        self.read_only
+    }
+}
+
+/// Resources the container of a step is allowed to consume (e.g. memory = "8g"),
+/// overriding limits declared for all steps.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct StepLimits {
+    pub memory: Option<String>,
+    pub cpus: Option<String>,
+    pub pids: Option<String>,
+    pub tmpfs_size: Option<String>,
+    pub disk_size: Option<String>,
+}
+
+/// Step as declared in a registry:
+/// the command it runs, the image it runs in, what it mounts,
+/// whether it needs a scaffolded project, how its logs are parsed
+/// and the verifier backend it runs with by default.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct StepDefinition {
+    name: String,
+    #[serde(default)]
+    image: Option<String>,
+    argv: Vec<String>,
+    #[serde(default)]
+    env: BTreeMap<String, String>,
+    #[serde(default)]
+    mounts: Vec<MountDefinition>,
+    #[serde(default)]
+    limits: StepLimits,
+    #[serde(default = "default_scaffold")]
+    scaffold: bool,
+    #[serde(default = "default_parser")]
+    parser: String,
+    #[serde(default)]
+    backend: Option<String>,
+}
+
+impl StepDefinition {
+    pub fn name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    /// Image the step is run from, when it differs from the default one
+    pub fn image(&self) -> Option<&str> {
+        self.image.as_deref()
+    }
+
+    pub fn argv(&self) -> &[String] {
+        self.argv.as_slice()
+    }
+
+    pub fn env(&self) -> &BTreeMap<String, String> {
+        &self.env
+    }
+
+    pub fn mounts(&self) -> &[MountDefinition] {
+        self.mounts.as_slice()
+    }
+
+    pub fn limits(&self) -> &StepLimits {
+        &self.limits
+    }
+
+    pub fn requires_scaffolding(&self) -> bool {
+        self.scaffold
+    }
+
+    pub fn parser(&self) -> &str {
+        self.parser.as_str()
+    }
+
+    /// Backend of `cargo verify` the step runs with unless another one is requested
+    pub fn backend(&self) -> Option<&str> {
+        self.backend.as_deref()
+    }
+
+    /// Render the command of the step,
+    /// arguments and environment variables rendered empty being left out.
+    pub fn command(&self, variables: &[(&str, &str)]) -> StepCommand {
+        let mut argv = self
+            .argv
+            .iter()
+            .map(|argument| render_template(argument, variables))
+            .filter(|argument| !argument.is_empty());
+
+        let mut command = StepCommand::new(argv.next().unwrap_or_default().as_str());
+        for argument in argv {
+            command = command.arg(argument.as_str());
+        }
+
+        for (name, value) in self.env.iter() {
+            let value = render_template(value, variables);
+            if !value.is_empty() {
+                command = command.env(name, value.as_str());
+            }
+        }
+
+        command
+    }
+}
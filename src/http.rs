@@ -3,7 +3,10 @@ use color_eyre::Report;
 use hyper::Server;
 use safepkt_backend::app::middleware;
 use safepkt_backend::app::router;
-use safepkt_backend::infra::signal_handling;
+use safepkt_backend::infra::garbage_collection;
+use safepkt_backend::infra::pipeline;
+use safepkt_backend::infra::step_registry;
+use safepkt_backend::infra::{default_container_api_client_factory, signal_handling};
 use std::env;
 use std::net::SocketAddr;
 use tracing::{error, info};
@@ -23,8 +26,28 @@ async fn main() -> Result<(), Report> {
     let hostname_port = format!("{}:{}", host_ip_address, port);
     let addr: SocketAddr = hostname_port.as_str().parse()?;
 
+    let registry = step_registry::step_registry()?;
+    info!(
+        "Loaded {} steps from the step registry",
+        registry.steps().len()
+    );
+
     let router = router::new_router().unwrap();
 
+    if garbage_collection::spawn_garbage_collection(default_container_api_client_factory())?
+        .is_some()
+    {
+        info!("Collecting garbage in the background");
+    }
+
+    let resumed_pipelines = pipeline::resume_pipelines(
+        default_container_api_client_factory(),
+        pipeline::DEFAULT_POLL_INTERVAL,
+    )?;
+    if !resumed_pipelines.is_empty() {
+        info!("Resumed {} pipelines", resumed_pipelines.len());
+    }
+
     info!(
         "About to listen to address {} and port {}",
         host_ip_address, port
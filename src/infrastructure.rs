@@ -1,16 +1,29 @@
 mod project;
+pub mod report;
 pub mod service;
 mod signal;
 mod verification;
 
 pub mod display;
+pub mod garbage_collection;
+pub use project::build_cache;
 pub use project::scaffold;
 pub use signal::shutdown as signal_handling;
 pub use signal::sigpipe;
+pub use verification::pipeline;
 pub use verification::program_verification;
+pub use verification::result_cache;
 pub use verification::runtime as verification_runtime;
+pub use verification::step_registry;
+pub use verification::time_budget;
 
+pub use verification::default_container_api_client_factory;
+pub use verification::ContainerAPIClientFactory;
+pub use verification::SharedContainerAPIClient;
+pub use verification::DEPENDENCY_AUDIT;
 pub use verification::PROGRAM_FUZZING;
+pub use verification::PROGRAM_LINTING;
+pub use verification::PROGRAM_MIRI;
 pub use verification::PROGRAM_VERIFICATION;
 pub use verification::SOURCE_RESTORATION;
 pub use verification::UPLOADED_SOURCES_LISTING;
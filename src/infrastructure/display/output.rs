@@ -1,7 +1,7 @@
 use std::io::{self, Write};
 use tracing::{error, info};
 
-fn display(template: &str, params: Vec<&str>, display: impl Fn(&str) -> ()) {
+fn display(template: &str, params: Vec<&str>, display: impl Fn(&str)) {
     let split: Vec<&str> = template.split("{}").collect();
 
     if split.len() == 1 {
@@ -0,0 +1,688 @@
+use crate::domain::verification_runtime::{VerificationRuntime, VerificationStepRunner};
+use crate::infrastructure as infra;
+use anyhow::Result;
+use color_eyre::{eyre::eyre, Report};
+use infra::program_verification::{format_run_id, parse_run_id, which_step};
+use infra::service::file_system::{
+    disk_usage, get_uploaded_source_directory, BASE64_ENCODED_SOURCE_EXTENSION,
+    PIPELINE_PROGRESS_EXTENSION, PROJECT_SETTINGS_EXTENSION,
+};
+use infra::verification::{ContainerAPIClientFactory, SharedContainerAPIClient};
+use infra::verification_runtime::docker::container::parse_size;
+use regex::Regex;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::task::JoinHandle;
+use tracing::{error, info};
+
+static STATUS_RUNNING: &str = "running";
+
+/// Retention of runs and project files:
+/// their maximum age, the number of finished runs kept per project
+/// and the disk space scaffolds and uploads can take,
+/// the least recently modified being removed first.
+#[derive(Debug, Default, PartialEq)]
+pub struct RetentionPolicy {
+    pub max_age: Option<u64>,
+    pub max_runs_per_project: Option<usize>,
+    pub disk_budget: Option<u64>,
+}
+
+impl RetentionPolicy {
+    pub fn from_env() -> Result<Self, Report> {
+        Self::from_lookup(|name| env::var(name).ok().filter(|value| !value.is_empty()))
+    }
+
+    pub fn from_lookup<L: Fn(&str) -> Option<String>>(lookup: L) -> Result<Self, Report> {
+        let invalid = |name: &str, value: &str| eyre!("Invalid value \"{}\" for {}", value, name);
+
+        Ok(RetentionPolicy {
+            max_age: lookup("GC_MAX_AGE")
+                .map(|value| {
+                    value
+                        .trim()
+                        .parse::<u64>()
+                        .map_err(|_| invalid("GC_MAX_AGE", value.as_str()))
+                })
+                .transpose()?,
+            max_runs_per_project: lookup("GC_MAX_RUNS_PER_PROJECT")
+                .map(|value| {
+                    value
+                        .trim()
+                        .parse::<usize>()
+                        .map_err(|_| invalid("GC_MAX_RUNS_PER_PROJECT", value.as_str()))
+                })
+                .transpose()?,
+            disk_budget: lookup("GC_DISK_BUDGET")
+                .map(|value| {
+                    parse_size(value.as_str())
+                        .map(|size| size as u64)
+                        .ok_or_else(|| invalid("GC_DISK_BUDGET", value.as_str()))
+                })
+                .transpose()?,
+        })
+    }
+
+    fn has_expired(&self, timestamp: Option<u64>, now: u64) -> bool {
+        match (self.max_age, timestamp) {
+            (Some(max_age), Some(timestamp)) => now.saturating_sub(timestamp) > max_age,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GarbageKind {
+    Container,
+    Scaffold,
+    Upload,
+}
+
+/// Container or file removed (or to be removed when running dry),
+/// identified by project id (and run id for containers)
+#[derive(Clone, Debug, Serialize)]
+pub struct Removal {
+    pub kind: GarbageKind,
+    pub project_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub run_id: Option<String>,
+    pub reason: String,
+    pub size: Option<u64>,
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+
+impl Removal {
+    fn container(project_id: &str, run_id: String, reason: String) -> Self {
+        Removal {
+            kind: GarbageKind::Container,
+            project_id: project_id.to_string(),
+            run_id: Some(run_id),
+            reason,
+            size: None,
+            path: None,
+        }
+    }
+
+    fn file(kind: GarbageKind, project_id: &str, file: &ProjectFile, reason: String) -> Self {
+        Removal {
+            kind,
+            project_id: project_id.to_string(),
+            run_id: None,
+            reason,
+            size: Some(file.size),
+            path: Some(file.path.clone()),
+        }
+    }
+}
+
+struct ProjectFile {
+    path: PathBuf,
+    modified: Option<u64>,
+    size: u64,
+}
+
+struct Run {
+    step_name: String,
+    finished_at: Option<u64>,
+    running: bool,
+}
+
+#[derive(Default)]
+struct Project {
+    upload: Option<ProjectFile>,
+    scaffold: Option<ProjectFile>,
+    runs: Vec<Run>,
+}
+
+impl Project {
+    fn is_running(&self) -> bool {
+        self.runs.iter().any(|run| run.running)
+    }
+}
+
+fn unix_time(time: SystemTime) -> Option<u64> {
+    time.duration_since(UNIX_EPOCH)
+        .ok()
+        .map(|elapsed| elapsed.as_secs())
+}
+
+fn project_file(path: PathBuf) -> ProjectFile {
+    let modified = fs::metadata(path.as_path())
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(unix_time);
+    let size = disk_usage(path.as_path());
+
+    ProjectFile {
+        path,
+        modified,
+        size,
+    }
+}
+
+/// Remove finished containers, scaffolded projects and uploaded sources
+/// per retention policy, leaving projects with a running step untouched.
+pub struct GarbageCollector {
+    container_api_client_factory: ContainerAPIClientFactory,
+    policy: RetentionPolicy,
+    uploads_directory: PathBuf,
+    scaffolds_directory: PathBuf,
+}
+
+impl GarbageCollector {
+    pub fn new(
+        container_api_client_factory: ContainerAPIClientFactory,
+        policy: RetentionPolicy,
+    ) -> Result<Self, Report> {
+        Ok(Self::with_directories(
+            container_api_client_factory,
+            policy,
+            PathBuf::from(get_uploaded_source_directory()?),
+            env::temp_dir(),
+        ))
+    }
+
+    pub fn with_directories(
+        container_api_client_factory: ContainerAPIClientFactory,
+        policy: RetentionPolicy,
+        uploads_directory: PathBuf,
+        scaffolds_directory: PathBuf,
+    ) -> Self {
+        GarbageCollector {
+            container_api_client_factory,
+            policy,
+            uploads_directory,
+            scaffolds_directory,
+        }
+    }
+
+    fn find_projects(&self) -> Result<BTreeMap<String, Project>, Report> {
+        let project_id_pattern = Regex::new(r"^[0-9a-f]{10}$")?;
+        let mut projects = BTreeMap::<String, Project>::new();
+
+        for entry in fs::read_dir(self.uploads_directory.as_path())?.filter_map(|e| e.ok()) {
+            let file_name = entry.file_name().to_string_lossy().to_string();
+
+            if let Some(project_id) = file_name.strip_suffix(BASE64_ENCODED_SOURCE_EXTENSION) {
+                projects.entry(project_id.to_string()).or_default().upload =
+                    Some(project_file(entry.path()));
+            }
+        }
+
+        for entry in fs::read_dir(self.scaffolds_directory.as_path())?.filter_map(|e| e.ok()) {
+            let project_id = entry.file_name().to_string_lossy().to_string();
+
+            // Only directories looking like scaffolded projects are considered
+            if project_id_pattern.is_match(project_id.as_str())
+                && entry.path().join("Cargo.toml").exists()
+            {
+                projects.entry(project_id).or_default().scaffold = Some(project_file(entry.path()));
+            }
+        }
+
+        Ok(projects)
+    }
+
+    async fn find_runs(
+        &self,
+        container_api_client: &SharedContainerAPIClient,
+        project_id: &str,
+    ) -> Result<Vec<Run>, Report> {
+        let steps = VerificationRuntime::build_steps()?;
+        let mut runs = vec![];
+
+        for step_name in VerificationRuntime::steps_names()? {
+            let step = which_step(&steps, step_name.to_string(), project_id.to_string())?;
+
+            // Steps which have never run for a project have no container
+            if let Ok(status) = container_api_client.inspect_container_status(&step).await {
+                runs.push(Run {
+                    step_name: step_name.to_string(),
+                    finished_at: status
+                        .get("finished_at")
+                        .and_then(|finished_at| finished_at.parse::<u64>().ok()),
+                    running: status.get("raw_status").map(String::as_str) == Some(STATUS_RUNNING),
+                });
+            }
+        }
+
+        Ok(runs)
+    }
+
+    fn select_runs(&self, project_id: &str, project: &mut Project, now: u64) -> Vec<Removal> {
+        let mut finished_runs: Vec<&Run> = project.runs.iter().filter(|run| !run.running).collect();
+        // Most recently finished runs first
+        finished_runs.sort_by_key(|run| std::cmp::Reverse(run.finished_at));
+
+        finished_runs
+            .into_iter()
+            .enumerate()
+            .filter_map(|(index, run)| {
+                let reason = match self.policy.max_runs_per_project {
+                    Some(max_runs) if index >= max_runs => {
+                        format!("more than {} finished runs for project", max_runs)
+                    }
+                    _ if self.policy.has_expired(run.finished_at, now) => {
+                        String::from("finished run has expired")
+                    }
+                    _ => return None,
+                };
+
+                Some(Removal::container(
+                    project_id,
+                    format_run_id(run.step_name.as_str(), project_id),
+                    reason,
+                ))
+            })
+            .collect()
+    }
+
+    fn select_files(&self, projects: &mut BTreeMap<String, Project>, now: u64) -> Vec<Removal> {
+        let mut removals = vec![];
+
+        for (project_id, project) in projects.iter_mut() {
+            if project.is_running() {
+                continue;
+            }
+
+            for (kind, file) in [
+                (GarbageKind::Scaffold, &mut project.scaffold),
+                (GarbageKind::Upload, &mut project.upload),
+            ] {
+                if let Some(expired) =
+                    file.take_if(|file| self.policy.has_expired(file.modified, now))
+                {
+                    removals.push(Removal::file(
+                        kind,
+                        project_id,
+                        &expired,
+                        String::from("expired"),
+                    ));
+                }
+            }
+        }
+
+        let disk_budget = match self.policy.disk_budget {
+            Some(disk_budget) => disk_budget,
+            None => return removals,
+        };
+
+        let mut remaining_files: Vec<(GarbageKind, &String, &ProjectFile, bool)> = projects
+            .iter()
+            .flat_map(|(project_id, project)| {
+                let running = project.is_running();
+
+                vec![
+                    project
+                        .scaffold
+                        .as_ref()
+                        .map(|file| (GarbageKind::Scaffold, project_id, file, running)),
+                    project
+                        .upload
+                        .as_ref()
+                        .map(|file| (GarbageKind::Upload, project_id, file, running)),
+                ]
+            })
+            .flatten()
+            .collect();
+
+        let mut disk_usage: u64 = remaining_files
+            .iter()
+            .map(|(_, _, file, _)| file.size)
+            .sum();
+
+        // Scaffolds can be generated again from uploads, so they are removed first,
+        // the least recently modified files being removed before the others.
+        remaining_files
+            .sort_by_key(|(kind, _, file, _)| (*kind != GarbageKind::Scaffold, file.modified));
+
+        for (kind, project_id, file, running) in remaining_files {
+            if disk_usage <= disk_budget {
+                break;
+            }
+
+            if running {
+                continue;
+            }
+
+            disk_usage -= file.size;
+            removals.push(Removal::file(
+                kind,
+                project_id,
+                file,
+                format!("disk budget of {} bytes exceeded", disk_budget),
+            ));
+        }
+
+        removals
+    }
+
+    /// Select garbage as of a point in time (seconds since the Unix epoch)
+    /// before removing it, unless running dry.
+    pub async fn collect(&self, now: u64, dry_run: bool) -> Result<Vec<Removal>, Report> {
+        let container_api_client = (self.container_api_client_factory)()?;
+        let mut projects = self.find_projects()?;
+
+        for (project_id, project) in projects.iter_mut() {
+            project.runs = self
+                .find_runs(&container_api_client, project_id.as_str())
+                .await?;
+        }
+
+        let mut removals = vec![];
+        for (project_id, project) in projects.iter_mut() {
+            removals.extend(self.select_runs(project_id.as_str(), project, now));
+        }
+        let file_removals = self.select_files(&mut projects, now);
+
+        // Runs of a project can not be found anymore once its upload is removed
+        for removal in file_removals.iter() {
+            if removal.kind != GarbageKind::Upload {
+                continue;
+            }
+
+            let project_id = removal.project_id.as_str();
+            for run in projects[project_id].runs.iter().filter(|run| !run.running) {
+                let run_id = format_run_id(run.step_name.as_str(), project_id);
+
+                if !removals
+                    .iter()
+                    .any(|removal| removal.run_id == Some(run_id.clone()))
+                {
+                    removals.push(Removal::container(
+                        project_id,
+                        run_id,
+                        String::from("project has been removed"),
+                    ));
+                }
+            }
+        }
+        removals.extend(file_removals);
+
+        // Containers left behind by projects which files have been removed otherwise
+        let steps = VerificationRuntime::build_steps()?;
+        for (run_id, status) in container_api_client.list_runs().await? {
+            let project_id = match parse_run_id(run_id.as_str()) {
+                Some((step_name, project_id)) if steps.contains_key(step_name.as_str()) => {
+                    project_id
+                }
+                _ => continue,
+            };
+
+            if status != STATUS_RUNNING && !projects.contains_key(project_id.as_str()) {
+                removals.push(Removal::container(
+                    project_id.as_str(),
+                    run_id,
+                    String::from("project has been removed"),
+                ));
+            }
+        }
+
+        if dry_run {
+            return Ok(removals);
+        }
+
+        for removal in removals.iter() {
+            match (removal.kind, removal.run_id.as_ref(), removal.path.as_ref()) {
+                (GarbageKind::Container, Some(run_id), _) => {
+                    let (step_name, _) = parse_run_id(run_id.as_str())
+                        .ok_or_else(|| eyre!("Invalid run id \"{}\"", run_id))?;
+                    let step = which_step(&steps, step_name, removal.project_id.clone())?;
+
+                    container_api_client
+                        .remove_existing_container(&step)
+                        .await?
+                }
+                (GarbageKind::Scaffold, _, Some(path)) => fs::remove_dir_all(path)?,
+                (GarbageKind::Upload, _, Some(path)) => {
+                    fs::remove_file(path)?;
+
+                    // The settings selected when uploading a source
+                    // and the progress of its last pipeline go along with it
+                    for extension in [PROJECT_SETTINGS_EXTENSION, PIPELINE_PROGRESS_EXTENSION] {
+                        let sidecar = self
+                            .uploads_directory
+                            .join(format!("{}{}", removal.project_id, extension));
+                        if sidecar.exists() {
+                            fs::remove_file(sidecar)?;
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(removals)
+    }
+
+    pub async fn collect_now(&self, dry_run: bool) -> Result<Vec<Removal>, Report> {
+        self.collect(unix_time(SystemTime::now()).unwrap_or_default(), dry_run)
+            .await
+    }
+}
+
+/// Get the interval between two garbage collections,
+/// declared in seconds as the value of the GC_INTERVAL environment variable.
+/// Garbage collection is disabled when no interval is declared.
+pub fn get_interval() -> Option<Duration> {
+    env::var("GC_INTERVAL")
+        .ok()
+        .and_then(|interval| interval.trim().parse::<u64>().ok())
+        .filter(|interval| *interval > 0)
+        .map(Duration::from_secs)
+}
+
+/// Check if garbage collection is only to report what it would remove,
+/// as declared with GC_DRY_RUN=true
+pub fn is_dry_run() -> bool {
+    env::var("GC_DRY_RUN").is_ok_and(|dry_run| dry_run == "true")
+}
+
+/// Collect garbage periodically in the background
+pub fn spawn_garbage_collection(
+    container_api_client_factory: ContainerAPIClientFactory,
+) -> Result<Option<JoinHandle<()>>, Report> {
+    let interval = match get_interval() {
+        Some(interval) => interval,
+        None => return Ok(None),
+    };
+
+    let collector =
+        GarbageCollector::new(container_api_client_factory, RetentionPolicy::from_env()?)?;
+    let dry_run = is_dry_run();
+
+    Ok(Some(tokio::spawn(async move {
+        let mut ticks = tokio::time::interval(interval);
+
+        loop {
+            ticks.tick().await;
+
+            match collector.collect_now(dry_run).await {
+                Ok(removals) => {
+                    for removal in removals {
+                        info!(
+                            "{} {:?} {}of project \"{}\" ({})",
+                            if dry_run { "Would remove" } else { "Removed" },
+                            removal.kind,
+                            removal
+                                .run_id
+                                .as_ref()
+                                .map(|run_id| format!("\"{}\" ", run_id))
+                                .unwrap_or_default(),
+                            removal.project_id,
+                            removal.reason
+                        );
+                    }
+                }
+                Err(report) => error!("Could not collect garbage: {}", report),
+            }
+        }
+    })))
+}
+
+#[tokio::test]
+async fn it_collects_garbage_per_retention_policy() {
+    use crate::domain::verification_runtime::ContainerAPIClient;
+    use crate::infra::verification_runtime::in_memory::InMemoryContainerAPIClient;
+    use crate::infra::{PROGRAM_FUZZING, PROGRAM_VERIFICATION, SOURCE_RESTORATION};
+    use crate::test;
+
+    let directory = env::temp_dir().join(format!("gc-{}", test::generate_random_letters()));
+    let uploads_directory = directory.join("uploads");
+    let scaffolds_directory = directory.join("scaffolds");
+    fs::create_dir_all(uploads_directory.as_path()).unwrap();
+
+    let (finished_project, running_project) = ("0123456789", "abcdef0123");
+    for project_id in [finished_project, running_project] {
+        let scaffold = scaffolds_directory.join(project_id);
+        fs::create_dir_all(scaffold.as_path()).unwrap();
+        fs::write(scaffold.join("Cargo.toml"), "[package]").unwrap();
+        fs::write(
+            uploads_directory.join(format!("{}.rs.b64", project_id)),
+            "cHViIGZuIGxpYigpIHt9",
+        )
+        .unwrap();
+    }
+
+    let now = unix_time(SystemTime::now()).unwrap();
+    let client = InMemoryContainerAPIClient::new();
+    let steps = VerificationRuntime::build_steps().unwrap();
+
+    for (offset, step_name) in [SOURCE_RESTORATION, PROGRAM_VERIFICATION, PROGRAM_FUZZING]
+        .iter()
+        .enumerate()
+    {
+        let run_id = format_run_id(step_name, finished_project);
+        client
+            .script_statuses(run_id.as_str(), vec!["exited"])
+            .script_finished_at(run_id.as_str(), now - 30 + offset as u64 * 10);
+        client
+            .start_container(
+                &which_step(&steps, step_name.to_string(), finished_project.to_string()).unwrap(),
+            )
+            .await
+            .unwrap();
+    }
+    client.script_statuses(
+        format_run_id(PROGRAM_VERIFICATION, running_project).as_str(),
+        vec!["running"],
+    );
+    client
+        .start_container(
+            &which_step(
+                &steps,
+                PROGRAM_VERIFICATION.to_string(),
+                running_project.to_string(),
+            )
+            .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    // Containers of a project which files are gone are only found by listing runs
+    let orphan_project = "fedcba9876";
+    client.script_statuses(
+        format_run_id(PROGRAM_VERIFICATION, orphan_project).as_str(),
+        vec!["exited"],
+    );
+    client
+        .start_container(
+            &which_step(
+                &steps,
+                PROGRAM_VERIFICATION.to_string(),
+                orphan_project.to_string(),
+            )
+            .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let collector = |policy| {
+        GarbageCollector::with_directories(
+            client.factory(),
+            policy,
+            uploads_directory.clone(),
+            scaffolds_directory.clone(),
+        )
+    };
+    let targets = |removals: &Vec<Removal>| {
+        removals
+            .iter()
+            .map(|removal| {
+                (
+                    removal.kind,
+                    removal
+                        .run_id
+                        .clone()
+                        .unwrap_or_else(|| removal.project_id.clone()),
+                )
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let by_count = collector(RetentionPolicy {
+        max_age: Some(3600),
+        max_runs_per_project: Some(1),
+        // Scaffolds and uploads of both projects take 58 bytes
+        disk_budget: Some(50),
+    });
+
+    let expected = vec![
+        (
+            GarbageKind::Container,
+            format_run_id(PROGRAM_VERIFICATION, finished_project),
+        ),
+        (
+            GarbageKind::Container,
+            format_run_id(SOURCE_RESTORATION, finished_project),
+        ),
+        (GarbageKind::Scaffold, finished_project.to_string()),
+        (
+            GarbageKind::Container,
+            format_run_id(PROGRAM_VERIFICATION, orphan_project),
+        ),
+    ];
+
+    let dry_run = by_count.collect(now, true).await.unwrap();
+    assert_eq!(expected, targets(&dry_run));
+    // Removals are reported without any path of the host
+    let reported = serde_json::to_string(&dry_run).unwrap();
+    assert!(!reported.contains(directory.to_string_lossy().as_ref()));
+    assert!(client.container_exists(format_run_id(SOURCE_RESTORATION, finished_project).as_str()));
+
+    let removals = by_count.collect(now, false).await.unwrap();
+    assert_eq!(expected, targets(&removals));
+    assert!(!client.container_exists(format_run_id(SOURCE_RESTORATION, finished_project).as_str()));
+    assert!(client.container_exists(format_run_id(PROGRAM_FUZZING, finished_project).as_str()));
+    assert!(!client.container_exists(format_run_id(PROGRAM_VERIFICATION, orphan_project).as_str()));
+    assert!(!scaffolds_directory.join(finished_project).exists());
+    assert!(scaffolds_directory.join(running_project).exists());
+
+    let by_budget = collector(RetentionPolicy {
+        disk_budget: Some(20),
+        ..Default::default()
+    });
+    let removals = by_budget.collect(now, false).await.unwrap();
+    assert_eq!(
+        vec![
+            (
+                GarbageKind::Container,
+                format_run_id(PROGRAM_FUZZING, finished_project)
+            ),
+            (GarbageKind::Upload, finished_project.to_string()),
+        ],
+        targets(&removals)
+    );
+    assert!(uploads_directory
+        .join(format!("{}.rs.b64", running_project))
+        .exists());
+    assert!(client.container_exists(format_run_id(PROGRAM_VERIFICATION, running_project).as_str()));
+
+    fs::remove_dir_all(directory).ok();
+}
@@ -1 +1,2 @@
+pub mod build_cache;
 pub mod scaffold;
@@ -0,0 +1,412 @@
+use crate::infra;
+use anyhow::Result;
+use color_eyre::{eyre::eyre, Report};
+use infra::file_system::disk_usage;
+use infra::scaffold::format_directory_path_to_scaffold;
+use infra::verification_runtime::docker::container::parse_size;
+use nix::fcntl::{flock, FlockArg};
+use sha2::{Digest, Sha256};
+use std::env;
+use std::fs;
+use std::os::unix::fs::MetadataExt;
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use tracing::debug;
+
+static CARGO_HOME_DIRECTORY: &str = "cargo-home";
+static TARGET_DIRECTORY: &str = "target";
+static LOCK_FILE: &str = "lock";
+static LAST_USED_FILE: &str = "last_used";
+
+/// Name of the variable pointing steps to the build cache of their project
+pub static BUILD_CACHE_VARIABLE: &str = "SAFEPKT_BUILD_CACHE";
+
+/// Cargo registry and target directory shared by projects
+/// depending on the same crates and built with the same toolchain.
+///
+/// Each entry of the cache holds a cargo home, a target directory and a lock file.
+/// Steps hold a shared lock on the entry they use (an exclusive one while seeding it),
+/// the backend holding one from preparing the entry until the step is started,
+/// so that entries in use are never evicted,
+/// the least recently used ones being evicted first when the cache exceeds its maximum size.
+#[derive(Debug, PartialEq)]
+pub struct BuildCache {
+    directory: PathBuf,
+    toolchain: String,
+    max_size: Option<u64>,
+}
+
+/// Entry of the build cache prepared for a step,
+/// which holds a shared lock on it until dropped (once the step has been started)
+/// so that it is not evicted before the step locks it itself.
+#[derive(Debug)]
+pub struct BuildCacheEntry {
+    path: PathBuf,
+    _lock: fs::File,
+}
+
+impl BuildCacheEntry {
+    pub fn path(&self) -> &Path {
+        self.path.as_path()
+    }
+}
+
+/// Keep the sections of a manifest declaring dependencies
+/// (e.g. "[dependencies]", "[dependencies.type-metadata]" or "[patch.crates-io]"),
+/// leaving out the name of the package.
+///
+/// # Examples
+///
+/// ```
+/// use safepkt_backend::infra::build_cache;
+///
+/// let manifest = "[package]\nname = \"safepkt_47a9690570\"\n\n[dependencies]\nscale = \"1.2\"\n";
+///
+/// assert_eq!("[dependencies]\nscale = \"1.2\"", build_cache::dependency_set(manifest));
+/// ```
+///
+pub fn dependency_set(manifest: &str) -> String {
+    let mut in_dependencies = false;
+
+    manifest
+        .lines()
+        .map(str::trim)
+        .filter(|line| {
+            if line.starts_with('[') {
+                let section = line.trim_matches(|c| c == '[' || c == ']');
+                in_dependencies =
+                    section.starts_with("dependencies") || section.starts_with("patch");
+            }
+
+            in_dependencies && !line.is_empty()
+        })
+        .collect::<Vec<&str>>()
+        .join("\n")
+}
+
+fn lock(entry: &Path, arg: FlockArg) -> Result<Option<fs::File>, Report> {
+    let lock_file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(entry.join(LOCK_FILE))?;
+
+    match flock(lock_file.as_raw_fd(), arg) {
+        Ok(()) => Ok(Some(lock_file)),
+        Err(nix::errno::Errno::EWOULDBLOCK) => Ok(None),
+        Err(errno) => Err(eyre!(
+            "Could not lock build cache entry \"{}\": {}",
+            entry.display(),
+            errno
+        )),
+    }
+}
+
+fn last_used(entry: &Path) -> Option<SystemTime> {
+    fs::metadata(entry.join(LAST_USED_FILE))
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+impl BuildCache {
+    pub fn from_env() -> Result<Option<Self>, Report> {
+        Self::from_lookup(|name| env::var(name).ok().filter(|value| !value.is_empty()))
+    }
+
+    pub fn from_lookup<L: Fn(&str) -> Option<String>>(lookup: L) -> Result<Option<Self>, Report> {
+        let directory = match lookup("BUILD_CACHE_DIRECTORY") {
+            Some(directory) => PathBuf::from(directory),
+            None => return Ok(None),
+        };
+
+        let max_size = lookup("BUILD_CACHE_MAX_SIZE")
+            .map(|size| {
+                parse_size(size.as_str())
+                    .map(|size| size as u64)
+                    .ok_or_else(|| eyre!("Invalid value \"{}\" for BUILD_CACHE_MAX_SIZE", size))
+            })
+            .transpose()?;
+
+        let toolchain = lookup("BUILD_CACHE_TOOLCHAIN")
+            .or_else(|| lookup("RVT_DOCKER_IMAGE"))
+            .unwrap_or_default();
+
+        Ok(Some(BuildCache {
+            directory,
+            toolchain,
+            max_size,
+        }))
+    }
+
+    /// Key of the entry shared by projects having the same dependencies
+    pub fn key(&self, manifest: &str) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(dependency_set(manifest).as_bytes());
+        hasher.update(b"\n");
+        hasher.update(self.toolchain.as_bytes());
+
+        hex::encode(hasher.finalize()).chars().take(16).collect()
+    }
+
+    /// Make the entry of a scaffolded project ready to be used and lock it,
+    /// before evicting entries exceeding the size of the cache.
+    pub fn prepare(&self, project_id: &str) -> Result<BuildCacheEntry, Report> {
+        let manifest_path =
+            Path::new(&format_directory_path_to_scaffold(project_id)).join("Cargo.toml");
+        let manifest = fs::read_to_string(manifest_path.as_path()).map_err(|error| {
+            eyre!(
+                "Could not read manifest \"{}\": {}",
+                manifest_path.display(),
+                error
+            )
+        })?;
+
+        let entry = self.directory.join(self.key(manifest.as_str()));
+        let lock = loop {
+            fs::create_dir_all(entry.join(CARGO_HOME_DIRECTORY))?;
+            fs::create_dir_all(entry.join(TARGET_DIRECTORY))?;
+
+            if let Some(lock) = lock(entry.as_path(), FlockArg::LockShared)? {
+                // The entry might have been evicted while waiting for the lock
+                let locked_file = lock.metadata()?;
+                let is_current = fs::metadata(entry.join(LOCK_FILE))
+                    .is_ok_and(|metadata| metadata.ino() == locked_file.ino());
+                if is_current {
+                    break lock;
+                }
+            }
+        };
+        fs::write(entry.join(LAST_USED_FILE), project_id)?;
+
+        self.evict(Some(entry.as_path()))?;
+
+        Ok(BuildCacheEntry {
+            path: entry,
+            _lock: lock,
+        })
+    }
+
+    /// Evict the least recently used entries until the cache fits in its size,
+    /// entries in use (locked by a step) being kept.
+    pub fn evict(&self, kept_entry: Option<&Path>) -> Result<Vec<PathBuf>, Report> {
+        let max_size = match self.max_size {
+            Some(max_size) => max_size,
+            None => return Ok(vec![]),
+        };
+
+        let mut entries: Vec<(PathBuf, u64, Option<SystemTime>)> =
+            fs::read_dir(self.directory.as_path())?
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|path| path.is_dir())
+                .map(|path| {
+                    let size = disk_usage(path.as_path());
+                    let last_used = last_used(path.as_path());
+
+                    (path, size, last_used)
+                })
+                .collect();
+        entries.sort_by_key(|(_, _, last_used)| *last_used);
+
+        let mut size: u64 = entries.iter().map(|(_, size, _)| size).sum();
+        let mut evicted = vec![];
+
+        for (entry, entry_size, _) in entries {
+            if size <= max_size {
+                break;
+            }
+
+            if Some(entry.as_path()) == kept_entry {
+                continue;
+            }
+
+            // Entries locked by running steps can not be evicted
+            if let Some(_lock) = lock(entry.as_path(), FlockArg::LockExclusiveNonblock)? {
+                fs::remove_dir_all(entry.as_path())?;
+                debug!("Evicted build cache entry \"{}\"", entry.display());
+
+                size = size.saturating_sub(entry_size);
+                evicted.push(entry);
+            }
+        }
+
+        Ok(evicted)
+    }
+}
+
+#[test]
+fn it_shares_entries_between_projects_having_the_same_dependencies() {
+    use crate::domain::project::manifest;
+    use crate::domain::project::settings::ProjectSettings;
+
+    let make_manifest = |package_name: &str, rvt_dir_path: &str| {
+        manifest::make_manifest(package_name, rvt_dir_path, &ProjectSettings::default())
+    };
+
+    let cache = BuildCache::from_lookup(|name| match name {
+        "BUILD_CACHE_DIRECTORY" => Some("/tmp/safepkt-build-cache".to_string()),
+        "RVT_DOCKER_IMAGE" => Some("rvt:safepkt".to_string()),
+        _ => None,
+    })
+    .unwrap()
+    .unwrap();
+
+    assert_eq!(
+        cache.key(make_manifest("safepkt_47a9690570", "/rvt").as_str()),
+        cache.key(make_manifest("safepkt_0123456789", "/rvt").as_str())
+    );
+    assert_ne!(
+        cache.key(make_manifest("safepkt_47a9690570", "/rvt").as_str()),
+        cache.key(make_manifest("safepkt_47a9690570", "/home/rvt").as_str())
+    );
+
+    let ink_3 = ProjectSettings::select(None, Some("3.0.0-rc4"), None, "").unwrap();
+    assert_ne!(
+        cache.key(make_manifest("safepkt_47a9690570", "/rvt").as_str()),
+        cache.key(manifest::make_manifest("safepkt_47a9690570", "/rvt", &ink_3).as_str())
+    );
+
+    let key = cache.key(make_manifest("safepkt_47a9690570", "/rvt").as_str());
+    let other_toolchain = BuildCache {
+        toolchain: "rvt:latest".to_string(),
+        ..cache
+    };
+    assert_ne!(
+        key,
+        other_toolchain.key(make_manifest("safepkt_47a9690570", "/rvt").as_str())
+    );
+
+    assert_eq!(None, BuildCache::from_lookup(|_| None).unwrap());
+}
+
+#[test]
+fn it_evicts_least_recently_used_entries_not_in_use() {
+    use crate::test;
+
+    let directory =
+        env::temp_dir().join(format!("build-cache-{}", test::generate_random_letters()));
+    let cache = BuildCache {
+        directory: directory.clone(),
+        toolchain: String::new(),
+        max_size: Some(1024),
+    };
+
+    // Entries from the least to the most recently used
+    for (name, size) in [("in_use", 600), ("oldest", 600), ("newest", 600)] {
+        let entry = directory.join(name);
+        fs::create_dir_all(entry.join(TARGET_DIRECTORY)).unwrap();
+        fs::write(entry.join(TARGET_DIRECTORY).join("lib.rlib"), vec![0; size]).unwrap();
+        fs::write(entry.join(LAST_USED_FILE), name).unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(20));
+    }
+    let _in_use = lock(
+        directory.join("in_use").as_path(),
+        FlockArg::LockSharedNonblock,
+    )
+    .unwrap()
+    .unwrap();
+
+    let evicted = cache
+        .evict(Some(directory.join("newest").as_path()))
+        .unwrap();
+
+    assert_eq!(vec![directory.join("oldest")], evicted);
+    assert!(directory.join("in_use").exists());
+    assert!(directory.join("newest").exists());
+
+    fs::remove_dir_all(directory).ok();
+}
+
+#[test]
+fn it_keeps_entries_prepared_for_steps_not_started_yet() {
+    use crate::test;
+
+    let directory =
+        env::temp_dir().join(format!("build-cache-{}", test::generate_random_letters()));
+    let cache = BuildCache {
+        directory: directory.clone(),
+        toolchain: String::new(),
+        max_size: Some(1),
+    };
+
+    let scaffold = |dependency: &str| {
+        let project_id = test::generate_random_letters();
+        let project_directory = format_directory_path_to_scaffold(project_id.as_str());
+        fs::create_dir_all(project_directory.as_str()).unwrap();
+        fs::write(
+            Path::new(project_directory.as_str()).join("Cargo.toml"),
+            format!("[dependencies]\n{} = \"1.0\"\n", dependency),
+        )
+        .unwrap();
+
+        project_id
+    };
+    let first_project = scaffold("scale");
+    let second_project = scaffold("serde");
+
+    // The step of the first project has not locked its entry yet
+    // when the second project is prepared
+    let first_entry = cache.prepare(first_project.as_str()).unwrap();
+    let second_entry = cache.prepare(second_project.as_str()).unwrap();
+
+    assert!(first_entry.path().join(CARGO_HOME_DIRECTORY).is_dir());
+    assert!(second_entry.path().join(CARGO_HOME_DIRECTORY).is_dir());
+
+    let first_path = first_entry.path().to_path_buf();
+    drop(first_entry);
+
+    assert_eq!(
+        vec![first_path],
+        cache.evict(Some(second_entry.path())).unwrap()
+    );
+
+    for project_id in [first_project, second_project] {
+        fs::remove_dir_all(format_directory_path_to_scaffold(project_id.as_str())).ok();
+    }
+    fs::remove_dir_all(directory).ok();
+}
+
+#[test]
+fn it_lets_steps_using_the_same_entry_build_concurrently() {
+    use crate::test;
+    use std::process::Command;
+
+    let entry = env::temp_dir().join(format!("build-cache-{}", test::generate_random_letters()));
+    fs::create_dir_all(entry.as_path()).unwrap();
+
+    // Each step signals it holds the lock before waiting for the other one,
+    // which never happens when steps are serialized
+    let script = r#"
+        source "${VERIFY_SCRIPT}"
+        lock_build_cache "${ENTRY}"
+        touch "${ENTRY}/${STEP}"
+        for _ in $(seq 1 50); do
+          [ -e "${ENTRY}/first" ] && [ -e "${ENTRY}/second" ] && exit 0
+          sleep 0.1
+        done
+        exit 1
+    "#;
+    let spawn_step = |step: &str| {
+        Command::new("bash")
+            .args(["-c", script])
+            .env(
+                "VERIFY_SCRIPT",
+                concat!(
+                    env!("CARGO_MANIFEST_DIR"),
+                    "/provisioning/web-server/safepkt/templates/verify.sh"
+                ),
+            )
+            .env("ENTRY", entry.as_path())
+            .env("STEP", step)
+            .spawn()
+            .unwrap()
+    };
+
+    let steps = vec![spawn_step("first"), spawn_step("second")];
+    for step in steps {
+        assert!(step.wait_with_output().unwrap().status.success());
+    }
+    assert!(entry.join("cargo-home").is_dir());
+
+    fs::remove_dir_all(entry).ok();
+}
@@ -1,9 +1,10 @@
 use crate::domain::project::manifest;
+use crate::domain::project::settings::{ProjectKind, ProjectSettings};
 use crate::infra::base64_decoder;
 use crate::infra::file_system;
 use crate::infra::verification_runtime::docker::container::TARGET_RVT_DIRECTORY;
 use anyhow::Result;
-use color_eyre::Report;
+use color_eyre::{eyre::eyre, Report};
 use fungus::prelude::*;
 use nix::unistd;
 use std::{env, fs, fs::File, path};
@@ -21,11 +22,10 @@ fn create_project_source_directory(project_id: &str) -> Result<String, Report> {
 
 #[test]
 fn it_creates_a_project_source_directory() {
-    use std::env;
     use std::fs;
     use std::path::Path;
 
-    env::set_var("SOURCE_DIRECTORY", "/tmp");
+    crate::test::configure_env();
 
     let project_id = "project_id";
     let actual_source_directory_path = create_project_source_directory(project_id).unwrap();
@@ -62,7 +62,6 @@ fn find_source_by_project_id(project_id: &str) -> Result<String, Report> {
 #[test]
 fn it_finds_a_source_in_the_file_system() {
     use crate::infra::scaffold;
-    use std::env;
     use std::fs;
     use std::io::Write;
 
@@ -71,7 +70,7 @@ fn it_finds_a_source_in_the_file_system() {
     let mut file = fs::File::create("/tmp/project_id.rs.b64").unwrap();
     assert!(file.write_all(expected_source_code.as_bytes()).is_ok());
 
-    env::set_var("SOURCE_DIRECTORY", "/tmp");
+    crate::test::configure_env();
 
     let actual_source = scaffold::find_source_by_project_id("project_id").unwrap();
     let actual_source = actual_source.as_str();
@@ -81,6 +80,125 @@ fn it_finds_a_source_in_the_file_system() {
     assert!(fs::remove_file("/tmp/project_id.rs.b64").is_ok());
 }
 
+/// Find a source by project id in the file system
+/// before decoding it.
+pub fn find_decoded_source_by_project_id(project_id: &str) -> Result<String, Report> {
+    let source = find_source_by_project_id(project_id)?;
+
+    base64_decoder::decode(source).map_err(|e| eyre!(e.to_string()))
+}
+
+fn format_project_settings_path(project_id: &str) -> Result<String, Report> {
+    let uploaded_source_directory = file_system::get_uploaded_source_directory()?;
+
+    Ok([
+        uploaded_source_directory,
+        format!("{}{}", project_id, file_system::PROJECT_SETTINGS_EXTENSION),
+    ]
+    .join(path::MAIN_SEPARATOR.to_string().as_str()))
+}
+
+/// Select the settings of a project (its kind and, for ink! contracts, its template),
+/// chosen when uploading its source or detected from it,
+/// before saving them next to the uploaded source.  
+/// Sources being identified by their content only,
+/// settings conflicting with those of a previous upload are rejected.
+pub fn select_project_settings(
+    project_id: &str,
+    kind: Option<&str>,
+    ink_version: Option<&str>,
+    template: Option<&str>,
+) -> Result<ProjectSettings, Report> {
+    let source = find_decoded_source_by_project_id(project_id)?;
+    let settings = ProjectSettings::select(kind, ink_version, template, source.as_str())?;
+
+    let settings_path = format_project_settings_path(project_id)?;
+    if path::Path::new(settings_path.as_str()).exists() {
+        let previous_settings: ProjectSettings =
+            serde_json::from_str(fs::read_to_string(settings_path.as_str())?.as_str())?;
+        if previous_settings != settings {
+            return Err(eyre!(
+                "Project \"{}\" has already been uploaded with other settings ({})",
+                project_id,
+                serde_json::to_string(&previous_settings)?
+            ));
+        }
+
+        return Ok(settings);
+    }
+
+    fs::write(settings_path, serde_json::to_string(&settings)?)?;
+
+    Ok(settings)
+}
+
+/// Find the settings of a project,
+/// detected from its source when none have been selected when uploading it.
+pub fn find_project_settings(project_id: &str) -> Result<ProjectSettings, Report> {
+    let settings_path = format_project_settings_path(project_id)?;
+    if path::Path::new(settings_path.as_str()).exists() {
+        return Ok(serde_json::from_str(
+            fs::read_to_string(settings_path)?.as_str(),
+        )?);
+    }
+
+    let source = find_decoded_source_by_project_id(project_id)?;
+    ProjectSettings::select(None, None, None, source.as_str())
+}
+
+#[test]
+fn it_selects_the_settings_of_projects() {
+    use crate::domain::project::settings::ProjectKind;
+    use crate::test;
+
+    let project_id = test::upload_source();
+    let settings = find_project_settings(project_id.as_str()).unwrap();
+    assert_eq!(ProjectKind::Library, settings.kind());
+    assert!(settings.ink_template().is_none());
+
+    let error = select_project_settings(project_id.as_str(), None, Some("2.1.0"), Some("flipper"))
+        .unwrap_err()
+        .to_string();
+    assert!(error.starts_with("Template \"flipper\" is not installed for ink! 2.1.0"));
+
+    let settings = select_project_settings(
+        project_id.as_str(),
+        None,
+        Some("3.0.0-rc4"),
+        Some("multisig"),
+    )
+    .unwrap();
+    assert_eq!(
+        settings,
+        find_project_settings(project_id.as_str()).unwrap()
+    );
+    assert_eq!(ProjectKind::InkContract, settings.kind());
+    assert_eq!("multisig", settings.ink_template().unwrap().name());
+
+    // Uploading the same source again keeps its settings
+    assert_eq!(
+        settings,
+        select_project_settings(
+            project_id.as_str(),
+            Some("ink_contract"),
+            Some("3.0.0-rc4"),
+            Some("multisig"),
+        )
+        .unwrap()
+    );
+    let error = select_project_settings(project_id.as_str(), Some("binary"), None, None)
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("has already been uploaded with other settings"));
+    assert_eq!(
+        settings,
+        find_project_settings(project_id.as_str()).unwrap()
+    );
+
+    test::remove_project(project_id.as_str());
+    assert!(find_project_settings(project_id.as_str()).is_err());
+}
+
 /// Create a project source directory and its parents if needed,
 /// before creating the project entry point (main.rs),
 /// which contains the source of a project found by project id.
@@ -112,6 +230,11 @@ fn create_library(project_id: &str) -> Result<(), Report> {
     let mut file = File::create(entry_point)?;
     file.write_all(decoded_file_contents.as_bytes())?;
 
+    own_project_directory(project_id)
+}
+
+/// Hand a scaffolded project over to the user running steps (UID_GID)
+fn own_project_directory(project_id: &str) -> Result<(), Report> {
     let project_directory = format_directory_path_to_scaffold(project_id);
     let project = path::Path::new(project_directory.as_str());
 
@@ -137,11 +260,10 @@ fn create_library(project_id: &str) -> Result<(), Report> {
 #[test]
 fn it_creates_an_entry_point() {
     use crate::test;
-    use std::env;
     use std::fs;
     use std::path;
 
-    env::set_var("SOURCE_DIRECTORY", "/tmp");
+    crate::test::configure_env();
 
     let random_prefix = test::generate_random_letters();
     let project_id = format!("{}_my_project_id", random_prefix.as_str());
@@ -189,11 +311,16 @@ pub fn format_project_name(project_id: &str) -> String {
     format!("{}{}", "safepkt_", project_id)
 }
 
-/// Create a manifest at the root of a scaffolded project
+/// Create a manifest at the root of a scaffolded project,
+/// depending on its kind and the version of ink! of its template
 fn create_manifest(project_id: &str) -> Result<(), Report> {
     let prefixed_project_id = format_project_name(project_id);
-    let manifest_contents =
-        manifest::make_manifest(prefixed_project_id.as_str(), TARGET_RVT_DIRECTORY);
+    let settings = find_project_settings(project_id)?;
+    let manifest_contents = manifest::make_manifest(
+        prefixed_project_id.as_str(),
+        TARGET_RVT_DIRECTORY,
+        &settings,
+    );
     let manifest_path = [env::temp_dir().to_str().unwrap(), project_id, "Cargo.toml"]
         .join(path::MAIN_SEPARATOR.to_string().as_str());
 
@@ -205,22 +332,30 @@ fn create_manifest(project_id: &str) -> Result<(), Report> {
 
 #[test]
 fn it_creates_a_project_manifest() {
+    use crate::test;
     use std::fs;
     use std::path::Path;
 
-    if fs::create_dir_all("/tmp/my_project_id").is_ok() {
-        assert!(create_manifest("my_project_id").is_ok());
+    let project_id = test::upload_source();
+    let project_directory = format_directory_path_to_scaffold(project_id.as_str());
+    if fs::create_dir_all(project_directory.as_str()).is_ok() {
+        assert!(create_manifest(project_id.as_str()).is_ok());
     } else {
         panic!("Could not create project directory before creating its manifest.");
     }
 
-    let expected_manifest_path = "/tmp/my_project_id/Cargo.toml";
-    assert!(Path::new(expected_manifest_path).exists());
+    let expected_manifest_path = Path::new(project_directory.as_str()).join("Cargo.toml");
+    assert!(expected_manifest_path.exists());
 
     let manifest_content = fs::read_to_string(expected_manifest_path).unwrap();
-    assert!(manifest_content.contains("safepkt_my_project_id"));
+    assert!(manifest_content.contains(format_project_name(project_id.as_str()).as_str()));
 
-    assert!(fs::remove_dir_all("/tmp/my_project_id").is_ok());
+    test::remove_project(project_id.as_str());
+
+    // Manifests depend on settings which can not be found without source
+    fs::create_dir_all("/tmp/unknown_project_id").unwrap();
+    assert!(create_manifest("unknown_project_id").is_err());
+    assert!(fs::remove_dir_all("/tmp/unknown_project_id").is_ok());
 }
 
 /// Format the path to a directory  
@@ -273,17 +408,82 @@ pub fn format_directory_path_to_scaffold(project_id: &str) -> String {
 /// ```
 ///
 pub fn scaffold_project(project_id: &str) -> Result<(), Report> {
-    if create_entry_point(project_id).is_ok() {
-        create_manifest(project_id)?;
+    create_entry_point(project_id)?;
+    create_manifest(project_id)
+}
+
+pub fn scaffold_library(project_id: &str) -> Result<(), Report> {
+    create_library(project_id)?;
+    create_manifest(project_id)
+}
+
+/// Remove the entry points of the other kinds of projects,
+/// left behind by a project scaffolded with other settings
+fn remove_stale_entry_points(project_id: &str, kind: ProjectKind) -> Result<(), Report> {
+    let project_source_directory =
+        path::Path::new(&format_directory_path_to_scaffold(project_id)).join("src");
+
+    for entry_point in [
+        ProjectKind::Binary.entry_point(),
+        ProjectKind::Library.entry_point(),
+    ] {
+        let stale_entry_point = project_source_directory.join(entry_point);
+        if entry_point != kind.entry_point() && stale_entry_point.exists() {
+            fs::remove_file(stale_entry_point)?;
+        }
     }
 
     Ok(())
 }
 
-pub fn scaffold_library(project_id: &str) -> Result<(), Report> {
-    if create_library(project_id).is_ok() {
-        create_manifest(project_id)?;
+/// Scaffold a project depending on its kind,
+/// binaries having an entry point (./src/main.rs) and other projects a library (./src/lib.rs)
+pub fn scaffold_uploaded_project(project_id: &str) -> Result<(), Report> {
+    let settings = find_project_settings(project_id)?;
+
+    match settings.kind() {
+        ProjectKind::Binary => {
+            scaffold_project(project_id)?;
+            own_project_directory(project_id)?;
+        }
+        ProjectKind::InkContract | ProjectKind::Library => scaffold_library(project_id)?,
     }
 
-    Ok(())
+    remove_stale_entry_points(project_id, settings.kind())
+}
+
+#[test]
+fn it_scaffolds_projects_depending_on_their_kind() {
+    use crate::test;
+    use std::path::Path;
+
+    let project_id = test::upload_source();
+    select_project_settings(project_id.as_str(), Some("binary"), None, None).unwrap();
+
+    assert!(scaffold_uploaded_project(project_id.as_str()).is_ok());
+
+    let project_directory = format_directory_path_to_scaffold(project_id.as_str());
+    assert!(Path::new(&project_directory).join("src/main.rs").exists());
+    assert!(!Path::new(&project_directory).join("src/lib.rs").exists());
+
+    let manifest = fs::read_to_string(Path::new(&project_directory).join("Cargo.toml")).unwrap();
+    assert!(manifest.contains("[[bin]]"));
+    assert!(!manifest.contains("ink_lang"));
+
+    // Scaffolding the project as a library leaves no entry point of a binary behind
+    fs::remove_file(format_project_settings_path(project_id.as_str()).unwrap()).unwrap();
+    select_project_settings(project_id.as_str(), Some("library"), None, None).unwrap();
+    assert!(scaffold_uploaded_project(project_id.as_str()).is_ok());
+    assert!(Path::new(&project_directory).join("src/lib.rs").exists());
+    assert!(!Path::new(&project_directory).join("src/main.rs").exists());
+
+    // Failures are reported instead of leaving a project without entry point
+    fs::remove_file(test::uploaded_file_path(
+        project_id.as_str(),
+        file_system::BASE64_ENCODED_SOURCE_EXTENSION,
+    ))
+    .unwrap();
+    assert!(scaffold_uploaded_project(project_id.as_str()).is_err());
+
+    test::remove_project(project_id.as_str());
 }
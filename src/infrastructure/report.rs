@@ -0,0 +1,7 @@
+pub mod audit;
+pub mod clippy;
+pub mod html;
+pub mod klee_statistics;
+pub mod metrics;
+pub mod miri;
+pub mod parser;
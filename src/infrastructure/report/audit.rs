@@ -0,0 +1,173 @@
+use crate::domain::value_object::Advisory;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+static VULNERABILITY: &str = "vulnerability";
+
+#[derive(Deserialize)]
+struct Package {
+    name: String,
+    version: String,
+}
+
+#[derive(Deserialize)]
+struct AdvisoryMetadata {
+    id: String,
+    title: String,
+    date: Option<String>,
+    url: Option<String>,
+}
+
+#[derive(Default, Deserialize)]
+struct Versions {
+    #[serde(default)]
+    patched: Vec<String>,
+    #[serde(default)]
+    unaffected: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct Vulnerability {
+    advisory: AdvisoryMetadata,
+    #[serde(default)]
+    versions: Versions,
+    package: Package,
+}
+
+#[derive(Deserialize)]
+struct Vulnerabilities {
+    list: Vec<Vulnerability>,
+}
+
+#[derive(Deserialize)]
+struct Warning {
+    kind: String,
+    package: Package,
+    advisory: Option<AdvisoryMetadata>,
+    versions: Option<Versions>,
+}
+
+#[derive(Deserialize)]
+struct AuditReport {
+    vulnerabilities: Vulnerabilities,
+    #[serde(default)]
+    warnings: BTreeMap<String, Vec<Warning>>,
+}
+
+fn to_advisory(
+    kind: &str,
+    package: Package,
+    advisory: AdvisoryMetadata,
+    versions: Versions,
+) -> Advisory {
+    Advisory {
+        id: advisory.id,
+        kind: kind.to_string(),
+        package: package.name,
+        version: package.version,
+        title: advisory.title,
+        date: advisory.date,
+        url: advisory.url,
+        patched_versions: versions.patched,
+        unaffected_versions: versions.unaffected,
+    }
+}
+
+/// Parse the report printed by `cargo audit --json`
+/// into the advisories affecting the dependencies of a project,
+/// vulnerabilities coming first and warnings without advisory (e.g. yanked crates) being left out.
+///
+/// # Examples
+///
+/// ```
+/// use safepkt_backend::infra::report::audit;
+///
+/// let raw_log = r#"{"vulnerabilities":{"found":true,"count":1,"list":[{"advisory":{"id":"RUSTSEC-2020-0071","title":"Potential segfault in the time crate","date":"2020-11-18"},"versions":{"patched":[">=0.2.23"],"unaffected":["=0.2.0"]},"package":{"name":"time","version":"0.1.43"}}]},"warnings":{}}"#;
+///
+/// let advisories = audit::parse_advisories(raw_log);
+/// assert_eq!("RUSTSEC-2020-0071", advisories[0].id);
+/// assert_eq!("0.1.43", advisories[0].version);
+/// assert_eq!(vec![">=0.2.23"], advisories[0].patched_versions);
+/// ```
+///
+pub fn parse_advisories(raw_log: &str) -> Vec<Advisory> {
+    // cargo writes progress (e.g. "Loaded 400 security advisories") besides the JSON report
+    let report = match raw_log
+        .lines()
+        .filter(|line| line.trim_start().starts_with('{'))
+        .find_map(|line| serde_json::from_str::<AuditReport>(line.trim()).ok())
+    {
+        Some(report) => report,
+        None => return vec![],
+    };
+
+    let mut advisories: Vec<Advisory> = report
+        .vulnerabilities
+        .list
+        .into_iter()
+        .map(|vulnerability| {
+            to_advisory(
+                VULNERABILITY,
+                vulnerability.package,
+                vulnerability.advisory,
+                vulnerability.versions,
+            )
+        })
+        .collect();
+
+    for warning in report.warnings.into_values().flatten() {
+        if let Some(advisory) = warning.advisory {
+            advisories.push(to_advisory(
+                warning.kind.as_str(),
+                warning.package,
+                advisory,
+                warning.versions.unwrap_or_default(),
+            ));
+        }
+    }
+
+    advisories
+}
+
+#[test]
+fn it_parses_vulnerabilities_and_warnings_reported_by_cargo_audit() {
+    let raw_log = r#"    Fetching advisory database from `/advisory-db`
+      Loaded 412 security advisories (from /advisory-db)
+    Scanning Cargo.lock for vulnerabilities (187 crate dependencies)
+{"database":{"advisory-count":412,"last-commit":null,"last-updated":null},"lockfile":{"dependency-count":187},"settings":{"target_arch":null,"target_os":null,"severity":null,"ignore":[],"informational_warnings":["unmaintained","unsound","yanked"]},"vulnerabilities":{"found":true,"count":1,"list":[{"advisory":{"id":"RUSTSEC-2021-0093","package":"crossbeam-deque","title":"Data race in crossbeam-deque","description":"...","date":"2021-12-08","aliases":["CVE-2021-43806"],"related":[],"collection":"crates","categories":["memory-corruption"],"keywords":[],"cvss":null,"informational":null,"references":[],"source":null,"url":"https://github.com/crossbeam-rs/crossbeam/security/advisories/GHSA-pqqp-xmhj-wgcw","withdrawn":null},"versions":{"patched":[">=0.7.4, <0.8.0",">=0.8.1"],"unaffected":["<0.7.0"]},"affected":null,"package":{"name":"crossbeam-deque","version":"0.7.3","source":"registry+https://github.com/rust-lang/crates.io-index","checksum":"9f02af974daeee82218205558e51ec8768b48cf524bd01d550abe5573a608285","dependencies":[]}}]},"warnings":{"unmaintained":[{"kind":"unmaintained","package":{"name":"ansi_term","version":"0.11.0","source":"registry+https://github.com/rust-lang/crates.io-index"},"advisory":{"id":"RUSTSEC-2021-0139","package":"ansi_term","title":"ansi_term is Unmaintained","date":"2021-08-18","url":null},"affected":null,"versions":{"patched":[],"unaffected":[]}}],"yanked":[{"kind":"yanked","package":{"name":"parity-scale-codec","version":"2.0.0"},"advisory":null,"affected":null,"versions":null}]}}
+"#;
+
+    let advisories = parse_advisories(raw_log);
+
+    assert_eq!(
+        vec![
+            Advisory {
+                id: "RUSTSEC-2021-0093".to_string(),
+                kind: "vulnerability".to_string(),
+                package: "crossbeam-deque".to_string(),
+                version: "0.7.3".to_string(),
+                title: "Data race in crossbeam-deque".to_string(),
+                date: Some("2021-12-08".to_string()),
+                url: Some(
+                    "https://github.com/crossbeam-rs/crossbeam/security/advisories/GHSA-pqqp-xmhj-wgcw"
+                        .to_string()
+                ),
+                patched_versions: vec![">=0.7.4, <0.8.0".to_string(), ">=0.8.1".to_string()],
+                unaffected_versions: vec!["<0.7.0".to_string()],
+            },
+            Advisory {
+                id: "RUSTSEC-2021-0139".to_string(),
+                kind: "unmaintained".to_string(),
+                package: "ansi_term".to_string(),
+                version: "0.11.0".to_string(),
+                title: "ansi_term is Unmaintained".to_string(),
+                date: Some("2021-08-18".to_string()),
+                url: None,
+                patched_versions: vec![],
+                unaffected_versions: vec![],
+            },
+        ],
+        advisories
+    );
+    assert!(parse_advisories("error: Couldn't load Cargo.lock").is_empty());
+}
@@ -0,0 +1,180 @@
+use crate::domain::value_object::{Diagnostic, SourceSpan, Suggestion};
+use crate::infra::report::parser::uploaded_source_path;
+use serde::Deserialize;
+
+static COMPILER_MESSAGE: &str = "compiler-message";
+
+#[derive(Deserialize)]
+struct CargoMessage {
+    reason: String,
+    message: Option<CompilerMessage>,
+}
+
+#[derive(Deserialize)]
+struct CompilerCode {
+    code: String,
+}
+
+#[derive(Deserialize)]
+struct CompilerSpan {
+    file_name: String,
+    line_start: usize,
+    line_end: usize,
+    column_start: usize,
+    column_end: usize,
+    is_primary: bool,
+    suggested_replacement: Option<String>,
+    suggestion_applicability: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct CompilerMessage {
+    message: String,
+    code: Option<CompilerCode>,
+    level: String,
+    spans: Vec<CompilerSpan>,
+    children: Vec<CompilerMessage>,
+}
+
+impl CompilerSpan {
+    fn is_in_uploaded_source(&self, source_path: &str) -> bool {
+        self.file_name.ends_with(source_path)
+    }
+
+    fn to_source_span(&self, source_path: &str) -> SourceSpan {
+        SourceSpan {
+            file: source_path.to_string(),
+            line_start: self.line_start,
+            line_end: self.line_end,
+            column_start: self.column_start,
+            column_end: self.column_end,
+        }
+    }
+}
+
+/// Find the first replacement suggested by the children of a message (e.g. "help: ...")
+fn find_suggestion(message: &CompilerMessage, source_path: &str) -> Option<Suggestion> {
+    message.children.iter().find_map(|child| {
+        child
+            .spans
+            .iter()
+            .filter(|span| span.is_in_uploaded_source(source_path))
+            .find_map(|span| {
+                span.suggested_replacement
+                    .as_ref()
+                    .map(|replacement| Suggestion {
+                        message: child.message.clone(),
+                        replacement: replacement.clone(),
+                        span: span.to_source_span(source_path),
+                        applicability: span.suggestion_applicability.clone(),
+                    })
+            })
+    })
+}
+
+fn to_diagnostic(message: CompilerMessage, source_path: &str) -> Option<Diagnostic> {
+    // Summaries (e.g. "2 warnings emitted") and diagnostics
+    // of other files than the uploaded source are left out
+    let span = message
+        .spans
+        .iter()
+        .find(|span| span.is_primary && span.is_in_uploaded_source(source_path))?
+        .to_source_span(source_path);
+
+    Some(Diagnostic {
+        lint: message.code.as_ref().map(|code| code.code.clone()),
+        level: message.level.clone(),
+        suggestion: find_suggestion(&message, source_path),
+        message: message.message,
+        span,
+    })
+}
+
+/// Parse the messages printed by `cargo clippy --message-format=json`
+/// into diagnostics located in the uploaded source
+/// (the entry point of the project e.g. "lib.rs"),
+/// lines which are not compiler messages being ignored.
+///
+/// # Examples
+///
+/// ```
+/// use safepkt_backend::infra::report::clippy;
+///
+/// let raw_log = r#"    Checking safepkt_47a9690570 v0.1.0
+/// {"reason":"compiler-message","message":{"message":"unneeded `return` statement","code":{"code":"clippy::needless_return","explanation":null},"level":"warning","spans":[{"file_name":"src/lib.rs","line_start":3,"line_end":3,"column_start":5,"column_end":14,"is_primary":true,"suggested_replacement":null,"suggestion_applicability":null}],"children":[]}}"#;
+///
+/// let diagnostics = clippy::parse_diagnostics(raw_log, "lib.rs");
+/// assert_eq!(1, diagnostics.len());
+/// assert_eq!(Some("clippy::needless_return".to_string()), diagnostics[0].lint);
+/// assert_eq!(3, diagnostics[0].span.line_start);
+/// ```
+///
+pub fn parse_diagnostics(raw_log: &str, entry_point: &str) -> Vec<Diagnostic> {
+    let source_path = uploaded_source_path(entry_point);
+
+    raw_log
+        .lines()
+        .filter_map(|line| serde_json::from_str::<CargoMessage>(line.trim()).ok())
+        .filter(|message| message.reason == COMPILER_MESSAGE)
+        .filter_map(|message| message.message)
+        .filter_map(|message| to_diagnostic(message, source_path.as_str()))
+        .collect()
+}
+
+#[test]
+fn it_parses_clippy_diagnostics_with_suggested_fixes() {
+    let raw_log = r#"{"reason":"compiler-artifact","package_id":"ink_lang 3.0.0-rc4","target":{"name":"ink_lang"},"fresh":true}
+{"reason":"compiler-message","package_id":"safepkt_47a9690570 0.1.0","message":{"rendered":"warning: unneeded `return` statement","message":"unneeded `return` statement","code":{"code":"clippy::needless_return","explanation":null},"level":"warning","spans":[{"file_name":"/safepkt-ink/examples/source/src/lib.rs","byte_start":40,"byte_end":49,"line_start":3,"line_end":3,"column_start":5,"column_end":14,"is_primary":true,"text":[],"label":null,"suggested_replacement":null,"suggestion_applicability":null,"expansion":null}],"children":[{"message":"remove `return`","code":null,"level":"help","spans":[{"file_name":"/safepkt-ink/examples/source/src/lib.rs","byte_start":40,"byte_end":49,"line_start":3,"line_end":3,"column_start":5,"column_end":14,"is_primary":true,"text":[],"label":null,"suggested_replacement":"x","suggestion_applicability":"MachineApplicable","expansion":null}],"children":[],"rendered":null}]}}
+{"reason":"compiler-message","package_id":"safepkt_47a9690570 0.1.0","message":{"rendered":"error: mismatched types","message":"mismatched types","code":{"code":"E0308","explanation":"..."},"level":"error","spans":[{"file_name":"src/lib.rs","byte_start":80,"byte_end":84,"line_start":7,"line_end":8,"column_start":9,"column_end":2,"is_primary":true,"text":[],"label":"expected `u32`","suggested_replacement":null,"suggestion_applicability":null,"expansion":null}],"children":[],"rendered":null}}
+{"reason":"compiler-message","package_id":"safepkt_47a9690570 0.1.0","message":{"rendered":"warning: 1 warning emitted","message":"1 warning emitted","code":null,"level":"warning","spans":[],"children":[],"rendered":null}}
+error: could not compile `safepkt_47a9690570`
+{"reason":"build-finished","success":false}
+"#;
+
+    let diagnostics = parse_diagnostics(raw_log, "lib.rs");
+
+    assert_eq!(2, diagnostics.len());
+    assert_eq!(
+        Diagnostic {
+            lint: Some("clippy::needless_return".to_string()),
+            level: "warning".to_string(),
+            message: "unneeded `return` statement".to_string(),
+            span: SourceSpan {
+                file: "src/lib.rs".to_string(),
+                line_start: 3,
+                line_end: 3,
+                column_start: 5,
+                column_end: 14,
+            },
+            suggestion: Some(Suggestion {
+                message: "remove `return`".to_string(),
+                replacement: "x".to_string(),
+                span: SourceSpan {
+                    file: "src/lib.rs".to_string(),
+                    line_start: 3,
+                    line_end: 3,
+                    column_start: 5,
+                    column_end: 14,
+                },
+                applicability: Some("MachineApplicable".to_string()),
+            }),
+        },
+        diagnostics[0]
+    );
+    assert_eq!(Some("E0308".to_string()), diagnostics[1].lint);
+    assert_eq!("error", diagnostics[1].level);
+    assert_eq!(None, diagnostics[1].suggestion);
+}
+
+#[test]
+fn it_locates_clippy_diagnostics_in_the_entry_point_of_binaries() {
+    let raw_log = r#"{"reason":"compiler-message","message":{"message":"unused variable: `x`","code":{"code":"unused_variables","explanation":null},"level":"warning","spans":[{"file_name":"src/main.rs","line_start":2,"line_end":2,"column_start":9,"column_end":10,"is_primary":true,"suggested_replacement":null,"suggestion_applicability":null}],"children":[]}}
+{"reason":"compiler-message","message":{"message":"unneeded `return` statement","code":{"code":"clippy::needless_return","explanation":null},"level":"warning","spans":[{"file_name":"src/lib.rs","line_start":3,"line_end":3,"column_start":5,"column_end":14,"is_primary":true,"suggested_replacement":null,"suggestion_applicability":null}],"children":[]}}"#;
+
+    let diagnostics = parse_diagnostics(raw_log, "main.rs");
+
+    assert_eq!(1, diagnostics.len());
+    assert_eq!(Some("unused_variables".to_string()), diagnostics[0].lint);
+    assert_eq!("src/main.rs", diagnostics[0].span.file);
+    assert_eq!(1, parse_diagnostics(raw_log, "lib.rs").len());
+}
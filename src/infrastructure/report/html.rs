@@ -0,0 +1,429 @@
+use crate::domain::value_object::{Interruption, KleeStatistics, StepReport, Verdict};
+use crate::infra::report::parser::uploaded_source_path;
+
+static STYLESHEET: &str = r#"
+body { font-family: sans-serif; margin: 2em; color: #222; }
+table { border-collapse: collapse; margin-bottom: 2em; }
+th, td { border: 1px solid #ccc; padding: .3em .8em; text-align: left; vertical-align: top; }
+.passed { color: #1a7f37; }
+.failed { color: #cf222e; }
+.unknown { color: #9a6700; }
+pre { background: #f6f8fa; padding: .5em; overflow-x: auto; }
+ol.source { font-family: monospace; background: #f6f8fa; padding-left: 4em; }
+ol.source li { white-space: pre; }
+ol.source li.failing { background: #ffebe9; }
+"#;
+
+/// Escape characters having a special meaning in HTML
+///
+/// # Examples
+///
+/// ```
+/// use safepkt_backend::infra::report::html;
+///
+/// assert_eq!("&lt;T&gt; &amp; &quot;a&quot;", html::escape("<T> & \"a\""));
+/// ```
+///
+pub fn escape(subject: &str) -> String {
+    subject
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+fn verdict_label(verdict: Verdict) -> &'static str {
+    match verdict {
+        Verdict::Passed => "passed",
+        Verdict::Failed => "failed",
+        Verdict::Unknown => "unknown",
+    }
+}
+
+fn format_optional<T: ToString>(value: Option<T>) -> String {
+    value.map_or_else(|| "-".to_string(), |v| v.to_string())
+}
+
+fn format_percentage(value: Option<f64>) -> String {
+    value.map_or_else(|| "-".to_string(), |v| format!("{:.2}%", v))
+}
+
+fn format_seconds(value: Option<f64>) -> String {
+    value.map_or_else(|| "-".to_string(), |v| format!("{:.2}s", v))
+}
+
+fn render_statistics(statistics: &KleeStatistics) -> String {
+    let rows = vec![
+        (
+            "Completed paths",
+            format_optional(statistics.completed_paths),
+        ),
+        (
+            "Partially completed paths",
+            format_optional(statistics.partially_completed_paths),
+        ),
+        (
+            "Generated tests",
+            format_optional(statistics.generated_tests),
+        ),
+        (
+            "Instructions",
+            format_optional(statistics.total_instructions),
+        ),
+        (
+            "Instruction coverage",
+            format_percentage(statistics.instruction_coverage),
+        ),
+        (
+            "Branch coverage",
+            format_percentage(statistics.branch_coverage),
+        ),
+        ("Solver time", format_seconds(statistics.solver_time)),
+        ("Total time", format_seconds(statistics.total_time)),
+    ];
+
+    let rows: Vec<String> = rows
+        .into_iter()
+        .map(|(label, value)| format!("<tr><th>{}</th><td>{}</td></tr>", label, value))
+        .collect();
+
+    format!("<table>{}</table>", rows.join(""))
+}
+
+fn render_interruption(report: &StepReport) -> String {
+    match report.interruption() {
+        Some(Interruption::OomKilled) => String::from(
+            "<p class=\"failed\">The step has been killed after running out of memory, results are partial.</p>",
+        ),
+        Some(Interruption::TimedOut) => String::from(
+            "<p class=\"failed\">The step has been stopped after exceeding its time budget, results are partial.</p>",
+        ),
+        None => String::new(),
+    }
+}
+
+fn render_summary(report: &StepReport) -> String {
+    let rows: Vec<String> = report
+        .tests()
+        .iter()
+        .enumerate()
+        .map(|(index, test)| {
+            let label = verdict_label(test.verdict());
+            let statistics = test.statistics().cloned().unwrap_or_default();
+            format!(
+                "<tr><td><a href=\"#test-{}\">{}</a></td><td class=\"{}\">{}</td><td>{}</td><td>{}</td><td>{} / {}</td><td>{}</td><td>{}</td></tr>",
+                index,
+                escape(test.name()),
+                label,
+                label,
+                test.findings().len(),
+                test.counterexamples().len(),
+                format_optional(statistics.completed_paths),
+                format_optional(statistics.partially_completed_paths),
+                format_percentage(statistics.instruction_coverage),
+                format_percentage(statistics.branch_coverage),
+            )
+        })
+        .collect();
+
+    format!(
+        "<table><thead><tr><th>Test</th><th>Verdict</th><th>Findings</th><th>Counterexamples</th><th>Completed / partial paths</th><th>Instruction coverage</th><th>Branch coverage</th></tr></thead><tbody>{}</tbody></table>",
+        rows.join("")
+    )
+}
+
+fn render_tests(report: &StepReport, entry_point: &str) -> String {
+    let source_path = uploaded_source_path(entry_point);
+
+    report
+        .tests()
+        .iter()
+        .enumerate()
+        .map(|(index, test)| {
+            let label = verdict_label(test.verdict());
+
+            let findings: Vec<String> = test
+                .findings()
+                .iter()
+                .map(|finding| match finding.line() {
+                    Some(line) => format!(
+                        "<li><a href=\"#L{}\">{}:{}</a> {}</li>",
+                        line,
+                        source_path,
+                        line,
+                        escape(finding.message())
+                    ),
+                    None => format!("<li>{}</li>", escape(finding.message())),
+                })
+                .collect();
+
+            let mut section = format!(
+                "<section id=\"test-{}\"><h3>{} <span class=\"{}\">{}</span></h3>",
+                index,
+                escape(test.name()),
+                label,
+                label
+            );
+            if !findings.is_empty() {
+                section
+                    .push_str(format!("<h4>Findings</h4><ul>{}</ul>", findings.join("")).as_str());
+            }
+            if !test.counterexamples().is_empty() {
+                section.push_str(
+                    format!(
+                        "<h4>Counterexamples</h4><pre>{}</pre>",
+                        escape(test.counterexamples().join("\n").as_str())
+                    )
+                    .as_str(),
+                );
+            }
+            if let Some(statistics) = test.statistics() {
+                section.push_str("<h4>KLEE statistics</h4>");
+                section.push_str(render_statistics(statistics).as_str());
+            }
+            if !test.summary().is_empty() {
+                section.push_str(
+                    format!(
+                        "<h4>Summary</h4><pre>{}</pre>",
+                        escape(test.summary().join("\n").as_str())
+                    )
+                    .as_str(),
+                );
+            }
+            section.push_str("</section>");
+
+            section
+        })
+        .collect::<Vec<String>>()
+        .join("")
+}
+
+fn render_diagnostics(report: &StepReport) -> String {
+    if report.diagnostics().is_empty() {
+        return String::new();
+    }
+
+    let diagnostics: Vec<String> = report
+        .diagnostics()
+        .iter()
+        .map(|diagnostic| {
+            let mut item = format!(
+                "<li><a href=\"#L{}\">{}:{}:{}</a> {}{}: {}",
+                diagnostic.span.line_start,
+                escape(diagnostic.span.file.as_str()),
+                diagnostic.span.line_start,
+                diagnostic.span.column_start,
+                escape(diagnostic.level.as_str()),
+                diagnostic
+                    .lint
+                    .as_ref()
+                    .map(|lint| format!(" <code>{}</code>", escape(lint)))
+                    .unwrap_or_default(),
+                escape(diagnostic.message.as_str()),
+            );
+            if let Some(suggestion) = &diagnostic.suggestion {
+                item.push_str(
+                    format!(
+                        "<pre>{}: {}</pre>",
+                        escape(suggestion.message.as_str()),
+                        escape(suggestion.replacement.as_str())
+                    )
+                    .as_str(),
+                );
+            }
+            item.push_str("</li>");
+
+            item
+        })
+        .collect();
+
+    format!("<h2>Diagnostics</h2><ul>{}</ul>", diagnostics.join(""))
+}
+
+fn render_undefined_behaviors(report: &StepReport) -> String {
+    if report.undefined_behaviors().is_empty() {
+        return String::new();
+    }
+
+    let undefined_behaviors: Vec<String> = report
+        .undefined_behaviors()
+        .iter()
+        .map(|undefined_behavior| {
+            let location = undefined_behavior
+                .location
+                .as_ref()
+                .map(|location| {
+                    format!(
+                        " {}:{}:{}",
+                        escape(location.file.as_str()),
+                        location.line,
+                        location.column
+                    )
+                })
+                .unwrap_or_default();
+
+            format!(
+                "<li><span class=\"failed\">{}</span>{}{}: {}<pre>{}</pre></li>",
+                escape(undefined_behavior.kind.as_str()),
+                undefined_behavior
+                    .test
+                    .as_ref()
+                    .map(|test| format!(" in <code>{}</code>", escape(test)))
+                    .unwrap_or_default(),
+                location,
+                escape(undefined_behavior.message.as_str()),
+                escape(undefined_behavior.backtrace.join("\n").as_str()),
+            )
+        })
+        .collect();
+
+    format!(
+        "<h2>Undefined behaviours</h2><ul>{}</ul>",
+        undefined_behaviors.join("")
+    )
+}
+
+fn render_advisories(report: &StepReport) -> String {
+    if report.advisories().is_empty() {
+        return String::new();
+    }
+
+    let rows: Vec<String> = report
+        .advisories()
+        .iter()
+        .map(|advisory| {
+            let id = match &advisory.url {
+                Some(url) => format!("<a href=\"{}\">{}</a>", escape(url), escape(&advisory.id)),
+                None => escape(&advisory.id),
+            };
+
+            format!(
+                "<tr><td>{}</td><td class=\"failed\">{}</td><td>{} {}</td><td>{}</td><td>{}</td></tr>",
+                id,
+                escape(&advisory.kind),
+                escape(&advisory.package),
+                escape(&advisory.version),
+                escape(&advisory.title),
+                escape(advisory.patched_versions.join(", ").as_str()),
+            )
+        })
+        .collect();
+
+    format!(
+        "<h2>Advisories</h2><table><thead><tr><th>Advisory</th><th>Kind</th><th>Dependency</th><th>Title</th><th>Patched versions</th></tr></thead><tbody>{}</tbody></table>",
+        rows.join("")
+    )
+}
+
+fn render_source(report: &StepReport, source: &str) -> String {
+    let failing_lines = report.failing_lines();
+
+    let lines: Vec<String> = source
+        .lines()
+        .enumerate()
+        .map(|(index, line)| {
+            let line_number = index + 1;
+            let class = if failing_lines.contains(&line_number) {
+                " class=\"failing\""
+            } else {
+                ""
+            };
+
+            format!("<li id=\"L{}\"{}>{}</li>", line_number, class, escape(line))
+        })
+        .collect();
+
+    format!("<ol class=\"source\">{}</ol>", lines.join(""))
+}
+
+/// Render a report as a self-contained HTML document
+/// embedding the uploaded source (the entry point of the project e.g. "lib.rs")
+/// with failing lines highlighted.
+pub fn render_report(report: &StepReport, source: &str, entry_point: &str) -> String {
+    let title = format!(
+        "SafePKT report - {} - {}",
+        report.step(),
+        report.project_id()
+    );
+
+    format!(
+        "<!DOCTYPE html><html lang=\"en\"><head><meta charset=\"utf-8\"><title>{}</title><style>{}</style></head><body><h1>{}</h1><p>Run <code>{}</code></p>{}<h2>Summary</h2>{}{}<h2>Tests</h2>{}{}{}{}<h2>Source</h2>{}</body></html>\n",
+        escape(title.as_str()),
+        STYLESHEET,
+        escape(title.as_str()),
+        escape(report.run_id()),
+        render_interruption(report),
+        render_summary(report),
+        report
+            .statistics()
+            .map(render_statistics)
+            .unwrap_or_default(),
+        render_tests(report, entry_point),
+        render_undefined_behaviors(report),
+        render_diagnostics(report),
+        render_advisories(report),
+        render_source(report, source),
+    )
+}
+
+#[test]
+fn it_renders_a_report_with_highlighted_lines() {
+    use crate::infra::report::parser;
+
+    let raw_log =
+        "Tests results for \"safe_transfer\"\n\tKLEE: ERROR: src/lib.rs:2: abort failure\n";
+    let report = parser::parse_report("run", "program_verification", "abc", raw_log, "lib.rs");
+
+    let html = render_report(&report, "fn a() {}\nfn b() { panic!(\"<b>\") }\n", "lib.rs");
+
+    assert!(html.contains("<a href=\"#L2\">src/lib.rs:2</a>"));
+    assert!(html
+        .contains("<li id=\"L2\" class=\"failing\">fn b() { panic!(&quot;&lt;b&gt;&quot;) }</li>"));
+    assert!(html.contains("<li id=\"L1\">fn a() {}</li>"));
+
+    // Findings of binaries link to their entry point
+    let raw_log = "Tests results for \"safe_parse\"\n\tKLEE: ERROR: src/main.rs:2: abort failure\n";
+    let report = parser::parse_report("run", "program_verification", "abc", raw_log, "main.rs");
+
+    let html = render_report(&report, "fn main() {}\nfn parse() {}\n", "main.rs");
+
+    assert!(html.contains("<a href=\"#L2\">src/main.rs:2</a>"));
+    assert!(html.contains("<li id=\"L2\" class=\"failing\">fn parse() {}</li>"));
+}
+
+#[test]
+fn it_renders_diagnostics_of_linting_runs() {
+    use crate::domain::value_object::CLIPPY_PARSER;
+    use crate::infra::report::parser;
+
+    let raw_log = r#"{"reason":"compiler-message","message":{"message":"unneeded `return` statement","code":{"code":"clippy::needless_return","explanation":null},"level":"warning","spans":[{"file_name":"src/lib.rs","line_start":2,"line_end":2,"column_start":10,"column_end":18,"is_primary":true,"suggested_replacement":null,"suggestion_applicability":null}],"children":[]}}"#;
+    let report = parser::parse_step_report(
+        CLIPPY_PARSER,
+        "run",
+        "program_linting",
+        "abc",
+        raw_log,
+        "lib.rs",
+    );
+
+    let html = render_report(&report, "fn a() {}\nfn b() { return; }\n", "lib.rs");
+
+    assert!(html.contains("<h2>Diagnostics</h2>"));
+    assert!(html.contains(
+        "<a href=\"#L2\">src/lib.rs:2:10</a> warning <code>clippy::needless_return</code>: unneeded `return` statement"
+    ));
+}
+
+#[test]
+fn it_renders_undefined_behaviors_detected_by_miri() {
+    use crate::infra::report::miri;
+
+    let raw_log = "test tests::safe_transfer ... error: Undefined Behavior: dereferencing pointer failed\n  --> src/lib.rs:2:10\n   = note: inside `tests::safe_transfer` at src/lib.rs:2:10: 2:14\n";
+    let report = miri::parse_report("run", "program_miri", "abc", raw_log, "lib.rs");
+
+    let html = render_report(&report, "fn a() {}\nfn b() { unsafe { *ptr } }\n", "lib.rs");
+
+    assert!(html.contains("<span class=\"failed\">undefined_behavior</span> in <code>tests::safe_transfer</code> src/lib.rs:2:10: dereferencing pointer failed"));
+    assert!(html.contains("<li id=\"L2\" class=\"failing\">"));
+}
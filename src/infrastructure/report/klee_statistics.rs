@@ -0,0 +1,112 @@
+use crate::domain::value_object::KleeStatistics;
+
+pub static BEGIN_KLEE_STATS: &str = "__BEGIN_KLEE_STATS__";
+pub static END_KLEE_STATS: &str = "__END_KLEE_STATS__";
+
+static KLEE_DONE_PREFIX: &str = "KLEE: done:";
+static ELAPSED_PREFIX: &str = "Elapsed:";
+
+/// Parse a counter reported by KLEE when done exploring an entry point
+/// (e.g. "KLEE: done: completed paths = 4").
+pub fn parse_done_line(line: &str, statistics: &mut KleeStatistics) {
+    let counter = match line.trim().strip_prefix(KLEE_DONE_PREFIX) {
+        Some(counter) => counter,
+        None => return,
+    };
+
+    let (name, value) = match counter.split_once('=') {
+        Some((name, value)) => (name.trim(), value.trim().parse::<u64>().ok()),
+        None => return,
+    };
+
+    match name {
+        "total instructions" => statistics.total_instructions = value,
+        "completed paths" => statistics.completed_paths = value,
+        "partially completed paths" => statistics.partially_completed_paths = value,
+        "generated tests" => statistics.generated_tests = value,
+        _ => {}
+    }
+}
+
+/// Parse the wall-clock time written by KLEE in its info file
+/// (e.g. "Elapsed: 00:01:03") into seconds.
+///
+/// # Examples
+///
+/// ```
+/// use safepkt_backend::infra::report::klee_statistics;
+///
+/// assert_eq!(Some(63.0), klee_statistics::parse_elapsed_line("Elapsed: 00:01:03"));
+/// assert_eq!(None, klee_statistics::parse_elapsed_line("KLEE: done: generated tests = 1"));
+/// ```
+///
+pub fn parse_elapsed_line(line: &str) -> Option<f64> {
+    let elapsed = line.trim().strip_prefix(ELAPSED_PREFIX)?.trim();
+
+    elapsed.split(':').try_fold(0.0, |seconds, part| {
+        part.trim().parse::<f64>().ok().map(|p| seconds * 60.0 + p)
+    })
+}
+
+fn column_value(header: &[&str], row: &[&str], names: &[&str]) -> Option<f64> {
+    names.iter().find_map(|name| {
+        header
+            .iter()
+            .position(|column| column.eq_ignore_ascii_case(name))
+            .and_then(|index| row.get(index))
+            .and_then(|value| value.trim().parse::<f64>().ok())
+    })
+}
+
+/// Parse the CSV table printed by `klee-stats --table-format=csv`
+/// for a single KLEE output directory.
+pub fn parse_klee_stats_table(rows: &[String], statistics: &mut KleeStatistics) {
+    let header: Vec<&str> = match rows.first() {
+        Some(header) => header.split(',').map(str::trim).collect(),
+        None => return,
+    };
+    let row: Vec<&str> = match rows.get(1) {
+        Some(row) => row.split(',').map(str::trim).collect(),
+        None => return,
+    };
+
+    if let Some(instructions) = column_value(&header, &row, &["Instrs"]) {
+        statistics.total_instructions = Some(instructions as u64);
+    }
+    if let Some(time) = column_value(&header, &row, &["Time(s)", "WallTime(s)"]) {
+        statistics.total_time = Some(time);
+    }
+    statistics.instruction_coverage = column_value(&header, &row, &["ICov(%)"]);
+    statistics.branch_coverage = column_value(&header, &row, &["BCov(%)"]);
+
+    statistics.solver_time =
+        column_value(&header, &row, &["TSolver(s)", "SolverTime(s)"]).or_else(|| {
+            let percentage = column_value(&header, &row, &["TSolver(%)"])?;
+            statistics
+                .total_time
+                .map(|total_time| total_time * percentage / 100.0)
+        });
+}
+
+#[test]
+fn it_parses_klee_stats_table() {
+    let rows = vec![
+        "Path,Instrs,Time(s),ICov(%),BCov(%),ICount,TSolver(%)".to_string(),
+        "kleeout/tests::safe_transfer,1234,2.00,75.50,60.25,900,50.00".to_string(),
+    ];
+
+    let mut statistics = KleeStatistics::default();
+    parse_done_line("KLEE: done: completed paths = 4", &mut statistics);
+    parse_done_line("KLEE: done: partially completed paths = 1", &mut statistics);
+    parse_done_line("KLEE: done: generated tests = 5", &mut statistics);
+    parse_klee_stats_table(&rows, &mut statistics);
+
+    assert_eq!(Some(4), statistics.completed_paths);
+    assert_eq!(Some(1), statistics.partially_completed_paths);
+    assert_eq!(Some(5), statistics.generated_tests);
+    assert_eq!(Some(1234), statistics.total_instructions);
+    assert_eq!(Some(75.5), statistics.instruction_coverage);
+    assert_eq!(Some(60.25), statistics.branch_coverage);
+    assert_eq!(Some(2.0), statistics.total_time);
+    assert_eq!(Some(1.0), statistics.solver_time);
+}
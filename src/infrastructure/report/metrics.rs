@@ -0,0 +1,129 @@
+use crate::domain::value_object::{KleeStatistics, StepReport};
+
+/// Statistics of KLEE exposed as metrics, along with their help text
+static KLEE_METRICS: [(&str, &str); 8] = [
+    (
+        "safepkt_klee_completed_paths",
+        "Paths completely explored by KLEE",
+    ),
+    (
+        "safepkt_klee_partially_completed_paths",
+        "Paths left partially explored by KLEE",
+    ),
+    ("safepkt_klee_generated_tests", "Tests generated by KLEE"),
+    (
+        "safepkt_klee_total_instructions",
+        "Instructions executed by KLEE",
+    ),
+    (
+        "safepkt_klee_instruction_coverage_percent",
+        "Instruction coverage reached by KLEE",
+    ),
+    (
+        "safepkt_klee_branch_coverage_percent",
+        "Branch coverage reached by KLEE",
+    ),
+    (
+        "safepkt_klee_solver_time_seconds",
+        "Time spent by KLEE in its solver",
+    ),
+    ("safepkt_klee_total_time_seconds", "Time spent by KLEE"),
+];
+
+fn metric_value(statistics: &KleeStatistics, metric: &str) -> Option<f64> {
+    match metric {
+        "safepkt_klee_completed_paths" => statistics.completed_paths.map(|value| value as f64),
+        "safepkt_klee_partially_completed_paths" => statistics
+            .partially_completed_paths
+            .map(|value| value as f64),
+        "safepkt_klee_generated_tests" => statistics.generated_tests.map(|value| value as f64),
+        "safepkt_klee_total_instructions" => {
+            statistics.total_instructions.map(|value| value as f64)
+        }
+        "safepkt_klee_instruction_coverage_percent" => statistics.instruction_coverage,
+        "safepkt_klee_branch_coverage_percent" => statistics.branch_coverage,
+        "safepkt_klee_solver_time_seconds" => statistics.solver_time,
+        "safepkt_klee_total_time_seconds" => statistics.total_time,
+        _ => None,
+    }
+}
+
+/// Escape the value of a label of the Prometheus text format
+///
+/// # Examples
+///
+/// ```
+/// use safepkt_backend::infra::report::metrics;
+///
+/// assert_eq!(r#"tests::\"safe\"\n"#, metrics::escape_label_value("tests::\"safe\"\n"));
+/// ```
+///
+pub fn escape_label_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Render the KLEE statistics of the entry points of runs in the Prometheus text format,
+/// each sample being labelled with its run, step, project and entry point.
+pub fn render_metrics(reports: &[StepReport]) -> String {
+    let mut metrics = String::new();
+
+    for (metric, help) in KLEE_METRICS.iter() {
+        metrics.push_str(format!("# HELP {} {}.\n", metric, help).as_str());
+        metrics.push_str(format!("# TYPE {} gauge\n", metric).as_str());
+
+        for report in reports {
+            for test in report.tests() {
+                let value = match test
+                    .statistics()
+                    .and_then(|statistics| metric_value(statistics, metric))
+                {
+                    Some(value) => value,
+                    None => continue,
+                };
+
+                metrics.push_str(
+                    format!(
+                        "{}{{run_id=\"{}\",step=\"{}\",project_id=\"{}\",entry_point=\"{}\"}} {}\n",
+                        metric,
+                        escape_label_value(report.run_id()),
+                        escape_label_value(report.step()),
+                        escape_label_value(report.project_id()),
+                        escape_label_value(test.name()),
+                        value
+                    )
+                    .as_str(),
+                );
+            }
+        }
+    }
+
+    metrics
+}
+
+#[test]
+fn it_renders_klee_statistics_per_entry_point() {
+    use crate::infra::report::parser::{parse_report, KLEE_STATISTICS_LOG};
+
+    let report = parse_report(
+        "program_verification-abc",
+        "program_verification",
+        "abc",
+        KLEE_STATISTICS_LOG,
+        "lib.rs",
+    );
+    let metrics = render_metrics(&[report]);
+    let labels = r#"{run_id="program_verification-abc",step="program_verification",project_id="abc",entry_point="safe_transfer_works"}"#;
+
+    assert!(metrics.contains("# TYPE safepkt_klee_partially_completed_paths gauge\n"));
+    assert!(
+        metrics.contains(format!("safepkt_klee_partially_completed_paths{} 3\n", labels).as_str())
+    );
+    assert!(metrics.contains(format!("safepkt_klee_total_time_seconds{} 6.5\n", labels).as_str()));
+    assert!(metrics.contains(format!("safepkt_klee_solver_time_seconds{} 0.65\n", labels).as_str()));
+
+    // Entry points KLEE has reported nothing about are left out
+    assert!(!metrics.contains("safe_balance"));
+}
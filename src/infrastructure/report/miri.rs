@@ -0,0 +1,344 @@
+use crate::domain::value_object::{
+    Finding, SourceLocation, StepReport, TestReport, UndefinedBehavior, Verdict,
+};
+use crate::infra::report::parser::{find_or_insert_test, uploaded_source_path};
+use regex::Regex;
+
+static ERROR_PREFIX: &str = "error: ";
+static UNDEFINED_BEHAVIOR_PREFIX: &str = "Undefined Behavior: ";
+static DATA_RACE_PREFIX: &str = "Data race detected";
+static LOCATION_PREFIX: &str = "--> ";
+static FRAME_PREFIXES: [&str; 2] = ["= note: inside ", "note: inside "];
+static ABORTING: &str = "error: aborting due to";
+static TEST_RESULT_PREFIX: &str = "test result:";
+
+/// Categorize the errors reported by Miri,
+/// other errors (e.g. "aborting due to 1 previous error") being left out.
+fn classify(error: &str) -> Option<(&'static str, &str)> {
+    if let Some(message) = error.strip_prefix(UNDEFINED_BEHAVIOR_PREFIX) {
+        if message.starts_with(DATA_RACE_PREFIX) {
+            return Some(("data_race", message));
+        }
+
+        return Some(("undefined_behavior", message));
+    }
+
+    [
+        ("memory leaked", "memory_leak"),
+        ("unsupported operation", "unsupported_operation"),
+        ("deadlock", "deadlock"),
+        ("abnormal termination", "abnormal_termination"),
+    ]
+    .iter()
+    .find(|(prefix, _)| error.starts_with(prefix))
+    .map(|(_, kind)| (*kind, error))
+}
+
+/// Parse a location (e.g. "src/lib.rs:10:14")
+fn parse_location(location: &str) -> Option<SourceLocation> {
+    let mut parts = location.trim().rsplitn(3, ':');
+    let column = parts.next()?.parse::<usize>().ok()?;
+    let line = parts.next()?.parse::<usize>().ok()?;
+    let file = parts.next()?;
+
+    Some(SourceLocation {
+        file: file.to_string(),
+        line,
+        column,
+    })
+}
+
+struct MiriReport {
+    tests: Vec<TestReport>,
+    undefined_behaviors: Vec<UndefinedBehavior>,
+    source_path: String,
+    source_line: Regex,
+}
+
+impl MiriReport {
+    /// Line of the uploaded source where undefined behaviour occurs,
+    /// which is either where it has been detected or the innermost frame of the contract.
+    fn source_line(&self, undefined_behavior: &UndefinedBehavior) -> Option<usize> {
+        let location = undefined_behavior
+            .location
+            .as_ref()
+            .filter(|location| location.file.ends_with(self.source_path.as_str()))
+            .map(|location| location.line);
+
+        location.or_else(|| {
+            undefined_behavior.backtrace.iter().find_map(|frame| {
+                self.source_line
+                    .captures(frame)
+                    .and_then(|captures| captures[1].parse::<usize>().ok())
+            })
+        })
+    }
+
+    fn add(&mut self, undefined_behavior: UndefinedBehavior) {
+        if let Some(test_name) = undefined_behavior.test.clone() {
+            let line = self.source_line(&undefined_behavior);
+            let test = find_or_insert_test(&mut self.tests, test_name.as_str());
+
+            test.set_verdict(Verdict::Failed);
+            test.add_finding(Finding::new(
+                format!(
+                    "{}: {}",
+                    undefined_behavior.kind, undefined_behavior.message
+                ),
+                line,
+            ));
+        }
+
+        self.undefined_behaviors.push(undefined_behavior);
+    }
+}
+
+/// Parse the output of `cargo miri test`
+/// into a report listing the verdict of each test
+/// alongside the undefined behaviours detected by Miri
+/// (kind, location and backtrace),
+/// lines being located in the entry point of the project (e.g. "lib.rs").
+///
+/// # Examples
+///
+/// ```
+/// use safepkt_backend::app::domain::value_object::Verdict;
+/// use safepkt_backend::infra::report::miri;
+///
+/// let raw_log = "test tests::safe_transfer ... error: Undefined Behavior: dereferencing pointer failed: null pointer is a dangling pointer\n  --> src/lib.rs:10:14\n";
+/// let report = miri::parse_report("program_miri-abc", "program_miri", "abc", raw_log, "lib.rs");
+///
+/// assert_eq!("undefined_behavior", report.undefined_behaviors()[0].kind);
+/// assert_eq!(Verdict::Failed, report.tests()[0].verdict());
+/// assert_eq!(vec![10], report.failing_lines());
+/// ```
+///
+pub fn parse_report(
+    run_id: &str,
+    step: &str,
+    project_id: &str,
+    raw_log: &str,
+    entry_point: &str,
+) -> StepReport {
+    let test_outcome = Regex::new(r"^test\s+(\S+)\s+\.\.\.\s*(.*)$").unwrap();
+    let source_path = uploaded_source_path(entry_point);
+    let mut report = MiriReport {
+        tests: vec![],
+        undefined_behaviors: vec![],
+        source_line: Regex::new(format!(r"{}:(\d+)", regex::escape(source_path.as_str())).as_str())
+            .unwrap(),
+        source_path,
+    };
+
+    let mut current_test: Option<String> = None;
+    let mut current: Option<UndefinedBehavior> = None;
+    // Frames printed without location (e.g. "note: inside closure")
+    // are followed by a line pointing at it
+    let mut pending_frame: Option<String> = None;
+
+    for line in raw_log.lines() {
+        let trimmed_line = line.trim();
+        let mut error = trimmed_line.strip_prefix(ERROR_PREFIX);
+
+        if let Some(captures) = test_outcome.captures(trimmed_line) {
+            let name = captures[1].to_string();
+            let outcome = captures.get(2).map_or("", |outcome| outcome.as_str());
+
+            // Errors reported once tests have completed (e.g. memory leaks)
+            // are not attributed to any of them
+            current_test = match outcome {
+                "ok" => {
+                    find_or_insert_test(&mut report.tests, name.as_str())
+                        .set_verdict(Verdict::Passed);
+                    None
+                }
+                "FAILED" => {
+                    find_or_insert_test(&mut report.tests, name.as_str())
+                        .set_verdict(Verdict::Failed);
+                    None
+                }
+                _ => Some(name),
+            };
+            error = outcome.strip_prefix(ERROR_PREFIX);
+        }
+
+        if let Some((kind, message)) = error.and_then(classify) {
+            if let Some(undefined_behavior) = current.take() {
+                report.add(undefined_behavior);
+            }
+            pending_frame = None;
+
+            current = Some(UndefinedBehavior {
+                kind: kind.to_string(),
+                message: message.to_string(),
+                test: current_test.clone(),
+                location: None,
+                backtrace: vec![],
+            });
+            continue;
+        }
+
+        let undefined_behavior = match current.as_mut() {
+            Some(undefined_behavior) => undefined_behavior,
+            None => continue,
+        };
+
+        if trimmed_line.starts_with(ABORTING)
+            || trimmed_line.starts_with(TEST_RESULT_PREFIX)
+            || test_outcome.is_match(trimmed_line)
+        {
+            if let Some(undefined_behavior) = current.take() {
+                report.add(undefined_behavior);
+            }
+            pending_frame = None;
+            continue;
+        }
+
+        if let Some(location) = trimmed_line.strip_prefix(LOCATION_PREFIX) {
+            match pending_frame.take() {
+                Some(frame) => undefined_behavior
+                    .backtrace
+                    .push(format!("{} at {}", frame, location)),
+                None if undefined_behavior.location.is_none() => {
+                    undefined_behavior.location = parse_location(location)
+                }
+                None => {}
+            }
+            continue;
+        }
+
+        if let Some(frame) = FRAME_PREFIXES
+            .iter()
+            .find_map(|prefix| trimmed_line.strip_prefix(prefix))
+        {
+            if let Some(frame) = pending_frame.take() {
+                undefined_behavior.backtrace.push(frame);
+            }
+
+            let frame = format!("inside {}", frame);
+            if frame.contains(" at ") {
+                undefined_behavior.backtrace.push(frame);
+            } else {
+                pending_frame = Some(frame);
+            }
+        }
+    }
+
+    if let Some(undefined_behavior) = current.take() {
+        report.add(undefined_behavior);
+    }
+
+    let mut step_report = StepReport::new(
+        run_id.to_string(),
+        step.to_string(),
+        project_id.to_string(),
+        report.tests,
+    );
+    step_report.set_undefined_behaviors(report.undefined_behaviors);
+
+    step_report
+}
+
+#[test]
+fn it_parses_undefined_behaviors_detected_by_miri() {
+    let raw_log = r#"
+running 3 tests
+test tests::safe_balance_of ... ok
+test tests::safe_transfer ... error: Undefined Behavior: out-of-bounds pointer use: alloc1234 has size 4, so pointer to 8 bytes starting at offset 0 is out-of-bounds
+  --> src/lib.rs:42:18
+   |
+42 |         unsafe { *ptr.add(1) }
+   |                  ^^^^^^^^^^^ out-of-bounds pointer use
+   |
+   = help: this indicates a bug in the program: it performed an invalid operation, and caused Undefined Behavior
+   = note: BACKTRACE:
+   = note: inside `Erc20::read` at src/lib.rs:42:18: 42:29
+note: inside `tests::safe_transfer`
+  --> src/lib.rs:120:9
+   |
+120|         erc20.read();
+   |         ^^^^^^^^^^^^
+note: inside closure
+  --> src/lib.rs:118:24
+
+error: aborting due to 1 previous error
+
+error: test failed, to rerun pass `--lib`
+"#;
+
+    let report = parse_report("run", "program_miri", "abc", raw_log, "lib.rs");
+
+    assert_eq!(
+        vec![UndefinedBehavior {
+            kind: "undefined_behavior".to_string(),
+            message: "out-of-bounds pointer use: alloc1234 has size 4, so pointer to 8 bytes starting at offset 0 is out-of-bounds".to_string(),
+            test: Some("tests::safe_transfer".to_string()),
+            location: Some(SourceLocation {
+                file: "src/lib.rs".to_string(),
+                line: 42,
+                column: 18,
+            }),
+            backtrace: vec![
+                "inside `Erc20::read` at src/lib.rs:42:18: 42:29".to_string(),
+                "inside `tests::safe_transfer` at src/lib.rs:120:9".to_string(),
+                "inside closure at src/lib.rs:118:24".to_string(),
+            ],
+        }],
+        report.undefined_behaviors()
+    );
+
+    assert_eq!(2, report.tests().len());
+    assert_eq!("safe_balance_of", report.tests()[0].name());
+    assert_eq!(Verdict::Passed, report.tests()[0].verdict());
+    assert_eq!(Verdict::Failed, report.tests()[1].verdict());
+    assert_eq!(vec![42], report.failing_lines());
+}
+
+#[test]
+fn it_locates_memory_leaks_in_the_uploaded_source() {
+    let raw_log = r#"
+test tests::safe_mint ... ok
+error: memory leaked: alloc77 (Rust heap, size: 4, align: 4), allocated here:
+  --> /rustc/0a1b2c/library/alloc/src/alloc.rs:98:9
+   |
+   = note: BACKTRACE:
+   = note: inside `std::alloc::alloc` at /rustc/0a1b2c/library/alloc/src/alloc.rs:98:9: 98:64
+   = note: inside `Erc721::mint` at src/lib.rs:64:9: 64:31
+"#;
+
+    let report = parse_report("run", "program_miri", "abc", raw_log, "lib.rs");
+
+    let undefined_behavior = &report.undefined_behaviors()[0];
+    assert_eq!("memory_leak", undefined_behavior.kind);
+    assert_eq!(None, undefined_behavior.test);
+    assert_eq!(
+        Some("/rustc/0a1b2c/library/alloc/src/alloc.rs".to_string()),
+        undefined_behavior
+            .location
+            .as_ref()
+            .map(|location| location.file.clone())
+    );
+    assert_eq!(
+        "inside `Erc721::mint` at src/lib.rs:64:9: 64:31",
+        undefined_behavior.backtrace[1]
+    );
+    assert_eq!(Verdict::Passed, report.tests()[0].verdict());
+}
+
+#[test]
+fn it_locates_undefined_behaviors_in_the_entry_point_of_binaries() {
+    let raw_log = r#"
+test tests::safe_parse ... error: Undefined Behavior: using uninitialized data, but this operation requires initialized memory
+  --> /rustc/0a1b2c/library/core/src/ptr/mod.rs:12:5
+   |
+   = note: BACKTRACE:
+   = note: inside `std::ptr::read` at /rustc/0a1b2c/library/core/src/ptr/mod.rs:12:5: 12:20
+   = note: inside `parse` at src/main.rs:7:13: 7:30
+"#;
+
+    let report = parse_report("run", "program_miri", "abc", raw_log, "main.rs");
+    assert_eq!(vec![7], report.failing_lines());
+
+    let report = parse_report("run", "program_miri", "abc", raw_log, "lib.rs");
+    assert!(report.failing_lines().is_empty());
+}
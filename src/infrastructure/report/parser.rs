@@ -0,0 +1,417 @@
+use crate::domain::value_object::{
+    Finding, KleeStatistics, StepReport, TestReport, Verdict, AUDIT_PARSER, CLIPPY_PARSER,
+    KLEE_PARSER, MIRI_PARSER,
+};
+use crate::infra::report::klee_statistics;
+use crate::infra::report::{audit, clippy, miri};
+use regex::Regex;
+use std::collections::HashMap;
+
+static TEST_RESULTS_PREFIX: &str = "Tests results for ";
+static EXPECTED_PANIC: &str = "Expected panic occurred.";
+static MISSING_PANIC: &str = "Panic should have occurred.";
+static KLEE_ERROR_PREFIX: &str = "KLEE: ERROR:";
+static KLEE_DONE_PREFIX: &str = "KLEE: done:";
+static KLEE_PREFIX: &str = "KLEE:";
+static MINIMAL_FAILING_INPUT: &str = "minimal failing input:";
+static TEST_VALUES: &str = "Test values:";
+
+struct Patterns {
+    test_outcome: Regex,
+    panicking_thread: Regex,
+    source_line: Regex,
+}
+
+impl Patterns {
+    fn new(entry_point: &str) -> Patterns {
+        let source_path = regex::escape(uploaded_source_path(entry_point).as_str());
+
+        Patterns {
+            test_outcome: Regex::new(r"^test\s+(\S+)\s+\.\.\.\s+(\S+)").unwrap(),
+            panicking_thread: Regex::new(r"thread '([^']+)' panicked at").unwrap(),
+            source_line: Regex::new(format!(r"{}:(\d+)", source_path).as_str()).unwrap(),
+        }
+    }
+
+    fn source_line(&self, line: &str) -> Option<usize> {
+        self.source_line
+            .captures(line)
+            .and_then(|captures| captures.get(1))
+            .and_then(|line_number| line_number.as_str().parse::<usize>().ok())
+    }
+}
+
+/// Path of the uploaded source in a scaffolded project,
+/// from the entry point of the project (e.g. "lib.rs")
+///
+/// # Examples
+///
+/// ```
+/// use safepkt_backend::infra::report::parser;
+///
+/// assert_eq!("src/main.rs", parser::uploaded_source_path("main.rs"));
+/// ```
+///
+pub fn uploaded_source_path(entry_point: &str) -> String {
+    format!("src/{}", entry_point)
+}
+
+/// Tests are named after their last path segment by the verification script
+/// whereas test runners print fully qualified names.
+pub(crate) fn short_test_name(name: &str) -> &str {
+    name.rsplit("::").next().unwrap_or(name)
+}
+
+pub(crate) fn find_or_insert_test<'a>(
+    tests: &'a mut Vec<TestReport>,
+    name: &str,
+) -> &'a mut TestReport {
+    let short_name = short_test_name(name);
+
+    match tests.iter().position(|t| t.name() == short_name) {
+        Some(index) => &mut tests[index],
+        None => {
+            tests.push(TestReport::new(short_name.to_string()));
+            tests.last_mut().unwrap()
+        }
+    }
+}
+
+/// Parse the raw log of a step
+/// into a report listing the verdict of each test
+/// alongside findings and counterexamples,
+/// lines being located in the entry point of the project (e.g. "lib.rs").
+///
+/// # Examples
+///
+/// ```
+/// use safepkt_backend::app::domain::value_object::Verdict;
+/// use safepkt_backend::infra::report::parser;
+///
+/// let raw_log = "Tests results for \"safe_transfer\"\n\tKLEE: ERROR: src/lib.rs:42: abort failure\n";
+/// let report = parser::parse_report("program_verification-abc", "program_verification", "abc", raw_log, "lib.rs");
+///
+/// assert_eq!(1, report.tests().len());
+/// assert_eq!(Verdict::Failed, report.tests()[0].verdict());
+/// assert_eq!(vec![42], report.failing_lines());
+/// ```
+///
+pub fn parse_report(
+    run_id: &str,
+    step: &str,
+    project_id: &str,
+    raw_log: &str,
+    entry_point: &str,
+) -> StepReport {
+    let patterns = Patterns::new(entry_point);
+    let mut tests: Vec<TestReport> = vec![];
+    let mut current_test: Option<String> = None;
+    let mut collecting_test_values = false;
+    let mut klee_stats_rows: Option<Vec<String>> = None;
+    let mut statistics = HashMap::<String, KleeStatistics>::new();
+
+    for line in raw_log.lines() {
+        let trimmed_line = line.trim();
+
+        if let Some(rows) = klee_stats_rows.as_mut() {
+            if trimmed_line != klee_statistics::END_KLEE_STATS {
+                if !trimmed_line.is_empty() {
+                    rows.push(trimmed_line.to_string());
+                }
+                continue;
+            }
+
+            if let Some(name) = &current_test {
+                let test_statistics = statistics.entry(name.clone()).or_default();
+                klee_statistics::parse_klee_stats_table(rows, test_statistics);
+            }
+            klee_stats_rows = None;
+            continue;
+        }
+
+        if collecting_test_values {
+            if !trimmed_line.is_empty() && line.starts_with(char::is_whitespace) {
+                if let Some(name) = &current_test {
+                    find_or_insert_test(&mut tests, name)
+                        .add_counterexample(trimmed_line.to_string());
+                }
+                continue;
+            }
+            collecting_test_values = false;
+        }
+
+        if let Some(name) = trimmed_line.strip_prefix(TEST_RESULTS_PREFIX) {
+            let name = name.trim_matches('"');
+            find_or_insert_test(&mut tests, name);
+            current_test = Some(name.to_string());
+            continue;
+        }
+
+        if let Some(captures) = patterns.test_outcome.captures(trimmed_line) {
+            let name = captures.get(1).unwrap().as_str();
+            let outcome = captures.get(2).unwrap().as_str();
+            let test = find_or_insert_test(&mut tests, name);
+
+            if outcome.eq_ignore_ascii_case("ok") {
+                if test.verdict() == Verdict::Unknown {
+                    test.set_verdict(Verdict::Passed);
+                }
+            } else {
+                test.set_verdict(Verdict::Failed);
+            }
+            current_test = Some(name.to_string());
+            continue;
+        }
+
+        if let Some(captures) = patterns.panicking_thread.captures(trimmed_line) {
+            let name = captures.get(1).unwrap().as_str().to_string();
+            let test = find_or_insert_test(&mut tests, name.as_str());
+            test.add_finding(Finding::new(
+                trimmed_line.to_string(),
+                patterns.source_line(trimmed_line),
+            ));
+
+            if let Some(index) = trimmed_line.find(MINIMAL_FAILING_INPUT) {
+                let input = trimmed_line[index + MINIMAL_FAILING_INPUT.len()..]
+                    .split('\'')
+                    .next()
+                    .unwrap_or_default()
+                    .trim();
+                test.add_counterexample(input.to_string());
+            }
+            current_test = Some(name);
+            continue;
+        }
+
+        let test = match &current_test {
+            Some(name) => find_or_insert_test(&mut tests, name),
+            None => continue,
+        };
+
+        if trimmed_line == klee_statistics::BEGIN_KLEE_STATS {
+            klee_stats_rows = Some(vec![]);
+        } else if let Some(elapsed) = klee_statistics::parse_elapsed_line(trimmed_line) {
+            let test_statistics = statistics.entry(test.name().to_string()).or_default();
+            if test_statistics.total_time.is_none() {
+                test_statistics.total_time = Some(elapsed);
+            }
+        } else if trimmed_line.ends_with(TEST_VALUES) {
+            collecting_test_values = true;
+        } else if let Some(index) = trimmed_line.find(MINIMAL_FAILING_INPUT) {
+            let input = trimmed_line[index + MINIMAL_FAILING_INPUT.len()..].trim();
+            test.add_counterexample(input.to_string());
+        } else if trimmed_line == EXPECTED_PANIC {
+            test.set_verdict(Verdict::Passed);
+            test.add_summary_line(trimmed_line.to_string());
+        } else if trimmed_line == MISSING_PANIC {
+            test.set_verdict(Verdict::Failed);
+            test.add_finding(Finding::new(trimmed_line.to_string(), None));
+        } else if let Some(error) = trimmed_line.strip_prefix(KLEE_ERROR_PREFIX) {
+            test.set_verdict(Verdict::Failed);
+            test.add_finding(Finding::new(
+                error.trim().to_string(),
+                patterns.source_line(error),
+            ));
+        } else if trimmed_line.starts_with(KLEE_PREFIX) {
+            if trimmed_line.starts_with(KLEE_DONE_PREFIX) {
+                if test.verdict() == Verdict::Unknown {
+                    test.set_verdict(Verdict::Passed);
+                }
+
+                let test_statistics = statistics.entry(test.name().to_string()).or_default();
+                klee_statistics::parse_done_line(trimmed_line, test_statistics);
+            }
+            test.add_summary_line(trimmed_line.to_string());
+        }
+    }
+
+    for test in tests.iter_mut() {
+        if let Some(test_statistics) = statistics.remove(test.name()) {
+            test.set_statistics(test_statistics);
+        }
+    }
+
+    StepReport::new(
+        run_id.to_string(),
+        step.to_string(),
+        project_id.to_string(),
+        tests,
+    )
+}
+
+/// Parse the raw log of a step with the parser declared in the step registry,
+/// logs of steps without parser being left out of tests.
+pub fn parse_step_report(
+    parser: &str,
+    run_id: &str,
+    step: &str,
+    project_id: &str,
+    raw_log: &str,
+    entry_point: &str,
+) -> StepReport {
+    if parser == KLEE_PARSER {
+        return parse_report(run_id, step, project_id, raw_log, entry_point);
+    }
+
+    if parser == MIRI_PARSER {
+        return miri::parse_report(run_id, step, project_id, raw_log, entry_point);
+    }
+
+    let mut report = StepReport::new(
+        run_id.to_string(),
+        step.to_string(),
+        project_id.to_string(),
+        vec![],
+    );
+
+    if parser == CLIPPY_PARSER {
+        report.set_diagnostics(clippy::parse_diagnostics(raw_log, entry_point));
+    }
+
+    if parser == AUDIT_PARSER {
+        report.set_advisories(audit::parse_advisories(raw_log));
+    }
+
+    report
+}
+
+#[test]
+fn it_parses_klee_results() {
+    let raw_log = r#"
+__BEGIN_EXPECTED_PANICS__
+Tests results for "safe_transfer_works"
+	KLEE: output directory is "/safepkt-ink/examples/source/kleeout/tests::safe_transfer_works"
+	KLEE: done: total instructions = 1234
+	KLEE: done: completed paths = 4
+Tests results for "safe_overflow"
+	KLEE: ERROR: src/lib.rs:87: abort failure
+	KLEE: NOTE: now ignoring this error at this location
+	KLEE: done: completed paths = 2
+Tests results for "safe_transfer_fails"
+	Panic should have occurred.
+__END_EXPECTED_PANICS__
+"#;
+
+    let report = parse_report("run", "program_verification", "abc", raw_log, "lib.rs");
+    let tests = report.tests();
+
+    assert_eq!(3, tests.len());
+    assert_eq!(Verdict::Passed, tests[0].verdict());
+    assert_eq!(3, tests[0].summary().len());
+    assert_eq!(Verdict::Failed, tests[1].verdict());
+    assert_eq!(Some(87), tests[1].findings()[0].line());
+    assert_eq!(Verdict::Failed, tests[2].verdict());
+    assert_eq!(None, tests[2].findings()[0].line());
+    assert_eq!(vec![87], report.failing_lines());
+
+    let statistics = tests[0].statistics().unwrap();
+    assert_eq!(Some(1234), statistics.total_instructions);
+    assert_eq!(Some(4), statistics.completed_paths);
+    assert_eq!(Some(6), report.statistics().unwrap().completed_paths);
+}
+
+/// Logs of a verification which KLEE has reported statistics of for its first entry point only
+#[cfg(test)]
+pub static KLEE_STATISTICS_LOG: &str = r#"
+Tests results for "safe_transfer_works"
+	KLEE: done: completed paths = 1
+	KLEE: done: partially completed paths = 3
+	KLEE: done: generated tests = 1
+	Elapsed: 00:00:07
+	__BEGIN_KLEE_STATS__
+	Path,Instrs,Time(s),ICov(%),BCov(%),ICount,TSolver(%)
+	kleeout/tests::safe_transfer_works,500,6.50,12.00,8.00,900,10.00
+	__END_KLEE_STATS__
+Tests results for "safe_balance"
+"#;
+
+#[test]
+fn it_parses_klee_statistics_per_entry_point() {
+    let report = parse_report(
+        "run",
+        "program_verification",
+        "abc",
+        KLEE_STATISTICS_LOG,
+        "lib.rs",
+    );
+    let test = &report.tests()[0];
+    let statistics = test.statistics().unwrap();
+
+    assert_eq!(Verdict::Passed, test.verdict());
+    assert_eq!(Some(3), statistics.partially_completed_paths);
+    assert_eq!(Some(6.5), statistics.total_time);
+    assert_eq!(Some(12.0), statistics.instruction_coverage);
+    assert_eq!(Some(false), statistics.is_exhaustive());
+}
+
+#[test]
+fn it_parses_proptest_results() {
+    let raw_log = r#"
+running 2 tests
+thread 'tests::safe_balance' panicked at 'Test failed: attempt to add with overflow; minimal failing input: amount = 18446744073709551615', src/lib.rs:120:9
+test tests::safe_total_supply ... ok
+test tests::safe_balance ... FAILED
+"#;
+
+    let report = parse_report("run", "program_fuzzing", "abc", raw_log, "lib.rs");
+    let tests = report.tests();
+
+    assert_eq!(2, tests.len());
+    assert_eq!("safe_balance", tests[0].name());
+    assert_eq!(Verdict::Failed, tests[0].verdict());
+    assert_eq!(Some(120), tests[0].findings()[0].line());
+    assert_eq!(
+        vec!["amount = 18446744073709551615".to_string()],
+        tests[0].counterexamples().to_vec()
+    );
+    assert_eq!(Verdict::Passed, tests[1].verdict());
+}
+
+#[test]
+fn it_parses_reports_with_the_parser_of_a_step() {
+    use crate::domain::value_object::NO_PARSER;
+
+    let raw_log =
+        "Tests results for \"safe_transfer\"\n\tKLEE: ERROR: src/lib.rs:42: abort failure\n";
+
+    let report = parse_step_report(
+        KLEE_PARSER,
+        "run",
+        "program_verification",
+        "abc",
+        raw_log,
+        "lib.rs",
+    );
+    assert_eq!(1, report.tests().len());
+
+    let report = parse_step_report(
+        NO_PARSER,
+        "run",
+        "source_restoration",
+        "abc",
+        raw_log,
+        "lib.rs",
+    );
+    assert!(report.tests().is_empty());
+
+    // Lines are located in the entry point of binaries
+    let raw_log = "Tests results for \"safe_parse\"\n\tKLEE: ERROR: src/main.rs:7: abort failure\n";
+    let report = parse_step_report(
+        KLEE_PARSER,
+        "run",
+        "program_verification",
+        "abc",
+        raw_log,
+        "main.rs",
+    );
+    assert_eq!(vec![7], report.failing_lines());
+    let report = parse_step_report(
+        KLEE_PARSER,
+        "run",
+        "program_verification",
+        "abc",
+        raw_log,
+        "lib.rs",
+    );
+    assert!(report.failing_lines().is_empty());
+}
@@ -7,13 +7,20 @@ use tracing::error;
 
 pub static BASE64_ENCODED_SOURCE_EXTENSION: &str = ".rs.b64";
 
+/// Extension of the file declaring the settings of the project of an uploaded source
+/// (its kind and ink! template)
+pub static PROJECT_SETTINGS_EXTENSION: &str = ".project.json";
+
+/// Extension of the file recording the progress of the last pipeline run against a project
+pub static PIPELINE_PROGRESS_EXTENSION: &str = ".pipeline.json";
+
 /// Hash content before truncating the result
 fn hash_content(content: &[u8]) -> String {
     let mut hasher = Sha256::new();
     hasher.update(content);
     let hash = hasher.finalize().to_vec();
 
-    hex::encode(hash).chars().into_iter().take(10).collect()
+    hex::encode(hash).chars().take(10).collect()
 }
 
 #[test]
@@ -59,6 +66,28 @@ pub fn ensure_directory_exists(path_as_str: &str) -> Result<&path::Path, Report>
     Ok(path)
 }
 
+/// Get the disk space (in bytes) taken by a file or a directory,
+/// symbolic links not being followed.
+pub fn disk_usage(path: &path::Path) -> u64 {
+    let metadata = match fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(_) => return 0,
+    };
+
+    if !metadata.is_dir() {
+        return metadata.len();
+    }
+
+    fs::read_dir(path)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| disk_usage(entry.path().as_path()))
+                .sum()
+        })
+        .unwrap_or_default()
+}
+
 /// Guard against missing file in file system  
 /// located at path provided as argument
 ///
@@ -1,5 +1,10 @@
 use crate::domain::value_object::{Flags, Source};
 use anyhow::Result;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+/// Fields of the messages of steps holding a boolean
+static BOOLEAN_FIELDS: [&str; 1] = ["cached"];
 
 /// Parse JSON content before deserializing it into source.
 ///
@@ -18,27 +23,36 @@ use anyhow::Result;
 /// assert_eq!(expected_source.as_bytes(), source.source());
 /// ```
 ///
-pub fn deserialize_source(subject: &str) -> Result<Source> {
+pub fn deserialize_source(subject: &str) -> Result<Source<'_>> {
     let source: Source = serde_json::from_str(subject)?;
 
     Ok(source)
 }
 
-/// Parse JSON content before deserializing it into flags.
+/// Parse JSON content before deserializing it into flags,
+/// raw flags and unknown options being rejected.
 ///
 /// # Examples
 ///
 /// ```
 /// use safepkt_backend::infra::serializer;
 ///
-/// let json_content = r#"
-/// {"flags": "LS1oZWxw"}
-/// "#;
+/// let flags = serializer::deserialize_flags(r#"{"timeout": 300}"#).unwrap();
+/// assert_eq!(Some(300), flags.timeout());
+/// assert!(!flags.fresh());
 ///
-/// let flags = serializer::deserialize_flags(json_content).unwrap();
+/// let flags = serializer::deserialize_flags(r#"{"fresh": true}"#).unwrap();
+/// assert!(flags.fresh());
 ///
-/// let expected_flags = "LS1oZWxw";
-/// assert_eq!(expected_flags.as_bytes(), flags.flags());
+/// let flags = serializer::deserialize_flags(
+///     r#"{"backend": "proptest", "options": {"cases": 20, "quiet": true}}"#,
+/// )
+/// .unwrap();
+/// assert_eq!(Some("proptest"), flags.backend());
+/// assert_eq!(Some(20), flags.options().cases);
+///
+/// assert!(serializer::deserialize_flags(r#"{"flags": "LS1oZWxw"}"#).is_err());
+/// assert!(serializer::deserialize_flags(r#"{"options": {"cases": "many"}}"#).is_err());
 /// ```
 ///
 pub fn deserialize_flags(subject: &str) -> Result<Flags> {
@@ -46,3 +60,35 @@ pub fn deserialize_flags(subject: &str) -> Result<Flags> {
 
     Ok(flags)
 }
+
+/// Serialize a message of a step (e.g. its progress) into JSON,
+/// fields holding a boolean (e.g. whether results have been cached) being serialized as such.
+///
+/// # Examples
+///
+/// ```
+/// use safepkt_backend::infra::serializer;
+/// use std::collections::HashMap;
+///
+/// let mut message = HashMap::<String, String>::new();
+/// message.insert("cached".to_string(), "true".to_string());
+///
+/// let json = serializer::serialize_step_message(&message).unwrap();
+/// assert_eq!(r#"{"cached":true}"#, String::from_utf8(json).unwrap());
+/// ```
+///
+pub fn serialize_step_message(message: &HashMap<String, String>) -> Result<Vec<u8>> {
+    let message: Map<String, Value> = message
+        .iter()
+        .map(|(name, value)| {
+            let value = match value.parse::<bool>() {
+                Ok(flag) if BOOLEAN_FIELDS.contains(&name.as_str()) => Value::Bool(flag),
+                _ => Value::String(value.clone()),
+            };
+
+            (name.clone(), value)
+        })
+        .collect();
+
+    Ok(serde_json::to_vec(&message)?)
+}
@@ -1,79 +1,93 @@
+pub mod pipeline;
 pub mod program_verification;
+pub mod result_cache;
 pub mod runtime;
+pub mod step_registry;
+pub mod time_budget;
 
 use crate::domain::value_object::*;
 use crate::domain::verification_runtime::*;
 use crate::infra;
 use async_trait::async_trait;
-use bollard::Docker;
 use color_eyre::Report;
 use infra::scaffold;
-use infra::verification_runtime::docker::{container, DockerContainerAPIClient};
 use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
 
+pub const DEPENDENCY_AUDIT: &str = "dependency_audit";
 pub const PROGRAM_FUZZING: &str = "program_fuzzing";
+pub const PROGRAM_LINTING: &str = "program_linting";
+pub const PROGRAM_MIRI: &str = "program_miri";
 pub const PROGRAM_VERIFICATION: &str = "program_verification";
 pub const SOURCE_RESTORATION: &str = "source_restoration";
 pub const UPLOADED_SOURCES_LISTING: &str = "uploaded_sources_listing";
 
-impl<'a> VerificationRuntime<'a, DockerContainerAPIClient<Docker>> {
+/// Container API client shared between a runtime
+/// and the tasks it spawns (e.g. to enforce time budgets).
+pub type SharedContainerAPIClient = Arc<
+    dyn ContainerAPIClient<R = Result<HashMap<String, String>, Report>, P = Result<(), Report>>
+        + Send
+        + Sync,
+>;
+
+/// Make container API clients on demand,
+/// so that runtimes built per request can share an injected client.
+pub type ContainerAPIClientFactory =
+    Arc<dyn Fn() -> Result<SharedContainerAPIClient, Report> + Send + Sync>;
+
+/// Make a factory of clients of the runtime selected by configuration
+pub fn default_container_api_client_factory() -> ContainerAPIClientFactory {
+    Arc::new(runtime::new_container_api_client)
+}
+
+impl<'a> VerificationRuntime<'a, SharedContainerAPIClient> {
+    /// Create a runtime relying on the container API client
+    /// selected by configuration (VERIFICATION_RUNTIME environment variable).
     pub fn new(
         step: StepInVerificationPlan<'a>,
         steps: HashMap<String, Step<'a>>,
     ) -> Result<Self, Report> {
-        let container_api_client = DockerContainerAPIClient::new().unwrap();
-        let runtime = VerificationRuntime {
-            container_api_client,
-            step_in_verification_plan: step,
-            verification_step_collection: VerificationStepsCollection::new(steps),
-        };
+        let container_api_client = runtime::new_container_api_client()?;
 
-        Ok(runtime)
+        Ok(Self::with_container_api_client(
+            container_api_client,
+            step,
+            steps,
+        ))
     }
 
-    pub fn build_steps(flags: Option<&str>) -> HashMap<String, Step> {
-        let mut steps = HashMap::<String, Step>::new();
-
-        steps.insert(
-            PROGRAM_FUZZING.to_string(),
-            Step::new(
-                PROGRAM_FUZZING,
-                container::program_fuzzing_cmd_provider(),
-                flags,
-            ),
-        );
-
-        steps.insert(
-            PROGRAM_VERIFICATION.to_string(),
-            Step::new(
-                PROGRAM_VERIFICATION,
-                container::program_verification_cmd_provider(),
-                flags,
-            ),
-        );
-
-        steps.insert(
-            UPLOADED_SOURCES_LISTING.to_string(),
-            Step::new(
-                UPLOADED_SOURCES_LISTING,
-                container::uploaded_sources_listing_cmd_provider(),
-                None,
-            ),
-        );
+    /// Create a runtime relying on a client made by a factory
+    pub fn with_container_api_client_factory(
+        factory: &ContainerAPIClientFactory,
+        step: StepInVerificationPlan<'a>,
+        steps: HashMap<String, Step<'a>>,
+    ) -> Result<Self, Report> {
+        Ok(Self::with_container_api_client(factory()?, step, steps))
+    }
 
-        steps.insert(
-            SOURCE_RESTORATION.to_string(),
-            Step::new(
-                SOURCE_RESTORATION,
-                container::source_code_restoration_cmd_provider(),
-                None,
-            ),
-        );
+    pub fn with_container_api_client(
+        container_api_client: SharedContainerAPIClient,
+        step: StepInVerificationPlan<'a>,
+        steps: HashMap<String, Step<'a>>,
+    ) -> Self {
+        VerificationRuntime {
+            container_api_client,
+            step_in_verification_plan: step,
+            verification_step_collection: VerificationStepsCollection::new(steps),
+        }
+    }
 
-        steps
+    /// Build the steps declared in the step registry
+    pub fn build_steps() -> Result<HashMap<String, Step<'static>>, Report> {
+        Ok(step_registry::step_registry()?
+            .steps()
+            .iter()
+            .map(|definition| (definition.name().to_string(), Step::new(definition)))
+            .collect())
     }
 
-    pub fn container_api_client(&self) -> &DockerContainerAPIClient<Docker> {
+    pub fn container_api_client(&self) -> &SharedContainerAPIClient {
         &self.container_api_client
     }
 
@@ -81,15 +95,15 @@ impl<'a> VerificationRuntime<'a, DockerContainerAPIClient<Docker>> {
         self.step_in_verification_plan.project_id().as_str()
     }
 
-    pub fn project_step(&self) -> &Step {
-        &self.step_in_verification_plan.step()
+    pub fn project_step(&self) -> &Step<'_> {
+        self.step_in_verification_plan.step()
     }
 
-    pub fn step_in_verification_plan(&self) -> &StepInVerificationPlan {
+    pub fn step_in_verification_plan(&self) -> &StepInVerificationPlan<'_> {
         &self.step_in_verification_plan
     }
 
-    pub fn verification_step_collection(&self) -> &VerificationStepsCollection {
+    pub fn verification_step_collection(&self) -> &VerificationStepsCollection<'_> {
         &self.verification_step_collection
     }
 
@@ -101,52 +115,115 @@ impl<'a> VerificationRuntime<'a, DockerContainerAPIClient<Docker>> {
 
         let mut message = HashMap::<String, String>::new();
 
-        match client
-            .start_container(&self.step_in_verification_plan())
-            .await
-        {
-            Ok(_) => {}
-            Err(report) => return Err(report),
-        }
+        let started = client
+            .start_container(self.step_in_verification_plan())
+            .await?;
 
-        message.insert(
-            "container_name".to_string(),
-            client
-                .format_container_name_for_step_in_verification_plan(project_step)
-                .clone(),
+        let run_id = program_verification::format_run_id(
+            project_step.step().name(),
+            project_step.project_id(),
         );
+
+        message.insert("container_name".to_string(), run_id.clone());
+        message.insert("run_id".to_string(), run_id);
+        if let Some(backend) = project_step.backend() {
+            message.insert(
+                "backend".to_string(),
+                backend.backend().as_str().to_string(),
+            );
+            message.insert("backend_options".to_string(), backend.format_options());
+        }
         message.insert(
             "message".to_string(),
             String::from("Rust verification tools container started successfully."),
         );
+        // Clients can describe how a run has been started (e.g. served from cache)
+        message.extend(started);
 
         Ok(message)
     }
 }
 
+/// Add the duration (in seconds) and the interpreted outcome of a run
+/// to the progress reported by a container API client.
+fn describe_outcome(progress: &mut HashMap<String, String>) {
+    let status = progress.get("raw_status").cloned().unwrap_or_default();
+    let timestamp = |name: &str| {
+        progress
+            .get(name)
+            .and_then(|timestamp| timestamp.parse::<u64>().ok())
+    };
+
+    // Runs still going on have lasted until now
+    let finished_at = timestamp("finished_at").or_else(|| {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .ok()
+            .map(|elapsed| elapsed.as_secs())
+            .filter(|_| status == "running")
+    });
+
+    if let (Some(started_at), Some(finished_at)) = (timestamp("started_at"), finished_at) {
+        progress.insert(
+            "duration".to_string(),
+            finished_at.saturating_sub(started_at).to_string(),
+        );
+    }
+
+    let exit_code = progress
+        .get("exit_code")
+        .and_then(|exit_code| exit_code.parse::<i64>().ok());
+    let oom_killed = progress.get("oom_killed").map(String::as_str) == Some("true");
+
+    if let Some(outcome) = Outcome::interpret(status.as_str(), exit_code, oom_killed) {
+        progress.insert("outcome".to_string(), outcome.as_str().to_string());
+    }
+}
+
 #[async_trait]
 impl VerificationStepRunner<Result<HashMap<String, String>, Report>>
-    for VerificationRuntime<'_, DockerContainerAPIClient<Docker>>
+    for VerificationRuntime<'_, SharedContainerAPIClient>
 {
-    fn steps_names() -> Vec<&'static str> {
-        let mut names = Vec::<&str>::new();
-        names.push(PROGRAM_FUZZING);
-        names.push(PROGRAM_VERIFICATION);
-        names.push(UPLOADED_SOURCES_LISTING);
-        names.push(SOURCE_RESTORATION);
-
-        names
+    fn steps_names() -> Result<Vec<&'static str>, Report> {
+        Ok(step_registry::step_registry()?.names())
     }
 
     async fn get_progress(&self) -> Result<HashMap<String, String>, Report> {
-        self.container_api_client()
-            .inspect_container_status(self.step_in_verification_plan())
-            .await
+        let project_step = self.step_in_verification_plan();
+        let mut progress = self
+            .container_api_client()
+            .inspect_container_status(project_step)
+            .await?;
+
+        let run_id =
+            program_verification::format_run_id(project_step.step().name(), self.project_id());
+        let has_exited = progress.get("raw_status").map(String::as_str) == Some("exited");
+
+        if has_exited && time_budget::has_timed_out(run_id.as_str()) {
+            progress.insert(
+                "raw_status".to_string(),
+                time_budget::STATUS_TIMED_OUT.to_string(),
+            );
+            progress.insert(
+                "message".to_string(),
+                format!(
+                    "Run \"{}\" has been stopped after exceeding its time budget",
+                    run_id
+                ),
+            );
+        }
+
+        describe_outcome(&mut progress);
+
+        Ok(progress)
     }
 
-    async fn get_report(&self) -> Result<HashMap<String, String>, Report> {
+    async fn get_report(
+        &self,
+        cursor: Option<&LogCursor>,
+    ) -> Result<HashMap<String, String>, Report> {
         self.container_api_client()
-            .tail_container_logs(self.step_in_verification_plan())
+            .tail_container_logs(self.step_in_verification_plan(), cursor)
             .await
     }
 
@@ -157,10 +234,25 @@ impl VerificationStepRunner<Result<HashMap<String, String>, Report>>
             .remove_existing_container(project_step)
             .await?;
 
-        if project_step.step().name() == UPLOADED_SOURCES_LISTING
-            || scaffold::scaffold_library(project_step.project_id()).is_ok()
+        let run_id =
+            program_verification::format_run_id(project_step.step().name(), self.project_id());
+        time_budget::forget(run_id.as_str());
+
+        if !project_step.step().definition().requires_scaffolding()
+            || scaffold::scaffold_uploaded_project(project_step.project_id()).is_ok()
         {
-            let result = self.start_rvt_container(project_step).await?;
+            let mut result = self.start_rvt_container(project_step).await?;
+            let is_cached = result.get("cached").map(String::as_str) == Some("true");
+
+            if let (Some(budget), false) = (project_step.time_budget(), is_cached) {
+                time_budget::watch(
+                    Arc::clone(&self.container_api_client),
+                    project_step.step().name(),
+                    self.project_id(),
+                    budget,
+                );
+                result.insert("time_budget".to_string(), budget.as_secs().to_string());
+            }
 
             return Ok(result);
         }
@@ -170,6 +262,10 @@ impl VerificationStepRunner<Result<HashMap<String, String>, Report>>
 
     async fn stop_running(&self) -> Result<HashMap<String, String>, Report> {
         let project_step = self.step_in_verification_plan();
+        time_budget::forget(
+            program_verification::format_run_id(project_step.step().name(), self.project_id())
+                .as_str(),
+        );
 
         self.container_api_client
             .stop_container(project_step)
@@ -179,12 +275,235 @@ impl VerificationStepRunner<Result<HashMap<String, String>, Report>>
 
         message.insert(
             "message".to_string(),
-            String::from(format!(
+            format!(
                 "Removed running container successfully for project with id \"{}\".",
                 project_step.project_id
-            )),
+            ),
         );
 
         Ok(message)
     }
 }
+
+#[tokio::test]
+async fn it_starts_a_step_before_following_it_up_and_stopping_it() {
+    use crate::test;
+
+    let project = test::InMemoryProject::upload();
+    let project_id = &project.project_id;
+    let run_id = project.run_id(PROGRAM_VERIFICATION);
+
+    let client = &project.client;
+    client
+        .script_statuses(run_id.as_str(), vec!["running", "running", "exited"])
+        .script_logs(run_id.as_str(), "Tests results for \"safe_transfer\"\n");
+
+    let runtime = project.runtime(PROGRAM_VERIFICATION);
+
+    let started = runtime.start_running().await.unwrap();
+    assert_eq!(run_id, started["run_id"]);
+    assert!(client.container_exists(run_id.as_str()));
+    assert!(
+        std::path::Path::new(&scaffold::format_directory_path_to_scaffold(
+            project_id.as_str()
+        ))
+        .join("src/lib.rs")
+        .exists()
+    );
+
+    let statuses: Vec<String> = vec![
+        runtime.get_progress().await.unwrap()["raw_status"].clone(),
+        runtime.get_progress().await.unwrap()["raw_status"].clone(),
+        runtime.get_progress().await.unwrap()["raw_status"].clone(),
+        runtime.get_progress().await.unwrap()["raw_status"].clone(),
+    ];
+    assert_eq!(vec!["running", "running", "exited", "exited"], statuses);
+
+    let report = runtime.get_report(None).await.unwrap();
+    assert_eq!("Tests results for \"safe_transfer\"\n", report["raw_log"]);
+
+    assert!(runtime.stop_running().await.is_ok());
+
+    let calls = client.calls();
+    assert_eq!(format!("remove_existing_container {}", run_id), calls[0]);
+    assert_eq!(format!("start_container {}", run_id), calls[1]);
+    assert_eq!(format!("stop_container {}", run_id), *calls.last().unwrap());
+}
+
+#[tokio::test]
+async fn it_restarts_a_step_by_removing_its_previous_container() {
+    use crate::test;
+
+    let project = test::InMemoryProject::upload();
+    let run_id = project.run_id(PROGRAM_FUZZING);
+
+    let client = &project.client;
+    client.script_statuses(run_id.as_str(), vec!["exited"]);
+
+    let runtime = project.runtime(PROGRAM_FUZZING);
+
+    runtime.start_running().await.unwrap();
+    runtime.start_running().await.unwrap();
+
+    assert_eq!(
+        vec![
+            format!("remove_existing_container {}", run_id),
+            format!("start_container {}", run_id),
+            format!("remove_existing_container {}", run_id),
+            format!("start_container {}", run_id),
+        ],
+        client.calls()
+    );
+}
+
+#[tokio::test]
+async fn it_propagates_errors_of_container_api_clients() {
+    use crate::test;
+
+    let project = test::InMemoryProject::upload();
+    let run_id = project.run_id(PROGRAM_VERIFICATION);
+
+    let client = &project.client;
+    client.script_start_error(run_id.as_str(), "No space left on device");
+
+    let runtime = project.runtime(PROGRAM_VERIFICATION);
+
+    let missing_container_error = format!("There is no container having name \"{}\"", run_id);
+    assert_eq!(
+        missing_container_error,
+        runtime.get_progress().await.unwrap_err().to_string()
+    );
+    assert_eq!(
+        missing_container_error,
+        runtime.get_report(None).await.unwrap_err().to_string()
+    );
+    assert_eq!(
+        missing_container_error,
+        runtime.stop_running().await.unwrap_err().to_string()
+    );
+    assert_eq!(
+        "No space left on device",
+        runtime.start_running().await.unwrap_err().to_string()
+    );
+    assert!(!client.container_exists(run_id.as_str()));
+}
+
+#[tokio::test]
+async fn it_stops_a_step_exceeding_its_time_budget() {
+    use crate::test;
+    use std::time::Duration;
+
+    // The clock only moves when advanced, so that the time budget is exceeded deterministically
+    tokio::time::pause();
+
+    let project = test::InMemoryProject::upload();
+    let project_id = &project.project_id;
+    let run_id = project.run_id(PROGRAM_VERIFICATION);
+
+    let client = &project.client;
+    client.script_statuses(run_id.as_str(), vec!["running"]);
+
+    let steps = VerificationRuntime::build_steps().unwrap();
+    let step = program_verification::which_step(
+        &steps,
+        PROGRAM_VERIFICATION.to_string(),
+        project_id.clone(),
+    )
+    .unwrap()
+    .with_time_budget(Some(Duration::from_millis(100)));
+    let runtime =
+        VerificationRuntime::with_container_api_client_factory(&client.factory(), step, steps)
+            .unwrap();
+
+    runtime.start_running().await.unwrap();
+    assert_eq!(
+        "running",
+        runtime.get_progress().await.unwrap()["raw_status"]
+    );
+
+    tokio::time::advance(Duration::from_millis(100)).await;
+    // Let the watchdog stop the run
+    while !time_budget::has_timed_out(run_id.as_str()) {
+        tokio::time::sleep(Duration::from_millis(1)).await;
+    }
+
+    let progress = runtime.get_progress().await.unwrap();
+    assert_eq!(time_budget::STATUS_TIMED_OUT, progress["raw_status"]);
+    assert_eq!("infrastructure_error", progress["outcome"]);
+    assert!(client
+        .calls()
+        .contains(&format!("stop_container {}", run_id)));
+
+    runtime.start_running().await.unwrap();
+    assert_eq!(
+        "running",
+        runtime.get_progress().await.unwrap()["raw_status"]
+    );
+    runtime.stop_running().await.unwrap();
+}
+
+#[tokio::test]
+async fn it_reports_the_outcome_of_steps() {
+    use crate::test;
+
+    let project = test::InMemoryProject::upload();
+    let run_id = project.run_id(PROGRAM_VERIFICATION);
+
+    let client = &project.client;
+    client
+        .script_statuses(run_id.as_str(), vec!["running", "exited"])
+        .script_started_at(run_id.as_str(), 1_635_847_965)
+        .script_finished_at(run_id.as_str(), 1_635_848_007)
+        .script_exit_code(run_id.as_str(), 1);
+
+    let runtime = project.runtime(PROGRAM_VERIFICATION);
+
+    runtime.start_running().await.unwrap();
+
+    let progress = runtime.get_progress().await.unwrap();
+    assert_eq!("running", progress["raw_status"]);
+    assert_eq!(None, progress.get("outcome"));
+    assert_eq!(None, progress.get("exit_code"));
+
+    let progress = runtime.get_progress().await.unwrap();
+    assert_eq!("exited", progress["raw_status"]);
+    assert_eq!("1", progress["exit_code"]);
+    assert_eq!("1635847965", progress["started_at"]);
+    assert_eq!("1635848007", progress["finished_at"]);
+    assert_eq!("42", progress["duration"]);
+    assert_eq!("false", progress["oom_killed"]);
+    assert_eq!("verification_failed", progress["outcome"]);
+}
+
+#[tokio::test]
+async fn it_tails_logs_written_since_a_cursor() {
+    use crate::test;
+
+    let project = test::InMemoryProject::upload();
+    let run_id = project.run_id(PROGRAM_VERIFICATION);
+
+    let client = &project.client;
+    client.script_logs(run_id.as_str(), "Running 1 test\n");
+
+    let runtime = project.runtime(PROGRAM_VERIFICATION);
+
+    runtime.start_running().await.unwrap();
+
+    let logs = runtime.get_report(None).await.unwrap();
+    assert_eq!("Running 1 test\n", logs["raw_log"]);
+    assert_eq!("offset:15", logs["cursor"]);
+
+    client.append_logs(run_id.as_str(), "Tests results for \"safe_transfer\"\n");
+
+    let cursor = LogCursor::parse(logs["cursor"].as_str()).unwrap();
+    let logs = runtime.get_report(Some(&cursor)).await.unwrap();
+    assert_eq!("Tests results for \"safe_transfer\"\n", logs["raw_log"]);
+
+    let cursor = LogCursor::parse(logs["cursor"].as_str()).unwrap();
+    let logs = runtime.get_report(Some(&cursor)).await.unwrap();
+    assert_eq!("", logs["raw_log"]);
+
+    // Cursors returned by the Docker runtime can not be passed to another runtime
+    let cursor = LogCursor::parse("time:2021-11-02T10:12:45Z").unwrap();
+    assert!(runtime.get_report(Some(&cursor)).await.is_err());
+}
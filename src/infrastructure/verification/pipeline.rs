@@ -0,0 +1,627 @@
+use crate::domain::value_object::*;
+use crate::domain::verification_runtime::{VerificationRuntime, VerificationStepRunner};
+use crate::infrastructure as infra;
+use anyhow::Result;
+use color_eyre::{eyre::eyre, Report};
+use infra::program_verification::{format_run_id, get_run_report, which_step};
+use infra::scaffold::find_decoded_source_by_project_id;
+use infra::service::file_system::{get_uploaded_source_directory, PIPELINE_PROGRESS_EXTENSION};
+use infra::time_budget::get_time_budget;
+use infra::verification::{ContainerAPIClientFactory, SharedContainerAPIClient};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::error;
+
+/// Interval between two inspections of the steps of a pipeline
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+struct PipelineRun {
+    progress: PipelineProgress,
+    driver: JoinHandle<()>,
+}
+
+/// Pipelines running in this backend instance, indexed by project id,
+/// pipelines being evicted once they have ended
+fn pipelines() -> &'static Mutex<HashMap<String, PipelineRun>> {
+    static PIPELINES: OnceLock<Mutex<HashMap<String, PipelineRun>>> = OnceLock::new();
+
+    PIPELINES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn format_progress_path(project_id: &str) -> Result<PathBuf, Report> {
+    Ok(Path::new(get_uploaded_source_directory()?.as_str())
+        .join(format!("{}{}", project_id, PIPELINE_PROGRESS_EXTENSION)))
+}
+
+/// Record the progress of a pipeline next to the source of its project,
+/// so that it outlives the backend instance running it
+fn save_progress(progress: &PipelineProgress) -> Result<(), Report> {
+    fs::write(
+        format_progress_path(progress.project_id.as_str())?,
+        serde_json::to_vec(progress)?,
+    )?;
+
+    Ok(())
+}
+
+fn read_progress(project_id: &str) -> Result<Option<PipelineProgress>, Report> {
+    let progress_path = format_progress_path(project_id)?;
+    if !progress_path.exists() {
+        return Ok(None);
+    }
+
+    Ok(Some(serde_json::from_slice(
+        fs::read(progress_path)?.as_slice(),
+    )?))
+}
+
+fn stage_runtime(
+    factory: &ContainerAPIClientFactory,
+    step_name: &str,
+    project_id: &str,
+) -> Result<VerificationRuntime<'static, SharedContainerAPIClient>, Report> {
+    let steps = VerificationRuntime::build_steps()?;
+    let step = which_step(&steps, step_name.to_string(), project_id.to_string())?;
+    let step = step.with_time_budget(get_time_budget(step_name, None)?);
+
+    VerificationRuntime::with_container_api_client_factory(factory, step, steps)
+}
+
+/// Apply a change to the progress of a running pipeline, recording it when it has changed
+fn update<T, F: FnOnce(&mut PipelineProgress) -> T>(project_id: &str, change: F) -> Option<T> {
+    let mut registry = pipelines().lock().unwrap();
+    let run = registry.get_mut(project_id)?;
+
+    let previous_progress = run.progress.clone();
+    let result = change(&mut run.progress);
+    if run.progress != previous_progress {
+        if let Err(report) = save_progress(&run.progress) {
+            error!(
+                "Could not record progress of pipeline for project having id \"{}\": {}",
+                project_id, report
+            );
+        }
+    }
+
+    Some(result)
+}
+
+/// Names of the steps of the stages of a pipeline
+fn stage_steps(progress: &PipelineProgress, indices: Vec<usize>) -> Vec<String> {
+    indices
+        .into_iter()
+        .map(|index| progress.stages[index].step.clone())
+        .collect()
+}
+
+async fn stop_stages(
+    factory: &ContainerAPIClientFactory,
+    project_id: &str,
+    step_names: Vec<String>,
+) {
+    for step_name in step_names {
+        let stopped = match stage_runtime(factory, step_name.as_str(), project_id) {
+            Ok(runtime) => runtime.stop_running().await.map(|_| ()),
+            Err(report) => Err(report),
+        };
+
+        if let Err(report) = stopped {
+            error!(
+                "Could not stop run \"{}\": {}",
+                format_run_id(step_name.as_str(), project_id),
+                report
+            );
+        }
+    }
+}
+
+/// Stop the pipeline of a project from within its driver
+async fn stop_from_driver(factory: &ContainerAPIClientFactory, project_id: &str) {
+    let running = update(project_id, |progress| {
+        let running = progress.stop();
+        stage_steps(progress, running)
+    })
+    .unwrap_or_default();
+
+    stop_stages(factory, project_id, running).await;
+}
+
+/// Start the steps of a pipeline as soon as their dependencies have ended,
+/// until all of them have ended or one of them ends with an outcome stopping the pipeline.
+async fn drive(factory: ContainerAPIClientFactory, project_id: String, poll_interval: Duration) {
+    loop {
+        let running = update(project_id.as_str(), |progress| {
+            progress.stages_with_status(StageStatus::Running)
+        })
+        .unwrap_or_default();
+
+        let mut has_to_stop = false;
+        for index in running {
+            let step_name = match update(project_id.as_str(), |progress| {
+                progress.stages[index].step.clone()
+            }) {
+                Some(step_name) => step_name,
+                None => return,
+            };
+
+            let outcome = match stage_runtime(&factory, step_name.as_str(), project_id.as_str()) {
+                Ok(runtime) => runtime.get_progress().await.map(|progress| {
+                    progress
+                        .get("outcome")
+                        .and_then(|outcome| Outcome::parse(outcome))
+                        .map(|outcome| (outcome, progress.get("message").cloned()))
+                }),
+                Err(report) => Err(report),
+            };
+
+            let ended = match outcome {
+                Ok(outcome) => outcome,
+                Err(report) => Some((Outcome::InfrastructureError, Some(report.to_string()))),
+            };
+
+            if let Some((outcome, message)) = ended {
+                has_to_stop |= update(project_id.as_str(), |progress| {
+                    progress.end_stage(index, outcome, message)
+                })
+                .unwrap_or_default();
+            }
+        }
+
+        if !has_to_stop {
+            let ready =
+                update(project_id.as_str(), |progress| progress.ready_stages()).unwrap_or_default();
+
+            for index in ready {
+                let step_name = match update(project_id.as_str(), |progress| {
+                    progress.stages[index].step.clone()
+                }) {
+                    Some(step_name) => step_name,
+                    None => return,
+                };
+
+                let started = match stage_runtime(&factory, step_name.as_str(), project_id.as_str())
+                {
+                    Ok(runtime) => runtime.start_running().await,
+                    Err(report) => Err(report),
+                };
+
+                match started {
+                    Ok(_) => {
+                        update(project_id.as_str(), |progress| progress.start_stage(index));
+                    }
+                    Err(report) => {
+                        error!("{}", report);
+
+                        has_to_stop |= update(project_id.as_str(), |progress| {
+                            progress.end_stage(
+                                index,
+                                Outcome::InfrastructureError,
+                                Some(format!(
+                                    "Could not run \"{}\" step for project having id \"{}\"",
+                                    step_name, project_id
+                                )),
+                            )
+                        })
+                        .unwrap_or_default();
+
+                        if has_to_stop {
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+
+        if has_to_stop {
+            stop_from_driver(&factory, project_id.as_str()).await;
+        }
+
+        let status = update(project_id.as_str(), |progress| progress.refresh_status());
+        if status != Some(PipelineStatus::Running) {
+            // Pipelines which have ended are followed up from their record
+            pipelines().lock().unwrap().remove(project_id.as_str());
+
+            return;
+        }
+
+        tokio::time::sleep(poll_interval).await;
+    }
+}
+
+/// Start running a pipeline in the background,
+/// unless another pipeline is running for the same project.
+pub fn start_pipeline(
+    factory: ContainerAPIClientFactory,
+    pipeline: &Pipeline,
+    poll_interval: Duration,
+) -> Result<PipelineProgress, Report> {
+    let project_id = pipeline.project_id().to_string();
+    find_decoded_source_by_project_id(project_id.as_str())?;
+
+    // Hold the lock while spawning so that the driver
+    // can not look up the pipeline before it is registered.
+    let mut registry = pipelines().lock().unwrap();
+
+    if registry.contains_key(project_id.as_str()) {
+        return Err(eyre!(
+            "A pipeline is already running for project having id \"{}\"",
+            project_id
+        ));
+    }
+
+    let progress = PipelineProgress::new(pipeline, |step_name| {
+        format_run_id(step_name, project_id.as_str())
+    });
+    save_progress(&progress)?;
+
+    let driver = tokio::spawn(drive(factory, project_id.clone(), poll_interval));
+    registry.insert(
+        project_id,
+        PipelineRun {
+            progress: progress.clone(),
+            driver,
+        },
+    );
+
+    Ok(progress)
+}
+
+/// Resume following up the pipelines left running by a previous backend instance,
+/// returning the ids of their projects
+pub fn resume_pipelines(
+    factory: ContainerAPIClientFactory,
+    poll_interval: Duration,
+) -> Result<Vec<String>, Report> {
+    let mut registry = pipelines().lock().unwrap();
+
+    let mut resumed = vec![];
+    for entry in fs::read_dir(get_uploaded_source_directory()?)? {
+        let file_name = entry?.file_name().to_string_lossy().to_string();
+        let project_id = match file_name.strip_suffix(PIPELINE_PROGRESS_EXTENSION) {
+            Some(project_id) if !registry.contains_key(project_id) => project_id.to_string(),
+            _ => continue,
+        };
+
+        let progress = match read_progress(project_id.as_str()) {
+            Ok(Some(progress)) if progress.status == PipelineStatus::Running => progress,
+            Ok(_) => continue,
+            Err(report) => {
+                error!(
+                    "Could not read progress of pipeline for project having id \"{}\": {}",
+                    project_id, report
+                );
+                continue;
+            }
+        };
+
+        let driver = tokio::spawn(drive(factory.clone(), project_id.clone(), poll_interval));
+        registry.insert(project_id.clone(), PipelineRun { progress, driver });
+        resumed.push(project_id);
+    }
+
+    Ok(resumed)
+}
+
+/// Get the progress of the last pipeline run against a project, if any
+pub fn get_pipeline_progress(project_id: &str) -> Result<Option<PipelineProgress>, Report> {
+    if let Some(run) = pipelines().lock().unwrap().get(project_id) {
+        return Ok(Some(run.progress.clone()));
+    }
+
+    read_progress(project_id)
+}
+
+/// Stop a pipeline on demand, along with the steps it is running
+pub async fn stop_pipeline(
+    factory: &ContainerAPIClientFactory,
+    project_id: &str,
+) -> Result<Option<PipelineProgress>, Report> {
+    let (running, progress) = {
+        let mut registry = pipelines().lock().unwrap();
+
+        let mut progress = match registry.remove(project_id) {
+            Some(run) => {
+                run.driver.abort();
+                run.progress
+            }
+            // Pipelines recorded as running by a backend instance which is gone are stopped as well
+            None => match read_progress(project_id)? {
+                Some(progress) => progress,
+                None => return Ok(None),
+            },
+        };
+
+        let running = progress.stop();
+        (stage_steps(&progress, running), progress)
+    };
+
+    save_progress(&progress)?;
+    stop_stages(factory, project_id, running).await;
+
+    Ok(Some(progress))
+}
+
+/// Combine the reports of the steps started by the last pipeline run against a project, if any
+pub async fn get_pipeline_report(
+    factory: &ContainerAPIClientFactory,
+    project_id: &str,
+) -> Result<Option<PipelineReport>, Report> {
+    let progress = match get_pipeline_progress(project_id)? {
+        Some(progress) => progress,
+        None => return Ok(None),
+    };
+
+    let mut reports = vec![];
+    for stage in progress.stages.iter() {
+        if matches!(stage.status, StageStatus::Pending | StageStatus::Skipped) {
+            continue;
+        }
+
+        match get_run_report(factory, stage.run_id.as_str()).await {
+            Ok(report) => reports.push(report),
+            Err(report) => error!(
+                "Could not get report of run \"{}\": {}",
+                stage.run_id, report
+            ),
+        }
+    }
+
+    Ok(Some(PipelineReport { progress, reports }))
+}
+
+/// Wait for a pipeline to end, pipelines being evicted once they have ended
+#[cfg(test)]
+async fn wait_for_pipeline(project_id: &str) -> PipelineProgress {
+    for _ in 0..100 {
+        if !pipelines().lock().unwrap().contains_key(project_id) {
+            return get_pipeline_progress(project_id).unwrap().unwrap();
+        }
+
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+
+    panic!("Pipeline of project \"{}\" is still running", project_id)
+}
+
+#[tokio::test]
+async fn it_runs_steps_of_pipelines_in_order() {
+    use crate::infra::{PROGRAM_FUZZING, PROGRAM_VERIFICATION};
+    use crate::test;
+
+    let project = test::InMemoryProject::upload();
+    let project_id = &project.project_id;
+    let verification_run_id = project.run_id(PROGRAM_VERIFICATION);
+    let fuzzing_run_id = project.run_id(PROGRAM_FUZZING);
+
+    let client = &project.client;
+    client
+        .script_statuses(verification_run_id.as_str(), vec!["running", "exited"])
+        .script_logs(
+            verification_run_id.as_str(),
+            "Tests results for \"safe_transfer\"\n",
+        )
+        .script_statuses(fuzzing_run_id.as_str(), vec!["exited"])
+        .script_exit_code(fuzzing_run_id.as_str(), 1);
+
+    let definition: PipelineDefinition = serde_json::from_str(
+        r#"{"steps": ["program_verification", "program_fuzzing"], "stop_on": ["infrastructure_error"]}"#,
+    )
+    .unwrap();
+    let pipeline = Pipeline::new(
+        project_id.clone(),
+        definition,
+        &VerificationRuntime::steps_names().unwrap(),
+    )
+    .unwrap();
+
+    let factory = client.factory();
+    let progress = start_pipeline(factory.clone(), &pipeline, Duration::from_millis(1)).unwrap();
+    assert_eq!(PipelineStatus::Running, progress.status);
+    assert!(start_pipeline(factory.clone(), &pipeline, Duration::from_millis(1)).is_err());
+
+    let progress = wait_for_pipeline(project_id.as_str()).await;
+    assert_eq!(PipelineStatus::Completed, progress.status);
+    assert_eq!(Some(Outcome::VerificationFailed), progress.outcome);
+    assert_eq!(Some(Outcome::Succeeded), progress.stages[0].outcome);
+    assert_eq!(
+        Some(Outcome::VerificationFailed),
+        progress.stages[1].outcome
+    );
+
+    let starts: Vec<String> = client
+        .calls()
+        .into_iter()
+        .filter(|call| call.starts_with("start_container"))
+        .collect();
+    assert_eq!(
+        vec![
+            format!("start_container {}", verification_run_id),
+            format!("start_container {}", fuzzing_run_id),
+        ],
+        starts
+    );
+
+    let report = get_pipeline_report(&factory, project_id.as_str())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(2, report.reports.len());
+    assert_eq!("safe_transfer", report.reports[0].tests()[0].name());
+}
+
+#[tokio::test]
+async fn it_stops_pipelines_on_configured_failures() {
+    use crate::infra::{PROGRAM_FUZZING, PROGRAM_LINTING, PROGRAM_VERIFICATION};
+    use crate::test;
+
+    let project = test::InMemoryProject::upload();
+    let project_id = &project.project_id;
+    let verification_run_id = project.run_id(PROGRAM_VERIFICATION);
+    let linting_run_id = project.run_id(PROGRAM_LINTING);
+
+    let client = &project.client;
+    client
+        .script_statuses(verification_run_id.as_str(), vec!["exited"])
+        .script_exit_code(verification_run_id.as_str(), 1)
+        .script_statuses(linting_run_id.as_str(), vec!["running"]);
+
+    let definition: PipelineDefinition = serde_json::from_str(
+        r#"{"steps": [
+            "program_verification",
+            "program_fuzzing",
+            {"step": "program_linting"}
+        ]}"#,
+    )
+    .unwrap();
+    let pipeline = Pipeline::new(
+        project_id.clone(),
+        definition,
+        &VerificationRuntime::steps_names().unwrap(),
+    )
+    .unwrap();
+
+    start_pipeline(client.factory(), &pipeline, Duration::from_millis(1)).unwrap();
+
+    let progress = wait_for_pipeline(project_id.as_str()).await;
+    assert_eq!(PipelineStatus::Stopped, progress.status);
+    assert_eq!(Some(Outcome::VerificationFailed), progress.outcome);
+    assert_eq!(StageStatus::Skipped, progress.stages[1].status);
+    assert_eq!(StageStatus::Stopped, progress.stages[2].status);
+
+    let calls = client.calls();
+    assert!(calls.contains(&format!("stop_container {}", linting_run_id)));
+    assert!(!calls.contains(&format!(
+        "start_container {}",
+        project.run_id(PROGRAM_FUZZING)
+    )));
+}
+
+#[tokio::test]
+async fn it_runs_pipelines_past_outcomes_not_stopping_them() {
+    use crate::infra::{PROGRAM_FUZZING, PROGRAM_VERIFICATION};
+    use crate::test;
+
+    let project = test::InMemoryProject::upload();
+    let project_id = &project.project_id;
+    let verification_run_id = project.run_id(PROGRAM_VERIFICATION);
+    let fuzzing_run_id = project.run_id(PROGRAM_FUZZING);
+
+    let client = &project.client;
+    client
+        .script_statuses(verification_run_id.as_str(), vec!["exited"])
+        .script_exit_code(verification_run_id.as_str(), 1)
+        .script_statuses(fuzzing_run_id.as_str(), vec!["exited"]);
+
+    let definition: PipelineDefinition = serde_json::from_str(
+        r#"{"steps": ["program_verification", "program_fuzzing"], "stop_on": ["infrastructure_error"]}"#,
+    )
+    .unwrap();
+    let pipeline = Pipeline::new(
+        project_id.clone(),
+        definition,
+        &VerificationRuntime::steps_names().unwrap(),
+    )
+    .unwrap();
+
+    start_pipeline(client.factory(), &pipeline, Duration::from_millis(1)).unwrap();
+
+    // Failed verifications do not stop pipelines which only stop on infrastructure errors
+    let progress = wait_for_pipeline(project_id.as_str()).await;
+    assert_eq!(PipelineStatus::Completed, progress.status);
+    assert_eq!(Some(Outcome::VerificationFailed), progress.outcome);
+    assert_eq!(
+        Some(Outcome::VerificationFailed),
+        progress.stages[0].outcome
+    );
+    assert_eq!(StageStatus::Ended, progress.stages[1].status);
+    assert!(client
+        .calls()
+        .contains(&format!("start_container {}", fuzzing_run_id)));
+}
+
+#[tokio::test]
+async fn it_resumes_pipelines_recorded_as_running() {
+    use crate::infra::{PROGRAM_FUZZING, PROGRAM_VERIFICATION};
+    use crate::test;
+
+    let project = test::InMemoryProject::upload();
+    let project_id = &project.project_id;
+    let verification_run_id = project.run_id(PROGRAM_VERIFICATION);
+    let fuzzing_run_id = project.run_id(PROGRAM_FUZZING);
+    assert!(get_pipeline_progress(project_id.as_str())
+        .unwrap()
+        .is_none());
+
+    let client = &project.client;
+    client
+        .script_statuses(verification_run_id.as_str(), vec!["exited"])
+        .script_logs(
+            verification_run_id.as_str(),
+            "Tests results for \"safe_transfer\"\n",
+        )
+        .script_statuses(fuzzing_run_id.as_str(), vec!["exited"]);
+
+    let definition: PipelineDefinition =
+        serde_json::from_str(r#"{"steps": ["program_verification", "program_fuzzing"]}"#).unwrap();
+    let pipeline = Pipeline::new(
+        project_id.clone(),
+        definition,
+        &VerificationRuntime::steps_names().unwrap(),
+    )
+    .unwrap();
+
+    // A backend instance has started verifying the program before going away
+    let mut progress = PipelineProgress::new(&pipeline, |step_name| {
+        format_run_id(step_name, project_id.as_str())
+    });
+    stage_runtime(&client.factory(), PROGRAM_VERIFICATION, project_id.as_str())
+        .unwrap()
+        .start_running()
+        .await
+        .unwrap();
+    progress.start_stage(0);
+    save_progress(&progress).unwrap();
+
+    let resumed = resume_pipelines(client.factory(), Duration::from_millis(1)).unwrap();
+    assert!(resumed.contains(project_id));
+
+    let progress = wait_for_pipeline(project_id.as_str()).await;
+    assert_eq!(PipelineStatus::Completed, progress.status);
+    assert_eq!(Some(Outcome::Succeeded), progress.outcome);
+
+    let starts: Vec<String> = client
+        .calls()
+        .into_iter()
+        .filter(|call| call.starts_with("start_container"))
+        .collect();
+    assert_eq!(
+        vec![
+            format!("start_container {}", verification_run_id),
+            format!("start_container {}", fuzzing_run_id),
+        ],
+        starts
+    );
+
+    // Pipelines which have ended are still reported once evicted
+    let progress = stop_pipeline(&client.factory(), project_id.as_str())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(PipelineStatus::Completed, progress.status);
+    assert!(
+        !resume_pipelines(client.factory(), Duration::from_millis(1))
+            .unwrap()
+            .contains(project_id)
+    );
+
+    // Pipelines of removed projects are no longer reported
+    let project_id = project_id.clone();
+    drop(project);
+    assert!(get_pipeline_progress(project_id.as_str())
+        .unwrap()
+        .is_none());
+}
@@ -1,58 +1,232 @@
 use crate::domain;
 use crate::infra::display;
+use crate::infra::report::parser;
+use crate::infra::scaffold::find_project_settings;
+use crate::infra::time_budget::get_time_budget;
+use crate::infra::{
+    default_container_api_client_factory, ContainerAPIClientFactory, SharedContainerAPIClient,
+};
 
 use async_trait::async_trait;
 use color_eyre::{eyre::eyre, Report};
 use domain::program_verification::*;
-use domain::value_object::{Step, StepInVerificationPlan};
+use domain::value_object::{
+    BackendSelection, Interruption, LogCursor, Step, StepInVerificationPlan, StepReport,
+    VerificationOptions,
+};
 use domain::verification_runtime::{VerificationRuntime, VerificationStepRunner};
 use std::collections::HashMap;
+use tracing::error;
 
 pub fn change_case(step: String) -> String {
     step.replace("-", "_")
 }
 
+/// Plan a step of the registry for a project,
+/// which fails when the registry has no step of that name.
 pub fn which_step<'a>(
     steps: &HashMap<String, Step<'a>>,
     step_param: String,
     project_id: String,
-) -> StepInVerificationPlan<'a> {
-    let step_name = change_case(step_param.clone());
-    let step = steps.get(step_name.as_str()).unwrap().clone();
-    let step = StepInVerificationPlan::new(project_id.clone(), step);
+) -> Result<StepInVerificationPlan<'a>, Report> {
+    let step_name = change_case(step_param);
+    let step = *steps
+        .get(step_name.as_str())
+        .ok_or_else(|| eyre!("Unknown step \"{}\"", step_name))?;
 
-    step
+    Ok(StepInVerificationPlan::new(project_id, step))
 }
 
-impl VerificationTarget<'_> {
-    pub fn new<'a>(step: &'a str, project_id: &'a str) -> VerificationTarget<'a> {
-        VerificationTarget { step, project_id }
+/// Format the id of a run from a step name and a project id.
+///
+/// # Examples
+///
+/// ```
+/// use safepkt_backend::infra::program_verification;
+///
+/// let run_id = program_verification::format_run_id("program_verification", "47a9690570");
+/// assert_eq!("program_verification-47a9690570", run_id);
+/// ```
+///
+pub fn format_run_id(step_name: &str, project_id: &str) -> String {
+    format!("{}-{}", step_name, project_id)
+}
+
+/// Split the id of a run into a step name and a project id.
+///
+/// # Examples
+///
+/// ```
+/// use safepkt_backend::infra::program_verification;
+///
+/// let (step_name, project_id) = program_verification::parse_run_id("program-verification-47a9690570").unwrap();
+/// assert_eq!("program_verification", step_name);
+/// assert_eq!("47a9690570", project_id);
+///
+/// assert!(program_verification::parse_run_id("47a9690570").is_none());
+/// ```
+///
+pub fn parse_run_id(run_id: &str) -> Option<(String, String)> {
+    let (step_name, project_id) = run_id.rsplit_once('-')?;
+
+    if step_name.is_empty() || project_id.is_empty() {
+        return None;
+    }
+
+    Some((change_case(step_name.to_string()), project_id.to_string()))
+}
+
+/// Tail the logs of a run before parsing them into a structured report.
+pub async fn get_run_report(
+    factory: &ContainerAPIClientFactory,
+    run_id: &str,
+) -> Result<StepReport, Report> {
+    let (step_name, project_id) =
+        parse_run_id(run_id).ok_or_else(|| eyre!("Invalid run id \"{}\"", run_id))?;
+
+    let steps = VerificationRuntime::build_steps()?;
+    let step = which_step(&steps, step_name.clone(), project_id.clone())?;
+    let parser_name = step.step().definition().parser().to_string();
+    let runtime = VerificationRuntime::with_container_api_client_factory(factory, step, steps)?;
+
+    let logs = runtime.get_report(None).await?;
+    let raw_log = logs.get("raw_log").map(String::as_str).unwrap_or_default();
+    let settings = find_project_settings(project_id.as_str())?;
+
+    let mut report = parser::parse_step_report(
+        parser_name.as_str(),
+        run_id,
+        step_name.as_str(),
+        project_id.as_str(),
+        raw_log,
+        settings.kind().entry_point(),
+    );
+
+    let progress = runtime.get_progress().await?;
+    report.set_interruption(
+        progress
+            .get("raw_status")
+            .and_then(|status| Interruption::from_status(status)),
+    );
+
+    Ok(report)
+}
+
+/// Get the reports of the runs managed by this backend instance
+/// which logs are parsed by a given parser (e.g. KLEE),
+/// runs which report can not be built being left out.
+pub async fn get_run_reports(
+    factory: &ContainerAPIClientFactory,
+    parser_name: &str,
+) -> Result<Vec<StepReport>, Report> {
+    let steps = VerificationRuntime::build_steps()?;
+    let runs = factory()?.list_runs().await?;
+
+    let mut run_ids: Vec<&String> = runs
+        .keys()
+        .filter(|run_id| {
+            parse_run_id(run_id.as_str())
+                .and_then(|(step_name, _)| steps.get(step_name.as_str()))
+                .is_some_and(|step| step.definition().parser() == parser_name)
+        })
+        .collect();
+    run_ids.sort();
+
+    let mut reports = vec![];
+    for run_id in run_ids {
+        match get_run_report(factory, run_id.as_str()).await {
+            Ok(report) => reports.push(report),
+            Err(report) => error!("Could not get report of run \"{}\": {}", run_id, report),
+        }
+    }
+
+    Ok(reports)
+}
+
+impl<'a> VerificationTarget<'a> {
+    pub fn new(step: &'a str, project_id: &'a str) -> VerificationTarget<'a> {
+        VerificationTarget {
+            step,
+            project_id,
+            timeout: None,
+            fresh_run: false,
+            backend: None,
+        }
+    }
+
+    /// Request a time budget (in seconds) capped by configuration
+    pub fn with_timeout(mut self, timeout: Option<u64>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Run the step even when results of a previous run have been cached
+    pub fn with_fresh_run(mut self, fresh_run: bool) -> Self {
+        self.fresh_run = fresh_run;
+        self
+    }
+
+    /// Run the step with a backend other than its default one
+    pub fn with_backend(mut self, backend: Option<&'a str>) -> Self {
+        self.backend = backend;
+        self
+    }
+}
+
+impl<'a> SmartContractVerification<'a, ContainerAPIClientFactory> {
+    pub fn with_container_api_client_factory(
+        target: VerificationTarget<'a>,
+        container_api_client_factory: ContainerAPIClientFactory,
+    ) -> Self {
+        SmartContractVerification {
+            target,
+            container_api_client_factory,
+        }
+    }
+
+    fn runtime<'b>(
+        &self,
+        step: StepInVerificationPlan<'b>,
+        steps: HashMap<String, Step<'b>>,
+    ) -> Result<VerificationRuntime<'b, SharedContainerAPIClient>, Report> {
+        VerificationRuntime::with_container_api_client_factory(
+            &self.container_api_client_factory,
+            step,
+            steps,
+        )
     }
 }
 
 #[async_trait]
-impl<'a> ProgramVerification for SmartContractVerification<'a> {
+impl<'a> ProgramVerification for SmartContractVerification<'a, ContainerAPIClientFactory> {
     type A = VerificationTarget<'a>;
     type R = Result<HashMap<String, String>, Report>;
 
     fn new(target: Self::A) -> Self {
-        SmartContractVerification { target }
+        Self::with_container_api_client_factory(target, default_container_api_client_factory())
     }
 
     async fn run_step(&self) -> Self::R {
-        let steps: HashMap<String, Step>;
-
         let target_step = self.target.step.to_string();
         let project_id = self.target.project_id.to_string();
 
-        steps = VerificationRuntime::build_steps(None);
-        let step_in_verification_plan = which_step(&steps, target_step, project_id.clone());
+        let steps = VerificationRuntime::build_steps()?;
+        let step_in_verification_plan = which_step(&steps, target_step, project_id.clone())?;
 
-        let step = step_in_verification_plan.step();
-        let step = step.clone();
-        let step_name = step.name().to_string().clone();
+        let step_name = step_in_verification_plan.step().name().to_string();
+        let time_budget = get_time_budget(step_name.as_str(), self.target.timeout)?;
+        let backend = self
+            .target
+            .backend
+            .map(|backend| BackendSelection::new(backend, &VerificationOptions::default()))
+            .transpose()
+            .map_err(|error| eyre!(error))?;
+        let step_in_verification_plan = step_in_verification_plan
+            .with_time_budget(time_budget)
+            .with_fresh_run(self.target.fresh_run)
+            .with_backend(backend);
 
-        let runtime = VerificationRuntime::new(step_in_verification_plan, steps).unwrap();
+        let runtime = self.runtime(step_in_verification_plan, steps)?;
 
         match runtime.start_running().await {
             Ok(result) => Ok(result),
@@ -69,19 +243,19 @@ impl<'a> ProgramVerification for SmartContractVerification<'a> {
         }
     }
 
-    async fn step_report(&self) -> Self::R {
+    async fn step_report(&self, cursor: Option<&LogCursor>) -> Self::R {
         let target_step = self.target.step.to_string();
         let project_id = self.target.project_id.to_string();
 
-        let steps = VerificationRuntime::build_steps(None);
-        let step = which_step(&steps, change_case(target_step), project_id.clone());
-        let runtime = VerificationRuntime::new(step, steps).unwrap();
+        let steps = VerificationRuntime::build_steps()?;
+        let step = which_step(&steps, change_case(target_step), project_id.clone())?;
+        let runtime = self.runtime(step, steps)?;
 
-        match runtime.get_report().await {
+        match runtime.get_report(cursor).await {
             Ok(logs) => Ok(logs),
             Err(report) => {
                 let err = report.to_string();
-                display::output::eprint("{}", vec![err.clone().as_str()], None);
+                display::output::eprint("{}", vec![err.as_str()], None);
                 Err(eyre!(err))
             }
         }
@@ -91,17 +265,108 @@ impl<'a> ProgramVerification for SmartContractVerification<'a> {
         let target_step = self.target.step.to_string();
         let project_id = self.target.project_id.to_string();
 
-        let steps = VerificationRuntime::build_steps(None);
-        let step = which_step(&steps, change_case(target_step), project_id.clone());
-        let runtime = VerificationRuntime::new(step, steps).unwrap();
+        let steps = VerificationRuntime::build_steps()?;
+        let step = which_step(&steps, change_case(target_step), project_id.clone())?;
+        let runtime = self.runtime(step, steps)?;
 
         match runtime.get_progress().await {
             Ok(status) => Ok(status),
             Err(report) => {
                 let err = report.to_string();
-                display::output::eprint("{}", vec![err.clone().as_str()], None);
+                display::output::eprint("{}", vec![err.as_str()], None);
                 Err(eyre!(err))
             }
         }
     }
 }
+
+#[tokio::test]
+async fn it_runs_a_step_before_following_it_up() {
+    use crate::infra::verification_runtime::in_memory::InMemoryContainerAPIClient;
+    use crate::infra::PROGRAM_VERIFICATION;
+    use crate::test;
+
+    let project_id = test::upload_source();
+    let run_id = format_run_id(PROGRAM_VERIFICATION, project_id.as_str());
+
+    let client = InMemoryContainerAPIClient::new();
+    client
+        .script_statuses(run_id.as_str(), vec!["running", "exited"])
+        .script_logs(
+            run_id.as_str(),
+            "Tests results for \"safe_overflow\"\n\tKLEE: ERROR: src/lib.rs:1: abort failure\n",
+        );
+
+    let target = VerificationTarget::new(PROGRAM_VERIFICATION, project_id.as_str());
+    let verification =
+        SmartContractVerification::with_container_api_client_factory(target, client.factory());
+
+    assert_eq!(run_id, verification.run_step().await.unwrap()["run_id"]);
+    assert_eq!(
+        "running",
+        verification.step_progress().await.unwrap()["raw_status"]
+    );
+    assert_eq!(
+        "exited",
+        verification.step_progress().await.unwrap()["raw_status"]
+    );
+    assert!(verification.step_report(None).await.unwrap()["raw_log"].contains("KLEE: ERROR"));
+
+    let report = get_run_report(&client.factory(), run_id.as_str())
+        .await
+        .unwrap();
+    assert_eq!(project_id, report.project_id());
+    assert_eq!(vec![1], report.failing_lines());
+
+    test::remove_project(project_id.as_str());
+}
+
+#[tokio::test]
+async fn it_fails_to_get_the_report_of_an_invalid_run() {
+    use crate::infra::verification_runtime::in_memory::InMemoryContainerAPIClient;
+
+    let factory = InMemoryContainerAPIClient::new().factory();
+
+    assert_eq!(
+        "Invalid run id \"47a9690570\"",
+        get_run_report(&factory, "47a9690570")
+            .await
+            .unwrap_err()
+            .to_string()
+    );
+    assert_eq!(
+        "Unknown step \"unknown_step\"",
+        get_run_report(&factory, "unknown-step-47a9690570")
+            .await
+            .unwrap_err()
+            .to_string()
+    );
+}
+
+#[tokio::test]
+async fn it_reports_steps_killed_for_running_out_of_memory() {
+    use crate::infra::verification_runtime::in_memory::InMemoryContainerAPIClient;
+    use crate::infra::PROGRAM_VERIFICATION;
+    use crate::test;
+
+    let project_id = test::upload_source();
+    let run_id = format_run_id(PROGRAM_VERIFICATION, project_id.as_str());
+
+    let client = InMemoryContainerAPIClient::new();
+    client
+        .script_statuses(run_id.as_str(), vec!["oom_killed"])
+        .script_logs(run_id.as_str(), "Tests results for \"safe_transfer\"\n");
+
+    let target = VerificationTarget::new(PROGRAM_VERIFICATION, project_id.as_str());
+    let verification =
+        SmartContractVerification::with_container_api_client_factory(target, client.factory());
+    verification.run_step().await.unwrap();
+
+    let report = get_run_report(&client.factory(), run_id.as_str())
+        .await
+        .unwrap();
+    assert_eq!(Some(Interruption::OomKilled), report.interruption());
+    assert_eq!(1, report.tests().len());
+
+    test::remove_project(project_id.as_str());
+}
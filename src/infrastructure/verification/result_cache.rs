@@ -0,0 +1,761 @@
+use crate::domain::value_object::{LogCursor, Outcome, StepCommand, StepInVerificationPlan};
+use crate::domain::verification_runtime::{ContainerAPIClient, VerificationRuntime};
+use crate::infra;
+use anyhow::Result;
+use async_trait::async_trait;
+use color_eyre::{eyre::eyre, Report};
+use infra::file_system::disk_usage;
+use infra::program_verification::{format_run_id, which_step};
+use infra::scaffold::{find_decoded_source_by_project_id, format_directory_path_to_scaffold};
+use infra::verification_runtime::docker::container::{
+    parse_size, step_command, TARGET_VERIFICATION_SCRIPT,
+};
+use infra::verification_runtime::slice_logs_since;
+use infra::SharedContainerAPIClient;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tracing::{debug, error};
+
+static RUNS_DIRECTORY: &str = "runs";
+static STATUS_RUNNING: &str = "running";
+
+/// Hash everything the results of a step depend on:
+/// the uploaded source, the step, its command as rendered for the run
+/// (template, backend, options and time budget included),
+/// the image it is run from and the manifest of the scaffolded project.
+pub fn format_key(
+    source: &str,
+    step_name: &str,
+    command: &StepCommand,
+    image_digest: &str,
+    manifest: &str,
+) -> String {
+    let mut hasher = Sha256::new();
+
+    let mut fields: Vec<String> = [source, step_name, image_digest, manifest]
+        .iter()
+        .map(|field| field.to_string())
+        .collect();
+    fields.extend(command.argv());
+    fields.extend(command.env_vars());
+
+    for field in fields {
+        hasher.update(field.as_bytes());
+        hasher.update([0]);
+    }
+
+    hex::encode(hasher.finalize())
+}
+
+#[test]
+fn it_keys_results_by_rendered_command() {
+    let command = |template: &str, klee_max_time: &str| {
+        StepCommand::new(TARGET_VERIFICATION_SCRIPT)
+            .arg("safepkt_47a9690570")
+            .arg(template)
+            .env("VERIFY_BACKEND", "klee")
+            .env("KLEE_MAX_TIME", klee_max_time)
+    };
+    let key = |command: StepCommand| {
+        format_key("source", "program_verification", &command, "sha256:0", "")
+    };
+
+    assert_eq!(key(command("flipper", "60")), key(command("flipper", "60")));
+    assert_ne!(
+        key(command("flipper", "60")),
+        key(command("flipper", "120"))
+    );
+    assert_ne!(key(command("flipper", "60")), key(command("erc20", "60")));
+    assert_ne!(
+        key(command("flipper", "60")),
+        key(command("flipper", "60").env("KLEE_SEARCH", "bfs"))
+    );
+}
+
+/// Progress and logs of a run, as reported once it has ended
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct CachedResult {
+    progress: HashMap<String, String>,
+    raw_log: String,
+}
+
+impl CachedResult {
+    pub fn new(progress: HashMap<String, String>, raw_log: String) -> Self {
+        CachedResult { progress, raw_log }
+    }
+
+    pub fn progress(&self) -> &HashMap<String, String> {
+        &self.progress
+    }
+
+    pub fn raw_log(&self) -> &str {
+        self.raw_log.as_str()
+    }
+}
+
+/// Run pointing at the results it is served from (cached)
+/// or is to store once it has ended.
+#[derive(Debug, Serialize, Deserialize)]
+struct TrackedRun {
+    key: String,
+    cached: bool,
+    stored: bool,
+}
+
+/// Results of runs, stored under the key of their inputs
+/// so that steps are not run again against unchanged projects,
+/// the least recently used ones being evicted once the cache exceeds its size.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ResultCache {
+    directory: PathBuf,
+    max_size: Option<u64>,
+}
+
+impl ResultCache {
+    pub fn new(directory: PathBuf) -> Self {
+        ResultCache {
+            directory,
+            max_size: None,
+        }
+    }
+
+    pub fn with_max_size(self, max_size: Option<u64>) -> Self {
+        ResultCache { max_size, ..self }
+    }
+
+    pub fn from_env() -> Result<Option<Self>, Report> {
+        Self::from_lookup(|name| env::var(name).ok().filter(|value| !value.is_empty()))
+    }
+
+    pub fn from_lookup<L: Fn(&str) -> Option<String>>(lookup: L) -> Result<Option<Self>, Report> {
+        let directory = match lookup("RESULT_CACHE_DIRECTORY") {
+            Some(directory) => PathBuf::from(directory),
+            None => return Ok(None),
+        };
+
+        let max_size = lookup("RESULT_CACHE_MAX_SIZE")
+            .map(|size| {
+                parse_size(size.as_str())
+                    .map(|size| size as u64)
+                    .ok_or_else(|| eyre!("Invalid value \"{}\" for RESULT_CACHE_MAX_SIZE", size))
+            })
+            .transpose()?;
+
+        Ok(Some(Self::new(directory).with_max_size(max_size)))
+    }
+
+    fn result_path(&self, key: &str) -> PathBuf {
+        self.directory.join(format!("{}.json", key))
+    }
+
+    fn run_path(&self, run_id: &str) -> PathBuf {
+        self.directory
+            .join(RUNS_DIRECTORY)
+            .join(format!("{}.json", run_id))
+    }
+
+    /// Get the results stored under a key, marking them as recently used
+    pub fn get(&self, key: &str) -> Option<CachedResult> {
+        let path = self.result_path(key);
+        let content = fs::read_to_string(path.as_path()).ok()?;
+
+        if let Ok(file) = fs::File::options().write(true).open(path.as_path()) {
+            file.set_modified(SystemTime::now()).ok();
+        }
+
+        serde_json::from_str(content.as_str()).ok()
+    }
+
+    /// Store results before evicting the ones exceeding the size of the cache
+    pub fn store(&self, key: &str, result: &CachedResult) -> Result<(), Report> {
+        write_json(self.result_path(key).as_path(), result)?;
+        self.evict(Some(key))?;
+
+        Ok(())
+    }
+
+    /// Evict the least recently used results until the cache fits in its size
+    pub fn evict(&self, kept_key: Option<&str>) -> Result<Vec<String>, Report> {
+        let max_size = match self.max_size {
+            Some(max_size) => max_size,
+            None => return Ok(vec![]),
+        };
+
+        let kept_path = kept_key.map(|key| self.result_path(key));
+        let mut results: Vec<(PathBuf, u64, Option<SystemTime>)> =
+            fs::read_dir(self.directory.as_path())?
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|path| {
+                    path.extension()
+                        .is_some_and(|extension| extension == "json")
+                })
+                .map(|path| {
+                    let size = disk_usage(path.as_path());
+                    let last_used = fs::metadata(path.as_path())
+                        .and_then(|metadata| metadata.modified())
+                        .ok();
+
+                    (path, size, last_used)
+                })
+                .collect();
+        results.sort_by_key(|(_, _, last_used)| *last_used);
+
+        let mut size: u64 = results.iter().map(|(_, size, _)| size).sum();
+        let mut evicted = vec![];
+
+        for (path, result_size, _) in results {
+            if size <= max_size {
+                break;
+            }
+
+            if Some(&path) == kept_path.as_ref() {
+                continue;
+            }
+
+            fs::remove_file(path.as_path())?;
+            debug!("Evicted cached results \"{}\"", path.display());
+
+            size = size.saturating_sub(result_size);
+            if let Some(key) = path.file_stem() {
+                evicted.push(key.to_string_lossy().to_string());
+            }
+        }
+
+        Ok(evicted)
+    }
+
+    fn tracked_run(&self, run_id: &str) -> Option<TrackedRun> {
+        let content = fs::read_to_string(self.run_path(run_id)).ok()?;
+        serde_json::from_str(content.as_str()).ok()
+    }
+
+    fn track(&self, run_id: &str, run: &TrackedRun) -> Result<(), Report> {
+        write_json(self.run_path(run_id).as_path(), run)
+    }
+
+    fn forget(&self, run_id: &str) {
+        fs::remove_file(self.run_path(run_id)).ok();
+    }
+}
+
+fn write_json<T: Serialize>(path: &Path, value: &T) -> Result<(), Report> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    // Write to a temporary file first so that readers never get partial entries
+    let temporary_path = path.with_extension("json.tmp");
+    fs::write(temporary_path.as_path(), serde_json::to_vec(value)?)?;
+    fs::rename(temporary_path.as_path(), path)?;
+
+    Ok(())
+}
+
+/// Runs having ended with a verdict, as opposed to runs still going on
+/// or interrupted by the infrastructure, which are worth running again.
+fn has_verdict(progress: &HashMap<String, String>) -> bool {
+    let status = progress
+        .get("raw_status")
+        .map(String::as_str)
+        .unwrap_or_default();
+    let exit_code = progress
+        .get("exit_code")
+        .and_then(|exit_code| exit_code.parse::<i64>().ok());
+    let oom_killed = progress.get("oom_killed").map(String::as_str) == Some("true");
+
+    matches!(
+        Outcome::interpret(status, exit_code, oom_killed),
+        Some(Outcome::Succeeded) | Some(Outcome::VerificationFailed)
+    )
+}
+
+/// Wait for a run to end before storing its results,
+/// unless it is interrupted, removed or started again in the meantime.
+async fn store_when_ended(
+    client: SharedContainerAPIClient,
+    cache: ResultCache,
+    step_name: String,
+    project_id: String,
+    key: String,
+    poll_interval: Duration,
+) {
+    let run_id = format_run_id(step_name.as_str(), project_id.as_str());
+
+    let result: Result<(), Report> = async {
+        let steps = VerificationRuntime::build_steps()?;
+        let step = which_step(&steps, step_name, project_id)?;
+
+        loop {
+            match cache.tracked_run(run_id.as_str()) {
+                Some(run) if run.key == key && !run.stored => {}
+                _ => return Ok(()),
+            }
+
+            let progress = client.inspect_container_status(&step).await?;
+            if progress.get("raw_status").map(String::as_str) == Some(STATUS_RUNNING) {
+                tokio::time::sleep(poll_interval).await;
+                continue;
+            }
+
+            if has_verdict(&progress) {
+                let logs = client.tail_container_logs(&step, None).await?;
+                let raw_log = logs.get("raw_log").cloned().unwrap_or_default();
+
+                cache.store(key.as_str(), &CachedResult::new(progress, raw_log))?;
+                cache.track(
+                    run_id.as_str(),
+                    &TrackedRun {
+                        key,
+                        cached: false,
+                        stored: true,
+                    },
+                )?;
+
+                debug!("Stored results of run \"{}\"", run_id);
+            }
+
+            return Ok(());
+        }
+    }
+    .await;
+
+    if let Err(report) = result {
+        error!("Could not store results of run \"{}\": {}", run_id, report);
+    }
+}
+
+/// Container API client serving the results of previous runs
+/// when a step is run again against an unchanged project,
+/// unless a fresh run is requested.
+///
+/// Only steps building projects are cached,
+/// their results being stored once they have ended with a verdict.
+pub struct CachingContainerAPIClient {
+    client: SharedContainerAPIClient,
+    cache: ResultCache,
+    poll_interval: Duration,
+}
+
+impl CachingContainerAPIClient {
+    pub fn new(client: SharedContainerAPIClient, cache: ResultCache) -> Self {
+        CachingContainerAPIClient {
+            client,
+            cache,
+            poll_interval: Duration::from_secs(1),
+        }
+    }
+
+    /// Set the interval at which started runs are checked for having ended
+    pub fn with_poll_interval(self, poll_interval: Duration) -> Self {
+        CachingContainerAPIClient {
+            poll_interval,
+            ..self
+        }
+    }
+
+    /// Get the key of the results of a step, when they can be cached
+    async fn key(
+        &self,
+        project_step: &StepInVerificationPlan<'_>,
+    ) -> Result<Option<String>, Report> {
+        if step_command(project_step)?.program() != TARGET_VERIFICATION_SCRIPT {
+            return Ok(None);
+        }
+
+        let project_id = project_step.project_id();
+
+        let image = self.client.inspect_image(project_step).await?;
+        let image_digest = image
+            .get("image_digest")
+            .ok_or_else(|| eyre!("Could not get digest of the image of steps"))?;
+
+        let source = find_decoded_source_by_project_id(project_id)?;
+
+        let manifest_path =
+            Path::new(&format_directory_path_to_scaffold(project_id)).join("Cargo.toml");
+        let manifest = fs::read_to_string(manifest_path.as_path()).map_err(|error| {
+            eyre!(
+                "Could not read manifest \"{}\": {}",
+                manifest_path.display(),
+                error
+            )
+        })?;
+
+        Ok(Some(format_key(
+            source.as_str(),
+            project_step.step().name(),
+            &step_command(project_step)?,
+            image_digest.as_str(),
+            manifest.as_str(),
+        )))
+    }
+
+    /// Get the results a run is served from
+    fn cached_result(&self, run_id: &str) -> Result<Option<CachedResult>, Report> {
+        match self.cache.tracked_run(run_id) {
+            Some(run) if run.cached => self
+                .cache
+                .get(run.key.as_str())
+                .map(Some)
+                .ok_or_else(|| eyre!("Results of run \"{}\" are no longer cached", run_id)),
+            _ => Ok(None),
+        }
+    }
+}
+
+#[async_trait]
+impl ContainerAPIClient for CachingContainerAPIClient {
+    type R = Result<HashMap<String, String>, Report>;
+    type P = Result<(), Report>;
+
+    async fn inspect_container_status(&self, project_step: &StepInVerificationPlan) -> Self::R {
+        let run_id = format_run_id(project_step.step().name(), project_step.project_id());
+
+        if let Some(result) = self.cached_result(run_id.as_str())? {
+            let mut progress = result.progress().clone();
+            progress.insert("cached".to_string(), true.to_string());
+
+            return Ok(progress);
+        }
+
+        let mut progress = self.client.inspect_container_status(project_step).await?;
+
+        if self.cache.tracked_run(run_id.as_str()).is_some() {
+            progress.insert("cached".to_string(), false.to_string());
+        }
+
+        Ok(progress)
+    }
+
+    async fn inspect_image(&self, project_step: &StepInVerificationPlan) -> Self::R {
+        self.client.inspect_image(project_step).await
+    }
+
+    async fn remove_existing_container(&self, project_step: &StepInVerificationPlan) -> Self::P {
+        let run_id = format_run_id(project_step.step().name(), project_step.project_id());
+        self.cache.forget(run_id.as_str());
+
+        self.client.remove_existing_container(project_step).await
+    }
+
+    async fn start_container(&self, project_step: &StepInVerificationPlan) -> Self::R {
+        let key = match self.key(project_step).await? {
+            Some(key) => key,
+            None => return self.client.start_container(project_step).await,
+        };
+        let run_id = format_run_id(project_step.step().name(), project_step.project_id());
+
+        if !project_step.fresh_run() && self.cache.get(key.as_str()).is_some() {
+            self.cache.track(
+                run_id.as_str(),
+                &TrackedRun {
+                    key,
+                    cached: true,
+                    stored: true,
+                },
+            )?;
+
+            let mut message = HashMap::<String, String>::new();
+            message.insert("cached".to_string(), true.to_string());
+            message.insert(
+                "message".to_string(),
+                format!(
+                    "Results of a previous run of \"{}\" step against the same project have been found in cache.",
+                    project_step.step().name()
+                ),
+            );
+
+            return Ok(message);
+        }
+
+        let mut message = self.client.start_container(project_step).await?;
+        self.cache.track(
+            run_id.as_str(),
+            &TrackedRun {
+                key: key.clone(),
+                cached: false,
+                stored: false,
+            },
+        )?;
+        message.insert("cached".to_string(), false.to_string());
+
+        tokio::spawn(store_when_ended(
+            self.client.clone(),
+            self.cache.clone(),
+            project_step.step().name().to_string(),
+            project_step.project_id().clone(),
+            key,
+            self.poll_interval,
+        ));
+
+        Ok(message)
+    }
+
+    async fn stop_container(&self, project_step: &StepInVerificationPlan) -> Self::P {
+        let run_id = format_run_id(project_step.step().name(), project_step.project_id());
+
+        // Runs served from cache have nothing left to stop
+        if self.cached_result(run_id.as_str())?.is_some() {
+            return Ok(());
+        }
+
+        self.client.stop_container(project_step).await
+    }
+
+    async fn tail_container_logs(
+        &self,
+        project_step: &StepInVerificationPlan,
+        cursor: Option<&LogCursor>,
+    ) -> Self::R {
+        let run_id = format_run_id(project_step.step().name(), project_step.project_id());
+
+        if let Some(result) = self.cached_result(run_id.as_str())? {
+            let (new_logs, next_cursor) = slice_logs_since(result.raw_log(), cursor)?;
+
+            let mut message = HashMap::<String, String>::new();
+            message.insert("container_name".to_string(), run_id);
+            message.insert("cursor".to_string(), next_cursor.to_string());
+            message.insert("raw_log".to_string(), new_logs);
+            message.insert("cached".to_string(), true.to_string());
+
+            return Ok(message);
+        }
+
+        self.client.tail_container_logs(project_step, cursor).await
+    }
+
+    async fn list_runs(&self) -> Self::R {
+        self.client.list_runs().await
+    }
+}
+
+#[cfg(test)]
+async fn wait_until_stored(cache: &ResultCache, run_id: &str) {
+    for _ in 0..100 {
+        if cache.tracked_run(run_id).is_some_and(|run| run.stored) {
+            return;
+        }
+
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+
+    panic!("Results of run \"{}\" have not been stored", run_id)
+}
+
+#[tokio::test]
+async fn it_serves_results_of_unchanged_projects_from_cache() {
+    use crate::domain::value_object::{BackendSelection, VerificationOptions};
+    use crate::domain::verification_runtime::{VerificationRuntime, VerificationStepRunner};
+    use crate::infra::file_system::PROJECT_SETTINGS_EXTENSION;
+    use crate::infra::program_verification::which_step;
+    use crate::infra::scaffold::select_project_settings;
+    use crate::infra::verification_runtime::in_memory::InMemoryContainerAPIClient;
+    use crate::infra::ContainerAPIClientFactory;
+    use crate::infra::PROGRAM_VERIFICATION;
+    use crate::test;
+    use std::sync::Arc;
+
+    let project_id = test::upload_source();
+    let run_id = format_run_id(PROGRAM_VERIFICATION, project_id.as_str());
+    let directory =
+        env::temp_dir().join(format!("result-cache-{}", test::generate_random_letters()));
+
+    let client = InMemoryContainerAPIClient::new();
+    client
+        .script_statuses(run_id.as_str(), vec!["exited"])
+        .script_exit_code(run_id.as_str(), 1)
+        .script_logs(run_id.as_str(), "Tests results for \"safe_transfer\"\n");
+
+    let in_memory_client = client.clone();
+    let cache = ResultCache::new(directory.clone());
+    let caching_cache = cache.clone();
+    let factory: ContainerAPIClientFactory = Arc::new(move || {
+        Ok(Arc::new(
+            CachingContainerAPIClient::new(
+                Arc::new(in_memory_client.clone()),
+                caching_cache.clone(),
+            )
+            .with_poll_interval(Duration::from_millis(10)),
+        ) as SharedContainerAPIClient)
+    });
+
+    // Options are passed to the default backend of the step
+    let run = |options: Option<VerificationOptions>, fresh_run: bool| {
+        let factory = factory.clone();
+        let project_id = project_id.clone();
+
+        async move {
+            let backend = options.map(|options| BackendSelection::new("klee", &options).unwrap());
+            let steps = VerificationRuntime::build_steps().unwrap();
+            let step = which_step(&steps, PROGRAM_VERIFICATION.to_string(), project_id)
+                .unwrap()
+                .with_fresh_run(fresh_run)
+                .with_backend(backend);
+            let runtime =
+                VerificationRuntime::with_container_api_client_factory(&factory, step, steps)
+                    .unwrap();
+
+            let started = runtime.start_running().await.unwrap();
+            let progress = runtime.get_progress().await.unwrap();
+            let report = runtime.get_report(None).await.unwrap();
+
+            (
+                started["cached"].clone(),
+                progress,
+                report["raw_log"].clone(),
+            )
+        }
+    };
+
+    let (cached, progress, _) = run(None, false).await;
+    assert_eq!("false", cached);
+    assert_eq!("verification_failed", progress["outcome"]);
+    wait_until_stored(&cache, run_id.as_str()).await;
+
+    let (cached, progress, raw_log) = run(Some(VerificationOptions::default()), false).await;
+    assert_eq!("true", cached);
+    assert_eq!("true", progress["cached"]);
+    assert_eq!("1", progress["exit_code"]);
+    assert_eq!("verification_failed", progress["outcome"]);
+    assert_eq!("Tests results for \"safe_transfer\"\n", raw_log);
+
+    let starts = |client: &InMemoryContainerAPIClient| {
+        client
+            .calls()
+            .iter()
+            .filter(|call| call.starts_with("start_container"))
+            .count()
+    };
+    assert_eq!(1, starts(&client));
+
+    assert_eq!("false", run(None, true).await.0);
+    let quiet = VerificationOptions {
+        quiet: Some(true),
+        ..Default::default()
+    };
+    assert_eq!("false", run(Some(quiet), false).await.0);
+    assert_eq!(3, starts(&client));
+
+    // The time KLEE can explore paths for is part of the key
+    let budget = |seconds: u64| VerificationOptions {
+        max_time: Some(seconds),
+        ..Default::default()
+    };
+    assert_eq!("false", run(Some(budget(60)), false).await.0);
+    wait_until_stored(&cache, run_id.as_str()).await;
+    assert_eq!("true", run(Some(budget(60)), false).await.0);
+    assert_eq!("false", run(Some(budget(120)), false).await.0);
+
+    // Sources uploaded again with another template (e.g. once garbage collected) are verified again
+    let select_template = |template: &str| {
+        fs::remove_file(test::uploaded_file_path(
+            project_id.as_str(),
+            PROJECT_SETTINGS_EXTENSION,
+        ))
+        .unwrap_or_default();
+        select_project_settings(project_id.as_str(), None, Some("2.1.0"), Some(template)).unwrap();
+    };
+    select_template("erc20");
+    assert_eq!("false", run(None, false).await.0);
+    wait_until_stored(&cache, run_id.as_str()).await;
+    assert_eq!("true", run(None, false).await.0);
+    select_template("erc721");
+    assert_eq!("false", run(None, false).await.0);
+
+    client.script_image_digest("sha256:upgraded");
+    assert_eq!("false", run(None, false).await.0);
+
+    fs::remove_dir_all(directory).ok();
+    test::remove_project(project_id.as_str());
+}
+
+#[tokio::test]
+async fn it_does_not_cache_results_of_interrupted_runs() {
+    use crate::domain::verification_runtime::{VerificationRuntime, VerificationStepRunner};
+    use crate::infra::program_verification::which_step;
+    use crate::infra::verification_runtime::in_memory::InMemoryContainerAPIClient;
+    use crate::infra::PROGRAM_FUZZING;
+    use crate::test;
+    use std::sync::Arc;
+
+    let project_id = test::upload_source();
+    let run_id = format_run_id(PROGRAM_FUZZING, project_id.as_str());
+    let directory =
+        env::temp_dir().join(format!("result-cache-{}", test::generate_random_letters()));
+
+    let client = InMemoryContainerAPIClient::new();
+    client
+        .script_statuses(run_id.as_str(), vec!["exited"])
+        .script_exit_code(run_id.as_str(), 137);
+
+    let cache = ResultCache::new(directory.clone());
+    let caching_client: SharedContainerAPIClient = Arc::new(
+        CachingContainerAPIClient::new(Arc::new(client.clone()), cache.clone())
+            .with_poll_interval(Duration::from_millis(10)),
+    );
+
+    for _ in 0..2 {
+        let steps = VerificationRuntime::build_steps().unwrap();
+        let step = which_step(&steps, PROGRAM_FUZZING.to_string(), project_id.clone()).unwrap();
+        let runtime =
+            VerificationRuntime::with_container_api_client(caching_client.clone(), step, steps);
+
+        assert_eq!("false", runtime.start_running().await.unwrap()["cached"]);
+        assert_eq!(
+            "infrastructure_error",
+            runtime.get_progress().await.unwrap()["outcome"]
+        );
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!cache.tracked_run(run_id.as_str()).unwrap().stored);
+    }
+
+    fs::remove_dir_all(directory).ok();
+    test::remove_project(project_id.as_str());
+}
+
+#[test]
+fn it_evicts_least_recently_used_results() {
+    use crate::test;
+
+    let directory =
+        env::temp_dir().join(format!("result-cache-{}", test::generate_random_letters()));
+    let cache = ResultCache::new(directory.clone()).with_max_size(Some(1024));
+    let result = CachedResult::new(HashMap::new(), "0".repeat(400));
+
+    for key in ["oldest", "used", "newest"] {
+        cache.store(key, &result).unwrap();
+        std::thread::sleep(Duration::from_millis(20));
+    }
+    assert!(cache.get("oldest").is_none());
+
+    // Reading results marks them as recently used
+    assert!(cache.get("used").is_some());
+    cache.store("latest", &result).unwrap();
+
+    assert!(cache.get("newest").is_none());
+    assert!(cache.get("used").is_some());
+    assert!(cache.get("latest").is_some());
+
+    assert_eq!(
+        None,
+        ResultCache::from_lookup(|name| match name {
+            "RESULT_CACHE_MAX_SIZE" => Some("1g".to_string()),
+            _ => None,
+        })
+        .unwrap()
+    );
+    assert!(ResultCache::from_lookup(|name| match name {
+        "RESULT_CACHE_DIRECTORY" => Some("/tmp/safepkt-results".to_string()),
+        "RESULT_CACHE_MAX_SIZE" => Some("a lot".to_string()),
+        _ => None,
+    })
+    .is_err());
+
+    fs::remove_dir_all(directory).ok();
+}
@@ -1 +1,91 @@
 pub mod docker;
+pub mod in_memory;
+pub mod process;
+
+use crate::domain::value_object::LogCursor;
+use crate::infrastructure as infra;
+use color_eyre::{eyre::eyre, Report};
+use docker::DockerContainerAPIClient;
+use infra::result_cache::{CachingContainerAPIClient, ResultCache};
+use infra::verification::SharedContainerAPIClient;
+use process::ProcessAPIClient;
+use std::env;
+use std::sync::Arc;
+
+pub static DOCKER_RUNTIME: &str = "docker";
+pub static PROCESS_RUNTIME: &str = "process";
+
+/// Get the name of the runtime running verification steps.
+/// It is declared as the value of the VERIFICATION_RUNTIME environment variable,
+/// Docker being the default runtime.
+///
+/// # Examples
+///
+/// ```
+/// use safepkt_backend::infra::verification_runtime;
+/// use std::env;
+///
+/// env::remove_var("VERIFICATION_RUNTIME");
+/// assert_eq!("docker", verification_runtime::get_runtime_name());
+/// ```
+///
+pub fn get_runtime_name() -> String {
+    env::var("VERIFICATION_RUNTIME").unwrap_or_else(|_| DOCKER_RUNTIME.to_string())
+}
+
+/// Create the client of the runtime selected by configuration,
+/// serving results from cache when a result cache is declared (RESULT_CACHE_DIRECTORY).
+pub fn new_container_api_client() -> Result<SharedContainerAPIClient, Report> {
+    let runtime_name = get_runtime_name();
+
+    let client: SharedContainerAPIClient = if runtime_name == DOCKER_RUNTIME {
+        Arc::new(DockerContainerAPIClient::new()?)
+    } else if runtime_name == PROCESS_RUNTIME {
+        Arc::new(ProcessAPIClient::new()?)
+    } else {
+        return Err(eyre!(
+            "Unknown verification runtime \"{}\" (expecting \"{}\" or \"{}\")",
+            runtime_name,
+            DOCKER_RUNTIME,
+            PROCESS_RUNTIME
+        ));
+    };
+
+    match ResultCache::from_env()? {
+        Some(cache) => Ok(Arc::new(CachingContainerAPIClient::new(client, cache))),
+        None => Ok(client),
+    }
+}
+
+/// Slice logs from the offset provided by a cursor,
+/// before returning them with the cursor to read the next ones from.
+/// Logs are read from their beginning when shorter than the offset
+/// (e.g. after a step has been restarted).
+///
+/// # Examples
+///
+/// ```
+/// use safepkt_backend::app::domain::value_object::LogCursor;
+/// use safepkt_backend::infra::verification_runtime;
+///
+/// let (logs, cursor) = verification_runtime::slice_logs_since("Running 1 test\n", None).unwrap();
+/// assert_eq!(("Running 1 test\n", "offset:15"), (logs.as_str(), cursor.to_string().as_str()));
+///
+/// let cursor = LogCursor::Offset(8);
+/// let (logs, _) = verification_runtime::slice_logs_since("Running 1 test\n", Some(&cursor)).unwrap();
+/// assert_eq!("1 test\n", logs);
+/// ```
+///
+pub fn slice_logs_since(
+    logs: &str,
+    cursor: Option<&LogCursor>,
+) -> Result<(String, LogCursor), Report> {
+    let offset = LogCursor::offset(cursor)? as usize;
+    let offset = if offset > logs.len() { 0 } else { offset };
+
+    let new_logs = logs
+        .get(offset..)
+        .ok_or_else(|| eyre!("Invalid log cursor \"{}\"", offset))?;
+
+    Ok((new_logs.to_string(), LogCursor::Offset(logs.len() as u64)))
+}
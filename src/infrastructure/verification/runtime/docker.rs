@@ -1,7 +1,9 @@
+pub mod connection;
 pub mod container;
 
 use crate::domain::value_object::*;
 use crate::domain::verification_runtime::*;
+use crate::infra::program_verification::format_run_id;
 use anyhow::Result;
 use async_trait::async_trait;
 use bollard::Docker;
@@ -14,7 +16,7 @@ pub struct DockerContainerAPIClient<C> {
 
 impl DockerContainerAPIClient<Docker> {
     pub fn new() -> Result<Self, Report> {
-        let docker = Docker::connect_with_socket_defaults()?;
+        let docker = connection::DockerConnectionSettings::from_env()?.connect()?;
 
         Ok(DockerContainerAPIClient { client: docker })
     }
@@ -27,11 +29,7 @@ impl DockerContainerAPIClient<Docker> {
         &self,
         project_step: &StepInVerificationPlan,
     ) -> String {
-        format!(
-            "{}-{}",
-            project_step.step().name().clone(),
-            project_step.project_id().clone()
-        )
+        format_run_id(project_step.step().name(), project_step.project_id())
     }
 }
 
@@ -45,12 +43,16 @@ impl ContainerAPIClient for DockerContainerAPIClient<Docker> {
         container::inspect_container_status(&self, container_name.as_str()).await
     }
 
+    async fn inspect_image(&self, project_step: &StepInVerificationPlan) -> Self::R {
+        container::inspect_image(&self, project_step).await
+    }
+
     async fn remove_existing_container(&self, project_step: &StepInVerificationPlan) -> Self::P {
         let container_name = self.format_container_name_for_step_in_verification_plan(project_step);
         container::remove_existing_container(&self, container_name).await
     }
 
-    async fn start_container(&self, project_step: &StepInVerificationPlan) -> Self::P {
+    async fn start_container(&self, project_step: &StepInVerificationPlan) -> Self::R {
         let container_name = self.format_container_name_for_step_in_verification_plan(project_step);
         container::start_container(&self, container_name, project_step).await
     }
@@ -60,8 +62,16 @@ impl ContainerAPIClient for DockerContainerAPIClient<Docker> {
         container::stop_container(&self, container_name, project_step).await
     }
 
-    async fn tail_container_logs(&self, project_step: &StepInVerificationPlan) -> Self::R {
+    async fn tail_container_logs(
+        &self,
+        project_step: &StepInVerificationPlan,
+        cursor: Option<&LogCursor>,
+    ) -> Self::R {
         let container_name = self.format_container_name_for_step_in_verification_plan(project_step);
-        container::tail_container_logs(&self, container_name.as_str()).await
+        container::tail_container_logs(&self, container_name.as_str(), cursor).await
+    }
+
+    async fn list_runs(&self) -> Self::R {
+        container::list_runs(&self).await
     }
 }
@@ -0,0 +1,201 @@
+use anyhow::Result;
+use bollard::{ClientVersion, Docker, API_DEFAULT_VERSION};
+use color_eyre::{eyre::eyre, Report};
+use std::env;
+use std::path::{Path, PathBuf};
+
+static DEFAULT_SOCKET: &str = "/var/run/docker.sock";
+static DEFAULT_TIMEOUT: u64 = 120;
+
+#[derive(Debug, PartialEq)]
+pub struct TlsCertificates {
+    pub key: PathBuf,
+    pub cert: PathBuf,
+    pub ca: PathBuf,
+}
+
+/// Settings of the connection to a Docker daemon
+/// (or to a daemon exposing a Docker-compatible API like Podman):
+/// a unix socket or a TCP endpoint secured with TLS certificates,
+/// along with the version of the API and a request timeout.
+///
+/// The default unix socket is used when neither a socket nor a TCP endpoint is declared.
+#[derive(Debug, Default, PartialEq)]
+pub struct DockerConnectionSettings {
+    pub socket: Option<String>,
+    pub tcp_endpoint: Option<String>,
+    pub tls: Option<TlsCertificates>,
+    pub api_version: Option<(usize, usize)>,
+    pub timeout: Option<u64>,
+}
+
+fn parse_api_version(version: &str) -> Result<(usize, usize), Report> {
+    let invalid_version = || eyre!("Invalid Docker API version \"{}\"", version);
+
+    let (major, minor) = version
+        .trim_start_matches('v')
+        .split_once('.')
+        .ok_or_else(invalid_version)?;
+
+    Ok((
+        major.parse::<usize>().map_err(|_| invalid_version())?,
+        minor.parse::<usize>().map_err(|_| invalid_version())?,
+    ))
+}
+
+impl DockerConnectionSettings {
+    pub fn from_env() -> Result<Self, Report> {
+        Self::from_lookup(|name| env::var(name).ok().filter(|value| !value.is_empty()))
+    }
+
+    pub fn from_lookup<L: Fn(&str) -> Option<String>>(lookup: L) -> Result<Self, Report> {
+        let tls_paths = (
+            lookup("DOCKER_TLS_KEY"),
+            lookup("DOCKER_TLS_CERT"),
+            lookup("DOCKER_TLS_CA"),
+        );
+
+        let tls = match tls_paths {
+            (Some(key), Some(cert), Some(ca)) => Some(TlsCertificates {
+                key: PathBuf::from(key),
+                cert: PathBuf::from(cert),
+                ca: PathBuf::from(ca),
+            }),
+            (None, None, None) => None,
+            _ => {
+                return Err(eyre!(
+                    "DOCKER_TLS_KEY, DOCKER_TLS_CERT and DOCKER_TLS_CA are to be declared together"
+                ))
+            }
+        };
+
+        let settings = DockerConnectionSettings {
+            socket: lookup("DOCKER_SOCKET"),
+            tcp_endpoint: lookup("DOCKER_TCP_ENDPOINT"),
+            tls,
+            api_version: lookup("DOCKER_API_VERSION")
+                .map(|version| parse_api_version(version.as_str()))
+                .transpose()?,
+            timeout: lookup("DOCKER_TIMEOUT")
+                .map(|timeout| {
+                    timeout
+                        .parse::<u64>()
+                        .map_err(|_| eyre!("Invalid Docker timeout \"{}\"", timeout))
+                })
+                .transpose()?,
+        };
+
+        if settings.socket.is_some() && settings.tcp_endpoint.is_some() {
+            return Err(eyre!(
+                "DOCKER_SOCKET and DOCKER_TCP_ENDPOINT can not be declared together"
+            ));
+        }
+
+        if settings.tls.is_some() && settings.tcp_endpoint.is_none() {
+            return Err(eyre!("TLS certificates require a DOCKER_TCP_ENDPOINT"));
+        }
+
+        Ok(settings)
+    }
+
+    fn client_version(&self) -> ClientVersion {
+        match self.api_version {
+            Some((major_version, minor_version)) => ClientVersion {
+                major_version,
+                minor_version,
+            },
+            None => ClientVersion {
+                major_version: API_DEFAULT_VERSION.major_version,
+                minor_version: API_DEFAULT_VERSION.minor_version,
+            },
+        }
+    }
+
+    /// Connect to a Docker daemon per settings
+    pub fn connect(&self) -> Result<Docker, Report> {
+        let timeout = self.timeout.unwrap_or(DEFAULT_TIMEOUT);
+        let client_version = self.client_version();
+
+        let docker = match (&self.tcp_endpoint, &self.tls, &self.socket) {
+            (Some(endpoint), Some(tls), _) => Docker::connect_with_ssl(
+                endpoint.as_str(),
+                Path::new(&tls.key),
+                Path::new(&tls.cert),
+                Path::new(&tls.ca),
+                timeout,
+                &client_version,
+            )?,
+            (Some(endpoint), None, _) => {
+                Docker::connect_with_http(endpoint.as_str(), timeout, &client_version)?
+            }
+            (None, _, socket) => Docker::connect_with_socket(
+                socket.as_deref().unwrap_or(DEFAULT_SOCKET),
+                timeout,
+                &client_version,
+            )?,
+        };
+
+        Ok(docker)
+    }
+}
+
+#[test]
+fn it_reads_docker_connection_settings() {
+    use std::collections::HashMap;
+
+    let mut variables = HashMap::new();
+    variables.insert("DOCKER_TCP_ENDPOINT", "tcp://worker:2376");
+    variables.insert("DOCKER_TLS_KEY", "/certs/key.pem");
+    variables.insert("DOCKER_TLS_CERT", "/certs/cert.pem");
+    variables.insert("DOCKER_TLS_CA", "/certs/ca.pem");
+    variables.insert("DOCKER_API_VERSION", "1.40");
+    variables.insert("DOCKER_TIMEOUT", "30");
+
+    let settings =
+        DockerConnectionSettings::from_lookup(|name| variables.get(name).map(|v| v.to_string()))
+            .unwrap();
+
+    assert_eq!(Some("tcp://worker:2376".to_string()), settings.tcp_endpoint);
+    assert_eq!(
+        Some(TlsCertificates {
+            key: PathBuf::from("/certs/key.pem"),
+            cert: PathBuf::from("/certs/cert.pem"),
+            ca: PathBuf::from("/certs/ca.pem"),
+        }),
+        settings.tls
+    );
+    assert_eq!(Some((1, 40)), settings.api_version);
+    assert_eq!(Some(30), settings.timeout);
+
+    assert_eq!(
+        DockerConnectionSettings::default(),
+        DockerConnectionSettings::from_lookup(|_| None).unwrap()
+    );
+}
+
+#[test]
+fn it_rejects_inconsistent_docker_connection_settings() {
+    let tls_without_endpoint = DockerConnectionSettings::from_lookup(|name| match name {
+        "DOCKER_TLS_KEY" | "DOCKER_TLS_CERT" | "DOCKER_TLS_CA" => {
+            Some("/certs/any.pem".to_string())
+        }
+        _ => None,
+    });
+    assert!(tls_without_endpoint.is_err());
+
+    let socket_and_endpoint = DockerConnectionSettings::from_lookup(|name| match name {
+        "DOCKER_SOCKET" => Some("/run/user/1000/podman/podman.sock".to_string()),
+        "DOCKER_TCP_ENDPOINT" => Some("tcp://worker:2375".to_string()),
+        _ => None,
+    });
+    assert!(socket_and_endpoint.is_err());
+
+    let invalid_version = DockerConnectionSettings::from_lookup(|name| match name {
+        "DOCKER_API_VERSION" => Some("latest".to_string()),
+        _ => None,
+    });
+    assert_eq!(
+        "Invalid Docker API version \"latest\"",
+        invalid_version.unwrap_err().to_string()
+    );
+}
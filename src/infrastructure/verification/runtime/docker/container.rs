@@ -1,17 +1,37 @@
 mod follow_up;
+pub mod labels;
+mod limits;
+mod network;
 mod removal;
+mod security;
 mod start;
 
 pub use follow_up::container_exists;
+pub use follow_up::find_container;
 pub use follow_up::inspect_container_status;
+pub use follow_up::list_runs;
 pub use follow_up::tail_container_logs;
+pub use follow_up::STATUS_OOM_KILLED;
+pub use limits::parse_size;
+pub use limits::ResourceLimits;
+pub use network::NetworkPolicy;
 pub use removal::remove_existing_container;
+pub use security::SecurityProfile;
 
-pub use start::program_fuzzing_cmd_provider;
-pub use start::program_verification_cmd_provider;
-pub use start::source_code_restoration_cmd_provider;
+pub use start::built_in_mount_targets;
+pub use start::get_mounts;
+pub use start::get_rvt_directory;
+pub use start::get_uid_gid;
+pub use start::get_uploaded_sources_listing_script_path;
+pub use start::get_verification_script_path;
+pub use start::inspect_image;
 pub use start::start_container;
+pub use start::step_command;
 pub use start::stop_container;
-pub use start::uploaded_sources_listing_cmd_provider;
 
+pub use start::TARGET_BUILD_CACHE;
 pub use start::TARGET_RVT_DIRECTORY;
+pub use start::TARGET_SOURCE_DIRECTORY;
+pub use start::TARGET_UPLOADED_SOURCES;
+pub use start::TARGET_UPLOADED_SOURCES_LISTING_SCRIPT;
+pub use start::TARGET_VERIFICATION_SCRIPT;
@@ -1,11 +1,16 @@
+use crate::domain::value_object::LogCursor;
 use crate::infrastructure as infra;
 use anyhow::Result;
 use bollard::container::{InspectContainerOptions, ListContainersOptions, LogOutput, LogsOptions};
 use bollard::models::*;
 use bollard::Docker;
+use chrono::{DateTime, TimeZone, Utc};
 use color_eyre::{eyre::eyre, Report};
 use futures::stream::StreamExt;
 use infra::display::output;
+use infra::verification::runtime::docker::container::labels::{
+    get_backend_metadata, instance_filters, run_filters, LABEL_RUN_ID,
+};
 use infra::verification::runtime::docker::DockerContainerAPIClient;
 use regex::Regex;
 use std::collections::HashMap;
@@ -13,16 +18,63 @@ use std::default::Default;
 use std::str;
 use tracing::debug;
 
-pub async fn container_exists(
+/// Status reported in place of "exited"
+/// when a container has been killed for exceeding its memory limit
+pub static STATUS_OOM_KILLED: &str = "oom_killed";
+
+/// Convert a timestamp provided by the Docker Engine API
+/// (RFC 3339 in UTC e.g. "2021-11-02T10:12:45.123456789Z")
+/// into seconds elapsed since the Unix epoch.
+fn parse_timestamp(timestamp: &str) -> Option<u64> {
+    let timestamp = DateTime::parse_from_rfc3339(timestamp).ok()?.timestamp();
+
+    // Docker reports "0001-01-01T00:00:00Z" for containers which have not finished
+    if timestamp <= 0 {
+        return None;
+    }
+
+    Some(timestamp as u64)
+}
+
+#[test]
+fn it_parses_timestamps_of_the_docker_engine_api() {
+    assert_eq!(
+        Some(1_635_847_965),
+        parse_timestamp("2021-11-02T10:12:45.123456789Z")
+    );
+    assert_eq!(Some(951_782_400), parse_timestamp("2000-02-29T00:00:00Z"));
+    assert_eq!(None, parse_timestamp("0001-01-01T00:00:00Z"));
+    assert_eq!(None, parse_timestamp("yesterday"));
+}
+
+/// Find the container of a run by its labels,
+/// as filtering by name would match any container having a name containing the run id.
+pub async fn find_container(
     container_api_client: &DockerContainerAPIClient<Docker>,
-    container_name: &str,
-) -> Result<bool, Report> {
-    let mut filters = HashMap::new();
-    filters.insert("name", vec![container_name]);
+    run_id: &str,
+) -> Result<Option<ContainerSummaryInner>, Report> {
+    let options = Some(ListContainersOptions {
+        all: true,
+        filters: run_filters(run_id),
+        ..Default::default()
+    });
+
+    let containers = container_api_client
+        .client()
+        .list_containers(options)
+        .await?;
+
+    Ok(containers.into_iter().next())
+}
 
+/// List the runs which containers are managed by this backend instance,
+/// along with their raw status, whether their project still exists or not.
+pub async fn list_runs(
+    container_api_client: &DockerContainerAPIClient<Docker>,
+) -> Result<HashMap<String, String>, Report> {
     let options = Some(ListContainersOptions {
         all: true,
-        filters,
+        filters: instance_filters(),
         ..Default::default()
     });
 
@@ -31,158 +83,271 @@ pub async fn container_exists(
         .list_containers(options)
         .await?;
 
-    Ok(!containers.is_empty())
+    Ok(containers
+        .into_iter()
+        .filter_map(|container| {
+            let run_id = container.labels?.get(LABEL_RUN_ID)?.clone();
+            Some((run_id, container.state.unwrap_or_default()))
+        })
+        .collect())
 }
 
-pub async fn tail_container_logs<'a>(
+pub async fn container_exists(
     container_api_client: &DockerContainerAPIClient<Docker>,
     container_name: &str,
-) -> Result<HashMap<String, String>, Report> {
-    if !container_exists(container_api_client, container_name)
-        .await
-        .unwrap()
-    {
-        return Err(eyre!(
-            "There is no container having name \"{}\"",
-            container_name
-        ));
+) -> Result<bool, Report> {
+    Ok(find_container(container_api_client, container_name)
+        .await?
+        .is_some())
+}
+
+/// Format log output of a container before displaying it
+struct LogFormatter {
+    stderr_output: Regex,
+    running_test: Regex,
+}
+
+impl LogFormatter {
+    fn new() -> Result<Self, Report> {
+        Ok(LogFormatter {
+            stderr_output: Regex::new(r"^STDERR:.+")?,
+            running_test: Regex::new(r"^Running\s.+")?,
+        })
     }
 
+    fn format(&self, log: &LogOutput, message: &[u8]) -> Result<Option<String>, Report> {
+        let message = str::from_utf8(message)?;
+
+        let formatted = match log {
+            LogOutput::StdOut { .. } => {
+                if self.running_test.is_match(message) {
+                    output::print("{}{}", vec!["\n", message], Some(true));
+                    format!("{}{}", "\n", String::from(message))
+                } else if self.stderr_output.is_match(message) {
+                    output::print("{}", vec!["."], Some(true));
+                    String::from(".")
+                } else {
+                    output::print("[STDOUT] {}", vec![message], Some(true));
+                    String::from(message)
+                }
+            }
+            LogOutput::StdErr { .. } => {
+                output::eprint("[STDERR] {}", vec![message], Some(true));
+                String::from(message)
+            }
+            LogOutput::Console { .. } => {
+                output::print("[CONSOLE] {}", vec![message], Some(true));
+                String::from(message)
+            }
+            _ => return Ok(None),
+        };
+
+        Ok(Some(formatted))
+    }
+}
+
+fn log_message(log: &LogOutput) -> &[u8] {
+    match log {
+        LogOutput::StdOut { message }
+        | LogOutput::StdErr { message }
+        | LogOutput::Console { message }
+        | LogOutput::StdIn { message } => message,
+    }
+}
+
+/// Split the timestamp prefixing a log message
+/// (RFC 3339 with nanoseconds e.g. "2021-11-02T10:12:45.123456789Z")
+fn split_timestamp(message: &[u8]) -> (Option<DateTime<Utc>>, &[u8]) {
+    let time = |timestamp: &[u8]| {
+        let timestamp = str::from_utf8(timestamp).ok()?;
+
+        DateTime::parse_from_rfc3339(timestamp)
+            .ok()
+            .map(|time| time.with_timezone(&Utc))
+    };
+
+    match message.iter().position(|byte| *byte == b' ') {
+        Some(position) => match time(&message[..position]) {
+            Some(time) => (Some(time), &message[position + 1..]),
+            None => (None, message),
+        },
+        None => (None, message),
+    }
+}
+
+async fn find_container_id(
+    container_api_client: &DockerContainerAPIClient<Docker>,
+    container_name: &str,
+) -> Result<String, Report> {
+    find_container(container_api_client, container_name)
+        .await?
+        .and_then(|container| container.id)
+        .ok_or_else(|| eyre!("There is no container having name \"{}\"", container_name))
+}
+
+/// Tail logs written after the time provided by a cursor (all logs without cursor),
+/// the cursor to read the next logs from being the time of the last log.
+pub async fn tail_container_logs(
+    container_api_client: &DockerContainerAPIClient<Docker>,
+    container_name: &str,
+    cursor: Option<&LogCursor>,
+) -> Result<HashMap<String, String>, Report> {
+    let since = LogCursor::time(cursor)?;
+    let container_id = find_container_id(container_api_client, container_name).await?;
+
+    // The Docker Engine API filters logs by second,
+    // logs written during the second of the cursor being filtered out below
     let mut logs_stream = container_api_client.client().logs(
-        container_name,
+        container_id.as_str(),
         Some(LogsOptions::<String> {
             stdout: true,
             stderr: true,
+            since: since.map_or(0, |since| since.timestamp()),
+            timestamps: true,
+            tail: String::from("all"),
             ..Default::default()
         }),
     );
 
     debug!("About to tail logs for container \"{}\"", container_name);
-    let mut logs: Vec<String> = vec![String::from("")];
+    let mut logs: Vec<String> = vec![];
+    let mut next_cursor = cursor
+        .cloned()
+        .unwrap_or_else(|| LogCursor::Time(Utc.timestamp(0, 0)));
+    let formatter = LogFormatter::new()?;
 
     while let Some(Ok(log)) = logs_stream.next().await {
-        match log {
-            LogOutput::StdOut { message } => {
-                let message = str::from_utf8(&*message)?;
-                let re = Regex::new(r"^STDERR:.+")?;
-                let running_test = Regex::new(r"^Running\s.+")?;
+        let (time, message) = split_timestamp(log_message(&log));
 
-                if running_test.is_match(message) {
-                    output::print("{}{}", vec!["\n", message], Some(true));
-                    logs.push(format!("{}{}", "\n", String::from(message)));
-                } else if re.is_match(message) {
-                    output::print("{}", vec!["."], Some(true));
-                    logs.push(String::from("."));
-                } else {
-                    output::print("[STDOUT] {}", vec![message], Some(true));
-                    logs.push(String::from(message))
-                }
+        if let (Some(time), Some(since)) = (time, since) {
+            if time <= since {
+                continue;
             }
-            LogOutput::StdErr { message } => {
-                let message = str::from_utf8(&*message).unwrap();
-                output::eprint("[STDERR] {}", vec![message], Some(true));
-                logs.push(String::from(message))
-            }
-            LogOutput::Console { message } => {
-                let message = str::from_utf8(&*message).unwrap();
-                output::print("[CONSOLE] {}", vec![message], Some(true));
-                logs.push(String::from(message))
-            }
-            _ => {}
+        }
+
+        if let Some(formatted) = formatter.format(&log, message)? {
+            logs.push(formatted);
+        }
+        if let Some(time) = time {
+            next_cursor = LogCursor::Time(time);
         }
     }
 
-    let all_logs = logs.join("");
+    let new_logs = logs.join("");
 
     let mut message = HashMap::<String, String>::new();
-
     message.insert("container_name".to_string(), container_name.to_string());
     message.insert(
         "messages".to_string(),
         format!(
             "Logs tailed for container having name \"{}\":\n\n{}",
-            container_name, all_logs,
+            container_name, new_logs,
         ),
     );
-    message.insert("raw_log".to_string(), all_logs);
+    message.insert("cursor".to_string(), next_cursor.to_string());
+    message.insert("raw_log".to_string(), new_logs);
 
     Ok(message)
 }
 
-async fn get_status<'a>(
+#[test]
+fn it_splits_timestamps_prefixing_log_messages() {
+    assert_eq!(
+        (
+            Some(Utc.timestamp(1_635_847_965, 123_456_789)),
+            "Running 1 test".as_bytes()
+        ),
+        split_timestamp(b"2021-11-02T10:12:45.123456789Z Running 1 test")
+    );
+    assert_eq!(
+        (None, "Running 1 test".as_bytes()),
+        split_timestamp(b"Running 1 test")
+    );
+}
+
+async fn get_status(
     container_api_client: &DockerContainerAPIClient<Docker>,
+    container_name: &str,
     container_summary: &ContainerSummaryInner,
 ) -> Result<HashMap<String, String>, Report> {
+    let container_id = container_summary
+        .id
+        .as_ref()
+        .ok_or_else(|| eyre!("Container having name \"{}\" has no id", container_name))?;
+
     let container_inspect_response = container_api_client
         .client()
-        .inspect_container(
-            container_summary.id.as_ref().unwrap(),
-            None::<InspectContainerOptions>,
-        )
-        .await
-        .unwrap();
-
-    let container_image = container_summary.image.as_ref().unwrap().as_str();
-    let container_name = container_summary.id.as_ref().unwrap();
-
-    if let Some(state) = container_inspect_response.state {
-        if let Some(status) = state.status {
-            let mut message = HashMap::<String, String>::new();
-            message.insert("container_name".to_string(), container_name.to_string());
-            message.insert("docker_image".to_string(), String::from(container_image));
-            message.insert("raw_status".to_string(), status.to_string());
-            message.insert(
-                "message".to_string(),
-                format!(
-                    "Status provided by inspection of container having name \"{}\" and being based on \"{}\" Docker image is \"{}\"",
-                    container_name.to_string(),
-                    container_image,
-                    status.to_string(),
-                ),
-            );
-
-            return Ok(message);
+        .inspect_container(container_id, None::<InspectContainerOptions>)
+        .await?;
+
+    let container_image = container_summary.image.as_deref().unwrap_or_default();
+
+    let state = container_inspect_response
+        .state
+        .ok_or_else(|| eyre!("Container having name \"{}\" has no state", container_name))?;
+    let status = state
+        .status
+        .map(|status| status.to_string())
+        .unwrap_or_default();
+    let oom_killed = state.oom_killed == Some(true);
+    let status = if oom_killed {
+        STATUS_OOM_KILLED.to_string()
+    } else {
+        status
+    };
+
+    let mut message = HashMap::<String, String>::new();
+    message.insert("container_name".to_string(), container_name.to_string());
+    message.insert("container_id".to_string(), container_id.to_string());
+    message.insert("docker_image".to_string(), String::from(container_image));
+    message.insert(
+        "message".to_string(),
+        format!(
+            "Status provided by inspection of container having name \"{}\" and being based on \"{}\" Docker image is \"{}\"",
+            container_name,
+            container_image,
+            status,
+        ),
+    );
+    message.insert("oom_killed".to_string(), oom_killed.to_string());
+
+    if let Some(started_at) = state.started_at.as_deref().and_then(parse_timestamp) {
+        message.insert("started_at".to_string(), started_at.to_string());
+    }
+
+    if let Some(finished_at) = state.finished_at.as_deref().and_then(parse_timestamp) {
+        message.insert("finished_at".to_string(), finished_at.to_string());
+
+        if let Some(exit_code) = state.exit_code {
+            message.insert("exit_code".to_string(), exit_code.to_string());
         }
     }
 
-    unreachable!()
+    if let Some(labels) = container_summary.labels.as_ref() {
+        message.extend(get_backend_metadata(labels));
+    }
+
+    message.insert("raw_status".to_string(), status);
+
+    Ok(message)
 }
 
-pub async fn inspect_container_status<'a>(
+pub async fn inspect_container_status(
     container_api_client: &DockerContainerAPIClient<Docker>,
     container_name: &str,
 ) -> Result<HashMap<String, String>, Report> {
-    let mut list_container_filters = HashMap::new();
-    list_container_filters.insert("name", vec![container_name]);
-
-    if !container_exists(container_api_client, container_name)
-        .await
-        .unwrap()
-    {
-        return Err(eyre!(
-            "There is no container having name \"{}\"",
-            container_name
-        ));
-    }
-
-    let containers = container_api_client
-        .client()
-        .list_containers(Some(ListContainersOptions {
-            all: true,
-            filters: list_container_filters,
-            ..Default::default()
-        }))
-        .await?;
-
-    match containers.first() {
+    match find_container(container_api_client, container_name).await? {
         Some(container_summary_inner) => {
-            let status = get_status(container_api_client, container_summary_inner).await?;
-
-            Ok(status)
-        }
-        _ => {
-            // Handled before by checking if container exists
-            unreachable!();
+            get_status(
+                container_api_client,
+                container_name,
+                &container_summary_inner,
+            )
+            .await
         }
+        None => Err(eyre!(
+            "There is no container having name \"{}\"",
+            container_name
+        )),
     }
 }
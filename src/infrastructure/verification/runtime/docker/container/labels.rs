@@ -0,0 +1,129 @@
+use crate::domain::value_object::*;
+use crate::infra::program_verification::format_run_id;
+use std::collections::HashMap;
+use std::env;
+
+pub static LABEL_STEP: &str = "org.safepkt.step";
+pub static LABEL_PROJECT_ID: &str = "org.safepkt.project_id";
+pub static LABEL_RUN_ID: &str = "org.safepkt.run_id";
+pub static LABEL_INSTANCE: &str = "org.safepkt.instance";
+pub static LABEL_BACKEND: &str = "org.safepkt.backend";
+pub static LABEL_BACKEND_OPTIONS: &str = "org.safepkt.backend_options";
+
+static DEFAULT_INSTANCE: &str = "safepkt-backend";
+
+/// Get the name of the backend instance managing containers,
+/// declared as the value of the BACKEND_INSTANCE environment variable
+/// so that backends sharing a Docker daemon leave each other's containers alone.
+pub fn get_backend_instance() -> String {
+    env::var("BACKEND_INSTANCE")
+        .ok()
+        .filter(|instance| !instance.is_empty())
+        .unwrap_or_else(|| DEFAULT_INSTANCE.to_string())
+}
+
+/// Labels carried by the container of a step
+pub fn get_labels(project_step: &StepInVerificationPlan<'_>) -> HashMap<String, String> {
+    let step_name = project_step.step().name();
+    let project_id = project_step.project_id();
+
+    let mut labels = HashMap::new();
+    labels.insert(LABEL_STEP.to_string(), step_name.to_string());
+    labels.insert(LABEL_PROJECT_ID.to_string(), project_id.to_string());
+    labels.insert(
+        LABEL_RUN_ID.to_string(),
+        format_run_id(step_name, project_id),
+    );
+    labels.insert(LABEL_INSTANCE.to_string(), get_backend_instance());
+
+    if let Some(backend) = project_step.backend() {
+        labels.insert(
+            LABEL_BACKEND.to_string(),
+            backend.backend().as_str().to_string(),
+        );
+        labels.insert(LABEL_BACKEND_OPTIONS.to_string(), backend.format_options());
+    }
+
+    labels
+}
+
+/// Backend a step has been run with (and its options),
+/// as recorded in the labels of its container
+pub fn get_backend_metadata(labels: &HashMap<String, String>) -> HashMap<String, String> {
+    [
+        (LABEL_BACKEND, "backend"),
+        (LABEL_BACKEND_OPTIONS, "backend_options"),
+    ]
+    .iter()
+    .filter_map(|(label, key)| Some((key.to_string(), labels.get(*label)?.clone())))
+    .collect()
+}
+
+/// Filters matching the containers managed by this backend instance
+pub fn instance_filters() -> HashMap<String, Vec<String>> {
+    let mut filters = HashMap::new();
+    filters.insert(
+        String::from("label"),
+        vec![format!("{}={}", LABEL_INSTANCE, get_backend_instance())],
+    );
+
+    filters
+}
+
+/// Filters matching exactly the container of a run
+/// managed by this backend instance
+pub fn run_filters(run_id: &str) -> HashMap<String, Vec<String>> {
+    let mut filters = HashMap::new();
+    filters.insert(
+        String::from("label"),
+        vec![
+            format!("{}={}", LABEL_RUN_ID, run_id),
+            format!("{}={}", LABEL_INSTANCE, get_backend_instance()),
+        ],
+    );
+
+    filters
+}
+
+#[test]
+fn it_labels_containers_of_steps() {
+    use crate::infra::step_registry::step_registry;
+    use crate::infra::PROGRAM_VERIFICATION;
+
+    let step = Step::new(step_registry().unwrap().step(PROGRAM_VERIFICATION).unwrap());
+    let project_step = StepInVerificationPlan::new(String::from("47a9690570"), step);
+
+    let labels = get_labels(&project_step);
+    assert_eq!("program_verification", labels[LABEL_STEP]);
+    assert_eq!("47a9690570", labels[LABEL_PROJECT_ID]);
+    assert_eq!("program_verification-47a9690570", labels[LABEL_RUN_ID]);
+    assert_eq!(get_backend_instance(), labels[LABEL_INSTANCE]);
+    assert_eq!("klee", labels[LABEL_BACKEND]);
+    assert_eq!("", labels[LABEL_BACKEND_OPTIONS]);
+
+    let options = VerificationOptions {
+        cases: Some(20),
+        ..Default::default()
+    };
+    let step = Step::new(step_registry().unwrap().step(PROGRAM_VERIFICATION).unwrap());
+    let project_step = StepInVerificationPlan::new(String::from("47a9690570"), step)
+        .with_backend(Some(BackendSelection::new("proptest", &options).unwrap()));
+
+    let metadata = get_backend_metadata(&get_labels(&project_step));
+    assert_eq!("proptest", metadata["backend"]);
+    assert_eq!("cases=20", metadata["backend_options"]);
+    assert!(get_backend_metadata(&HashMap::new()).is_empty());
+
+    let filters = run_filters("program_verification-4");
+    assert_eq!(
+        vec![
+            "org.safepkt.run_id=program_verification-4".to_string(),
+            format!("org.safepkt.instance={}", get_backend_instance()),
+        ],
+        filters["label"]
+    );
+    assert_eq!(
+        vec![format!("org.safepkt.instance={}", get_backend_instance())],
+        instance_filters()["label"]
+    );
+}
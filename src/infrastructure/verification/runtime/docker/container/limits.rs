@@ -0,0 +1,202 @@
+use crate::domain::value_object::{StepDefinition, StepLimits};
+use anyhow::Result;
+use bollard::models::HostConfig;
+use color_eyre::{eyre::eyre, Report};
+use std::collections::HashMap;
+use std::env;
+
+static CPU_PERIOD: i64 = 100_000;
+static TMPFS_TARGET: &str = "/tmp";
+
+/// Resources a step container is allowed to consume
+/// (memory, CPUs, processes, tmpfs mounted at /tmp and writable layer),
+/// the limits of a step taking precedence over the limits of all steps.
+#[derive(Debug, Default, PartialEq)]
+pub struct ResourceLimits {
+    pub memory: Option<i64>,
+    pub cpus: Option<f64>,
+    pub pids: Option<i64>,
+    pub tmpfs_size: Option<i64>,
+    pub disk_size: Option<i64>,
+}
+
+/// Parse a size expressed in bytes with an optional unit (k, m, g or t).
+///
+/// # Examples
+///
+/// ```
+/// use safepkt_backend::infra::verification_runtime::docker::container;
+///
+/// assert_eq!(Some(512 * 1024 * 1024), container::parse_size("512m"));
+/// assert_eq!(Some(4 * 1024 * 1024 * 1024), container::parse_size("4G"));
+/// assert_eq!(Some(1024), container::parse_size("1024"));
+/// assert_eq!(None, container::parse_size("a lot"));
+/// ```
+///
+pub fn parse_size(size: &str) -> Option<i64> {
+    let size = size.trim().to_lowercase();
+    let size = size.strip_suffix('b').unwrap_or(size.as_str());
+
+    let (amount, multiplier) = match size.chars().last()? {
+        'k' => (&size[..size.len() - 1], 1 << 10),
+        'm' => (&size[..size.len() - 1], 1 << 20),
+        'g' => (&size[..size.len() - 1], 1 << 30),
+        't' => (&size[..size.len() - 1], 1 << 40),
+        _ => (size, 1),
+    };
+
+    amount
+        .trim()
+        .parse::<i64>()
+        .ok()
+        .and_then(|amount| amount.checked_mul(multiplier))
+}
+
+fn lookup_limit<L: Fn(&str) -> Option<String>>(
+    lookup: &L,
+    name: &str,
+    step_name: &str,
+) -> Option<(String, String)> {
+    let step_variable = format!("{}_{}", name, step_name.to_uppercase());
+
+    lookup(step_variable.as_str())
+        .map(|value| (step_variable, value))
+        .or_else(|| lookup(name).map(|value| (name.to_string(), value)))
+}
+
+fn parse_limit<L, T, P>(
+    lookup: &L,
+    name: &str,
+    step_name: &str,
+    parse: P,
+) -> Result<Option<T>, Report>
+where
+    L: Fn(&str) -> Option<String>,
+    P: Fn(&str) -> Option<T>,
+{
+    lookup_limit(lookup, name, step_name)
+        .map(|(variable, value)| {
+            parse(value.as_str())
+                .ok_or_else(|| eyre!("Invalid value \"{}\" for {}", value, variable))
+        })
+        .transpose()
+}
+
+/// Get the limit of a step registry matching a variable (e.g. memory for CONTAINER_MEMORY_LIMIT)
+fn registry_limit<'a>(limits: &'a StepLimits, name: &str) -> Option<&'a String> {
+    match name {
+        "CONTAINER_MEMORY_LIMIT" => limits.memory.as_ref(),
+        "CONTAINER_CPU_LIMIT" => limits.cpus.as_ref(),
+        "CONTAINER_PIDS_LIMIT" => limits.pids.as_ref(),
+        "CONTAINER_TMPFS_SIZE" => limits.tmpfs_size.as_ref(),
+        "CONTAINER_DISK_LIMIT" => limits.disk_size.as_ref(),
+        _ => None,
+    }
+}
+
+impl ResourceLimits {
+    pub fn for_step(definition: &StepDefinition) -> Result<Self, Report> {
+        Self::from_definition_lookup(definition, |name| {
+            env::var(name).ok().filter(|value| !value.is_empty())
+        })
+    }
+
+    /// Read limits declared for a step by its definition in the step registry,
+    /// unless they are overridden by variables suffixed with its name.
+    pub fn from_definition_lookup<L: Fn(&str) -> Option<String>>(
+        definition: &StepDefinition,
+        lookup: L,
+    ) -> Result<Self, Report> {
+        let step_suffix = format!("_{}", definition.name().to_uppercase());
+
+        Self::from_lookup(definition.name(), |name| {
+            lookup(name).or_else(|| {
+                name.strip_suffix(step_suffix.as_str())
+                    .and_then(|name| registry_limit(definition.limits(), name))
+                    .cloned()
+            })
+        })
+    }
+
+    pub fn from_lookup<L: Fn(&str) -> Option<String>>(
+        step_name: &str,
+        lookup: L,
+    ) -> Result<Self, Report> {
+        Ok(ResourceLimits {
+            memory: parse_limit(&lookup, "CONTAINER_MEMORY_LIMIT", step_name, parse_size)?,
+            cpus: parse_limit(&lookup, "CONTAINER_CPU_LIMIT", step_name, |cpus| {
+                cpus.trim().parse::<f64>().ok().filter(|cpus| *cpus > 0.0)
+            })?,
+            pids: parse_limit(&lookup, "CONTAINER_PIDS_LIMIT", step_name, |pids| {
+                pids.trim().parse::<i64>().ok().filter(|pids| *pids > 0)
+            })?,
+            tmpfs_size: parse_limit(&lookup, "CONTAINER_TMPFS_SIZE", step_name, parse_size)?,
+            disk_size: parse_limit(&lookup, "CONTAINER_DISK_LIMIT", step_name, parse_size)?,
+        })
+    }
+
+    /// Apply limits to the host configuration of a container
+    pub fn apply(&self, host_config: &mut HostConfig) {
+        if let Some(memory) = self.memory {
+            host_config.memory = Some(memory);
+            // Prevent containers from swapping beyond their memory limit
+            host_config.memory_swap = Some(memory);
+        }
+
+        if let Some(cpus) = self.cpus {
+            host_config.cpu_period = Some(CPU_PERIOD);
+            host_config.cpu_quota = Some((cpus * CPU_PERIOD as f64) as i64);
+        }
+
+        host_config.pids_limit = self.pids;
+
+        if let Some(tmpfs_size) = self.tmpfs_size {
+            let mut tmpfs = host_config.tmpfs.take().unwrap_or_default();
+            tmpfs.insert(TMPFS_TARGET.to_string(), format!("size={}", tmpfs_size));
+            host_config.tmpfs = Some(tmpfs);
+        }
+
+        if let Some(disk_size) = self.disk_size {
+            let mut storage_options = HashMap::new();
+            storage_options.insert("size".to_string(), disk_size.to_string());
+            host_config.storage_opt = Some(storage_options);
+        }
+    }
+}
+
+#[test]
+fn it_applies_resource_limits_overridden_per_step() {
+    let limits = ResourceLimits::from_lookup("program_verification", |name| match name {
+        "CONTAINER_MEMORY_LIMIT" => Some("2g".to_string()),
+        "CONTAINER_MEMORY_LIMIT_PROGRAM_VERIFICATION" => Some("4g".to_string()),
+        "CONTAINER_CPU_LIMIT" => Some("1.5".to_string()),
+        "CONTAINER_PIDS_LIMIT" => Some("256".to_string()),
+        "CONTAINER_TMPFS_SIZE" => Some("512m".to_string()),
+        _ => None,
+    })
+    .unwrap();
+
+    let mut host_config = HostConfig::default();
+    limits.apply(&mut host_config);
+
+    assert_eq!(Some(4 << 30), host_config.memory);
+    assert_eq!(Some(4 << 30), host_config.memory_swap);
+    assert_eq!(Some(150_000), host_config.cpu_quota);
+    assert_eq!(Some(100_000), host_config.cpu_period);
+    assert_eq!(Some(256), host_config.pids_limit);
+    assert_eq!("size=536870912", host_config.tmpfs.unwrap()[TMPFS_TARGET]);
+    assert_eq!(None, host_config.storage_opt);
+
+    let unlimited = ResourceLimits::from_lookup("program_fuzzing", |_| None).unwrap();
+    assert_eq!(ResourceLimits::default(), unlimited);
+
+    assert_eq!(
+        "Invalid value \"lots\" for CONTAINER_PIDS_LIMIT_PROGRAM_FUZZING",
+        ResourceLimits::from_lookup("program_fuzzing", |name| match name {
+            "CONTAINER_PIDS_LIMIT_PROGRAM_FUZZING" => Some("lots".to_string()),
+            _ => None,
+        })
+        .unwrap_err()
+        .to_string()
+    );
+}
@@ -0,0 +1,101 @@
+use anyhow::Result;
+use bollard::models::HostConfig;
+use color_eyre::{eyre::eyre, Report};
+use std::env;
+
+static NETWORK_NONE: &str = "none";
+static NETWORK_HOST: &str = "host";
+
+/// Network a step container is attached to.
+///
+/// Containers have no network by default, in which case cargo runs offline
+/// and dependencies are to be available in the container image
+/// (or in the CARGO_HOME of a project).
+///
+/// A step can opt in for a network restricted by the operator
+/// (e.g. a Docker network only reaching a crates registry mirror),
+/// the network of a step taking precedence over the network of all steps.
+#[derive(Debug, PartialEq)]
+pub struct NetworkPolicy {
+    network: Option<String>,
+}
+
+impl NetworkPolicy {
+    pub fn for_step(step_name: &str) -> Result<Self, Report> {
+        Self::from_lookup(step_name, |name| {
+            env::var(name).ok().filter(|value| !value.is_empty())
+        })
+    }
+
+    pub fn from_lookup<L: Fn(&str) -> Option<String>>(
+        step_name: &str,
+        lookup: L,
+    ) -> Result<Self, Report> {
+        let step_variable = format!("CONTAINER_NETWORK_{}", step_name.to_uppercase());
+        let network = lookup(step_variable.as_str())
+            .or_else(|| lookup("CONTAINER_NETWORK"))
+            .filter(|network| network != NETWORK_NONE);
+
+        if network.as_deref() == Some(NETWORK_HOST) {
+            return Err(eyre!(
+                "Verification containers can not share the network of the host (step \"{}\")",
+                step_name
+            ));
+        }
+
+        Ok(NetworkPolicy { network })
+    }
+
+    pub fn is_offline(&self) -> bool {
+        self.network.is_none()
+    }
+
+    pub fn network_mode(&self) -> &str {
+        self.network.as_deref().unwrap_or(NETWORK_NONE)
+    }
+
+    /// Variables to be declared in the environment of a container
+    pub fn environment(&self) -> Vec<String> {
+        if self.is_offline() {
+            return vec![String::from("CARGO_NET_OFFLINE=true")];
+        }
+
+        vec![]
+    }
+
+    pub fn apply(&self, host_config: &mut HostConfig) {
+        host_config.network_mode = Some(self.network_mode().to_string());
+    }
+}
+
+#[test]
+fn it_isolates_containers_from_the_network_by_default() {
+    let policy = NetworkPolicy::from_lookup("program_verification", |_| None).unwrap();
+
+    let mut host_config = HostConfig::default();
+    policy.apply(&mut host_config);
+
+    assert!(policy.is_offline());
+    assert_eq!(Some("none".to_string()), host_config.network_mode);
+    assert_eq!(vec!["CARGO_NET_OFFLINE=true"], policy.environment());
+}
+
+#[test]
+fn it_attaches_containers_of_opted_in_steps_to_a_restricted_network() {
+    let lookup = |name: &str| match name {
+        "CONTAINER_NETWORK_PROGRAM_FUZZING" => Some("safepkt-registry-mirror".to_string()),
+        _ => None,
+    };
+
+    let fuzzing = NetworkPolicy::from_lookup("program_fuzzing", lookup).unwrap();
+    assert!(!fuzzing.is_offline());
+    assert_eq!("safepkt-registry-mirror", fuzzing.network_mode());
+    assert!(fuzzing.environment().is_empty());
+
+    let verification = NetworkPolicy::from_lookup("program_verification", lookup).unwrap();
+    assert!(verification.is_offline());
+
+    assert!(
+        NetworkPolicy::from_lookup("program_verification", |_| Some("host".to_string())).is_err()
+    );
+}
@@ -1,30 +1,33 @@
 use crate::infrastructure as infra;
 use anyhow::Result;
 use bollard::container::RemoveContainerOptions;
+use bollard::errors::Error;
 use bollard::Docker;
 use color_eyre::Report;
-use infra::verification::runtime::docker::{container::container_exists, DockerContainerAPIClient};
+use infra::verification::runtime::docker::{container::find_container, DockerContainerAPIClient};
 
 pub async fn remove_existing_container(
     container_api_client: &DockerContainerAPIClient<Docker>,
     container_name: String,
 ) -> Result<(), Report> {
-    let existing_container = container_exists(container_api_client, container_name.as_str())
-        .await
-        .unwrap();
+    let options = Some(RemoveContainerOptions {
+        force: true,
+        ..Default::default()
+    });
 
-    if existing_container {
-        let options = Some(RemoveContainerOptions {
-            force: true,
-            ..Default::default()
-        });
+    // Containers started before being labeled are found by their exact name
+    let container_id = find_container(container_api_client, container_name.as_str())
+        .await?
+        .and_then(|container| container.id)
+        .unwrap_or(container_name);
 
-        container_api_client
-            .client()
-            .remove_container(container_name.as_str(), options)
-            .await
-            .unwrap();
+    match container_api_client
+        .client()
+        .remove_container(container_id.as_str(), options)
+        .await
+    {
+        Ok(_) => Ok(()),
+        Err(Error::DockerResponseNotFoundError { .. }) => Ok(()),
+        Err(error) => Err(error.into()),
     }
-
-    Ok(())
 }
@@ -0,0 +1,65 @@
+use bollard::models::HostConfig;
+
+static TMPFS_TARGET: &str = "/tmp";
+static TMPFS_OPTIONS: &str = "rw,exec,nosuid,nodev";
+
+/// Security profile of step containers, which
+///  - run as the system user declared in UID_GID,
+///  - have all capabilities dropped,
+///  - can not gain new privileges,
+///  - have a read-only root file system,
+///    only the project directory and a tmpfs mounted at /tmp being writable.
+pub struct SecurityProfile<'a> {
+    uid_gid: &'a str,
+}
+
+impl<'a> SecurityProfile<'a> {
+    pub fn new(uid_gid: &'a str) -> SecurityProfile<'a> {
+        SecurityProfile { uid_gid }
+    }
+
+    pub fn user(&self) -> &'a str {
+        self.uid_gid
+    }
+
+    pub fn apply(&self, host_config: &mut HostConfig) {
+        host_config.cap_drop = Some(vec![String::from("ALL")]);
+        host_config.security_opt = Some(vec![String::from("no-new-privileges")]);
+        host_config.readonly_rootfs = Some(true);
+
+        // Preserve the size of the tmpfs when limited
+        let mut tmpfs = host_config.tmpfs.take().unwrap_or_default();
+        tmpfs
+            .entry(TMPFS_TARGET.to_string())
+            .and_modify(|options| *options = format!("{},{}", TMPFS_OPTIONS, options))
+            .or_insert_with(|| TMPFS_OPTIONS.to_string());
+        host_config.tmpfs = Some(tmpfs);
+    }
+}
+
+#[test]
+fn it_hardens_the_host_configuration_of_containers() {
+    use std::collections::HashMap;
+
+    let mut tmpfs = HashMap::new();
+    tmpfs.insert(TMPFS_TARGET.to_string(), "size=1024".to_string());
+    let mut host_config = HostConfig {
+        tmpfs: Some(tmpfs),
+        ..Default::default()
+    };
+
+    let profile = SecurityProfile::new("1000:1000");
+    profile.apply(&mut host_config);
+
+    assert_eq!("1000:1000", profile.user());
+    assert_eq!(Some(vec!["ALL".to_string()]), host_config.cap_drop);
+    assert_eq!(
+        Some(vec!["no-new-privileges".to_string()]),
+        host_config.security_opt
+    );
+    assert_eq!(Some(true), host_config.readonly_rootfs);
+    assert_eq!(
+        "rw,exec,nosuid,nodev,size=1024",
+        host_config.tmpfs.unwrap()[TMPFS_TARGET]
+    );
+}
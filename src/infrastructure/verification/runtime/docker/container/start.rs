@@ -3,36 +3,48 @@ use crate::infra;
 use anyhow::Result;
 use bollard::container::{Config, CreateContainerOptions};
 use bollard::{models::*, Docker};
-use color_eyre::Report;
+use color_eyre::{eyre::eyre, Report};
+use infra::build_cache::{BuildCache, BUILD_CACHE_VARIABLE};
 use infra::display;
-use infra::scaffold::{format_directory_path_to_scaffold, format_project_name};
+use infra::scaffold::{
+    find_project_settings, format_directory_path_to_scaffold, format_project_name,
+};
+use infra::time_budget::get_klee_max_time;
+use infra::verification_runtime::docker::container::labels::get_labels;
+use infra::verification_runtime::docker::container::{
+    find_container, NetworkPolicy, ResourceLimits, SecurityProfile,
+};
 use infra::verification_runtime::docker::DockerContainerAPIClient;
+use std::collections::HashMap;
 use std::env;
 use std::path;
 
 pub static TARGET_RVT_DIRECTORY: &str = "/home/rust-verification-tools";
 
-static TARGET_SOURCE_DIRECTORY: &str = "/safepkt-ink/examples/source";
-static TARGET_UPLOADED_SOURCES: &str = "/uploaded-sources";
-static TARGET_VERIFICATION_SCRIPT: &str = "/usr/local/bin/verify";
-static TARGET_UPLOADED_SOURCES_LISTING_SCRIPT: &str = "/usr/local/bin/list-uploaded-sources";
+static KLEE_MAX_TIME: &str = "KLEE_MAX_TIME";
 
-fn get_uid_gid() -> Result<String, Report> {
+pub static TARGET_SOURCE_DIRECTORY: &str = "/safepkt-ink/examples/source";
+pub static TARGET_UPLOADED_SOURCES: &str = "/uploaded-sources";
+pub static TARGET_VERIFICATION_SCRIPT: &str = "/usr/local/bin/verify";
+pub static TARGET_BUILD_CACHE: &str = "/safepkt-build-cache";
+pub static TARGET_UPLOADED_SOURCES_LISTING_SCRIPT: &str = "/usr/local/bin/list-uploaded-sources";
+
+pub fn get_uid_gid() -> Result<String, Report> {
     let uid_gid = env::var("UID_GID")?;
     Ok(uid_gid)
 }
 
-fn get_rvt_directory() -> Result<String, Report> {
+pub fn get_rvt_directory() -> Result<String, Report> {
     let source_directory = env::var("RVT_DIRECTORY")?;
     Ok(source_directory)
 }
 
-fn get_verification_script_path() -> Result<String, Report> {
+pub fn get_verification_script_path() -> Result<String, Report> {
     let verification_script_path = env::var("VERIFICATION_SCRIPT")?;
     Ok(verification_script_path)
 }
 
-fn get_uploaded_sources_listing_script_path() -> Result<String, Report> {
+pub fn get_uploaded_sources_listing_script_path() -> Result<String, Report> {
     let uploaded_sources_listing_script_path = env::var("UPLOADED_SOURCES_LISTING_SCRIPT")?;
     Ok(uploaded_sources_listing_script_path)
 }
@@ -42,141 +54,289 @@ fn get_rvt_container_image() -> Result<String, Report> {
     Ok(container_image)
 }
 
-pub fn program_verification_cmd_provider() -> StepProvider {
-    |prefixed_hash: &str, bitcode: &str, additional_flags: Option<&str>| -> String {
-        match additional_flags {
-            Some(flags) => format!(
-                "/usr/local/bin/verify {} {} {} {} {}",
-                prefixed_hash, bitcode, "multisig_plain", "", flags
-            ),
-            None => format!(
-                "/usr/local/bin/verify {} {} {} {} {}",
-                prefixed_hash, bitcode, "multisig_plain", "", ""
-            ),
-        }
+fn bind_mount(target: &str, source: String, read_only: bool) -> Mount {
+    Mount {
+        target: Some(target.to_string()),
+        source: Some(source),
+        typ: Some(MountTypeEnum::BIND),
+        consistency: Some(String::from("default")),
+        read_only: Some(read_only),
+        ..Default::default()
     }
 }
 
-pub fn program_fuzzing_cmd_provider() -> StepProvider {
-    |prefixed_hash: &str, bitcode: &str, _: Option<&str>| -> String {
-        format!(
-            "/usr/local/bin/verify {} {} {} {} {}",
-            prefixed_hash, bitcode, "multisig_plain", "5", ""
-        )
-    }
+/// Targets mounted for all steps, which can not be declared in the step registry
+pub fn built_in_mount_targets() -> [&'static str; 5] {
+    [
+        TARGET_SOURCE_DIRECTORY,
+        TARGET_RVT_DIRECTORY,
+        TARGET_UPLOADED_SOURCES_LISTING_SCRIPT,
+        TARGET_VERIFICATION_SCRIPT,
+        TARGET_BUILD_CACHE,
+    ]
+}
+
+fn get_bitcode_filename(project_id: &str) -> String {
+    format!("{}.bc", project_id)
 }
 
-pub fn source_code_restoration_cmd_provider() -> StepProvider {
-    |_: &str, _: &str, _: Option<&str>| -> String {
-        // library or binary scaffolding
-        // let path_to_source = [TARGET_SOURCE_DIRECTORY, "src", "main.rs"]
-        let path_to_source = [TARGET_SOURCE_DIRECTORY, "src", "lib.rs"]
-            .join(path::MAIN_SEPARATOR.to_string().as_str());
+/// Values of the placeholders of commands declared in the step registry
+fn command_variables(
+    project_step: &StepInVerificationPlan<'_>,
+) -> Result<Vec<(&'static str, String)>, Report> {
+    let project_id = project_step.project_id();
 
-        format!("cat {}", path_to_source)
-    }
+    // Steps which do not build the project (e.g. listing uploaded sources) ignore its settings
+    let settings = if project_step.step().definition().requires_scaffolding() {
+        Some(find_project_settings(project_id.as_str())?)
+    } else {
+        None
+    };
+    let ink_template = settings
+        .as_ref()
+        .and_then(|settings| settings.ink_template());
+
+    Ok(vec![
+        ("project_id", project_id.clone()),
+        ("project_name", format_project_name(project_id.as_str())),
+        ("bitcode", get_bitcode_filename(project_id.as_str())),
+        (
+            "entry_point",
+            settings
+                .as_ref()
+                .map_or("", |settings| settings.kind().entry_point())
+                .to_string(),
+        ),
+        // Plain libraries and binaries are not scaffolded from a template
+        (
+            "ink_template",
+            ink_template
+                .map_or("", |template| template.name())
+                .to_string(),
+        ),
+        (
+            "ink_directory",
+            ink_template
+                .map_or("", |template| template.ink_directory())
+                .to_string(),
+        ),
+    ])
 }
 
-pub fn uploaded_sources_listing_cmd_provider() -> StepProvider {
-    |_: &str, _: &str, _: Option<&str>| -> String {
-        format!("{}", "/usr/local/bin/list-uploaded-sources")
+fn as_template_variables<'a>(variables: &'a [(&'static str, String)]) -> Vec<(&'a str, &'a str)> {
+    variables
+        .iter()
+        .map(|(name, value)| (*name, value.as_str()))
+        .collect()
+}
+
+/// Mount the project directory (writable),
+/// the shared tools (read-only)
+/// and the paths declared by the step in the step registry (e.g. uploaded sources).
+pub fn get_mounts(project_step: &StepInVerificationPlan<'_>) -> Result<Vec<Mount>, Report> {
+    let project_id = project_step.project_id();
+    let uploaded_source_directory = infra::file_system::get_uploaded_source_directory()?;
+    let uploaded_source = [
+        uploaded_source_directory.clone(),
+        format!(
+            "{}{}",
+            project_id,
+            infra::file_system::BASE64_ENCODED_SOURCE_EXTENSION
+        ),
+    ]
+    .join(path::MAIN_SEPARATOR.to_string().as_str());
+
+    let mut variables = command_variables(project_step)?;
+    variables.extend(vec![
+        ("source_directory", uploaded_source_directory),
+        ("uploaded_source", uploaded_source),
+        (
+            "project_directory",
+            format_directory_path_to_scaffold(project_id),
+        ),
+        ("rvt_directory", get_rvt_directory()?),
+        (
+            "advisory_database",
+            env::var("ADVISORY_DATABASE").unwrap_or_default(),
+        ),
+    ]);
+    let variables = as_template_variables(&variables);
+
+    let mut mounts = vec![
+        bind_mount(
+            TARGET_SOURCE_DIRECTORY,
+            format_directory_path_to_scaffold(project_id),
+            false,
+        ),
+        bind_mount(TARGET_RVT_DIRECTORY, get_rvt_directory()?, true),
+        bind_mount(
+            TARGET_UPLOADED_SOURCES_LISTING_SCRIPT,
+            get_uploaded_sources_listing_script_path()?,
+            true,
+        ),
+        bind_mount(
+            TARGET_VERIFICATION_SCRIPT,
+            get_verification_script_path()?,
+            true,
+        ),
+    ];
+
+    let definition = project_step.step().definition();
+    for mount in definition.mounts() {
+        let source = render_template(mount.source(), &variables);
+
+        // e.g. {advisory_database} when ADVISORY_DATABASE has not been declared
+        if source.is_empty() {
+            return Err(eyre!(
+                "Could not mount \"{}\" for step \"{}\" (its source \"{}\" is not configured)",
+                mount.target(),
+                definition.name(),
+                mount.source()
+            ));
+        }
+
+        mounts.push(bind_mount(
+            render_template(mount.target(), &variables).as_str(),
+            source,
+            mount.read_only(),
+        ));
     }
+
+    Ok(mounts)
+}
+
+/// Mount the build cache entry of a project (writable)
+fn mount_build_cache(host_config: &mut HostConfig, build_cache_entry: &path::Path) {
+    host_config
+        .mounts
+        .get_or_insert_with(Vec::new)
+        .push(bind_mount(
+            TARGET_BUILD_CACHE,
+            build_cache_entry.to_string_lossy().to_string(),
+            false,
+        ));
 }
 
 fn get_configuration<'a>(
     command_parts: Vec<&'a str>,
     container_image: &'a str,
-    project_id: &'a str,
-    uid_gid: &'a str,
+    project_step: &StepInVerificationPlan<'_>,
+    environment: Vec<&'a str>,
+    labels: &'a HashMap<String, String>,
+    network_policy: &NetworkPolicy,
+    security_profile: &SecurityProfile<'a>,
 ) -> Result<Config<&'a str>, Report> {
-    let rvt_directory = get_rvt_directory()?;
-    let verification_script_path = get_verification_script_path()?;
-    let uploaded_sources_listing_script_path = get_uploaded_sources_listing_script_path()?;
-
-    let host_config = HostConfig {
+    let mut host_config = HostConfig {
         auto_remove: Some(false),
-        mounts: Some(vec![
-            Mount {
-                target: Some(TARGET_SOURCE_DIRECTORY.to_string()),
-                source: Some(format_directory_path_to_scaffold(project_id)),
-                typ: Some(MountTypeEnum::BIND),
-                consistency: Some(String::from("default")),
-                ..Default::default()
-            },
-            Mount {
-                target: Some(TARGET_UPLOADED_SOURCES.to_string()),
-                source: Some(infra::file_system::get_uploaded_source_directory().unwrap()),
-                typ: Some(MountTypeEnum::BIND),
-                consistency: Some(String::from("default")),
-                ..Default::default()
-            },
-            Mount {
-                target: Some(TARGET_RVT_DIRECTORY.to_string()),
-                source: Some(rvt_directory),
-                typ: Some(MountTypeEnum::BIND),
-                consistency: Some(String::from("default")),
-                ..Default::default()
-            },
-            Mount {
-                target: Some(TARGET_UPLOADED_SOURCES_LISTING_SCRIPT.to_string()),
-                source: Some(uploaded_sources_listing_script_path),
-                typ: Some(MountTypeEnum::BIND),
-                consistency: Some(String::from("default")),
-                ..Default::default()
-            },
-            Mount {
-                target: Some(TARGET_VERIFICATION_SCRIPT.to_string()),
-                source: Some(verification_script_path),
-                typ: Some(MountTypeEnum::BIND),
-                consistency: Some(String::from("default")),
-                ..Default::default()
-            },
-        ]),
-        network_mode: Some(String::from("host")),
+        mounts: Some(get_mounts(project_step)?),
         ..Default::default()
     };
+    ResourceLimits::for_step(project_step.step().definition())?.apply(&mut host_config);
+    network_policy.apply(&mut host_config);
+    security_profile.apply(&mut host_config);
 
     Ok(Config {
         cmd: Some(command_parts),
-        env: Some(vec![uid_gid]),
+        env: Some(environment),
         host_config: Some(host_config),
         image: Some(container_image),
+        labels: Some(
+            labels
+                .iter()
+                .map(|(name, value)| (name.as_str(), value.as_str()))
+                .collect(),
+        ),
+        user: Some(security_profile.user()),
         working_dir: Some(TARGET_SOURCE_DIRECTORY),
         ..Default::default()
     })
 }
 
-fn get_bitcode_filename(project_id: &str) -> String {
-    format!("{}.bc", project_id)
+/// Build the command of a step from its definition in the step registry,
+/// verification options being declared as environment variables
+/// so that they are never split nor interpreted.
+pub fn step_command(project_step: &StepInVerificationPlan<'_>) -> Result<StepCommand, Report> {
+    let variables = command_variables(project_step)?;
+
+    let mut command = project_step
+        .step()
+        .definition()
+        .command(&as_template_variables(&variables));
+
+    let mut klee_max_time = get_klee_max_time(project_step.time_budget());
+
+    // The backend of `cargo verify` and its options are declared to the verification script
+    if let Some(backend) = project_step.backend() {
+        for (name, value) in backend.environment() {
+            // The time budget of the step caps the time requested for KLEE
+            if name == KLEE_MAX_TIME {
+                let requested = value.parse::<u64>().ok();
+                klee_max_time = klee_max_time.min(requested).or(klee_max_time.or(requested));
+                continue;
+            }
+
+            command = command.env(name.as_str(), value.as_str());
+        }
+    }
+
+    if let Some(klee_max_time) = klee_max_time {
+        command = command.env(KLEE_MAX_TIME, klee_max_time.to_string().as_str());
+    }
+
+    Ok(command)
+}
+
+/// Get the image a step is run from (RVT_DOCKER_IMAGE unless declared in the step registry)
+fn get_step_image(project_step: &StepInVerificationPlan<'_>) -> Result<String, Report> {
+    match project_step.step().definition().image() {
+        Some(image) => Ok(image.to_string()),
+        None => get_rvt_container_image(),
+    }
 }
 
 pub async fn start_container(
     container_api_client: &DockerContainerAPIClient<Docker>,
     container_name: String,
     project_step: &StepInVerificationPlan<'_>,
-) -> Result<(), Report> {
-    let project_id = project_step.project_id().clone();
-    let step = project_step.step;
-
-    let container_image = get_rvt_container_image()?;
-    let prefixed_hash = format_project_name(project_id.as_str());
-    let prefixed_hash = prefixed_hash.as_str();
+) -> Result<HashMap<String, String>, Report> {
+    let container_image = get_step_image(project_step)?;
 
-    let bitcode_file_name = get_bitcode_filename(project_id.as_str());
-    let bitcode_file_name = bitcode_file_name.as_str();
+    let command = step_command(project_step)?;
+    let argv = command.argv();
 
-    let command: String = step.step_provider()(prefixed_hash, bitcode_file_name, step.flags());
-    let command = command.as_str();
-    let command_parts = command.split(" ").collect::<Vec<&str>>();
+    let uid_gid = get_uid_gid()?;
+    let mut environment = vec![format!("UID_GID={}", uid_gid)];
+    environment.extend(command.env_vars());
+    let network_policy = NetworkPolicy::for_step(project_step.step().name())?;
+    environment.extend(network_policy.environment());
+    let labels = get_labels(project_step);
 
-    let uid_gid = format!("UID_GID={}", get_uid_gid()?);
+    // Only the verification script builds projects
+    let build_cache_entry = match BuildCache::from_env()? {
+        Some(build_cache) if command.program() == TARGET_VERIFICATION_SCRIPT => {
+            Some(build_cache.prepare(project_step.project_id())?)
+        }
+        _ => None,
+    };
+    if build_cache_entry.is_some() {
+        environment.push(format!("{}={}", BUILD_CACHE_VARIABLE, TARGET_BUILD_CACHE));
+    }
 
-    let configuration = get_configuration(
-        command_parts,
+    let mut configuration = get_configuration(
+        argv.iter().map(String::as_str).collect(),
         container_image.as_str(),
-        project_id.as_str(),
-        uid_gid.as_str(),
+        project_step,
+        environment.iter().map(String::as_str).collect(),
+        &labels,
+        &network_policy,
+        &SecurityProfile::new(uid_gid.as_str()),
     )?;
+    if let (Some(build_cache_entry), Some(host_config)) = (
+        build_cache_entry.as_ref(),
+        configuration.host_config.as_mut(),
+    ) {
+        mount_build_cache(host_config, build_cache_entry.path());
+    }
 
     display::output::print(
         "About to start container with name {} based on image {}",
@@ -200,7 +360,34 @@ pub async fn start_container(
         .start_container::<String>(&id, None)
         .await?;
 
-    Ok(())
+    // The step locks its build cache entry itself once started
+    drop(build_cache_entry);
+
+    let mut message = HashMap::<String, String>::new();
+    message.insert("container_id".to_string(), id);
+
+    Ok(message)
+}
+
+/// Inspect the image a step is run from,
+/// which digest identifies the toolchain it is run with.
+pub async fn inspect_image(
+    container_api_client: &DockerContainerAPIClient<Docker>,
+    project_step: &StepInVerificationPlan<'_>,
+) -> Result<HashMap<String, String>, Report> {
+    let container_image = get_step_image(project_step)?;
+
+    let image_digest = container_api_client
+        .client()
+        .inspect_image(container_image.as_str())
+        .await?
+        .id;
+
+    let mut message = HashMap::<String, String>::new();
+    message.insert("image".to_string(), container_image);
+    message.insert("image_digest".to_string(), image_digest);
+
+    Ok(message)
 }
 
 pub async fn stop_container(
@@ -216,10 +403,222 @@ pub async fn stop_container(
         None,
     );
 
+    let container_id = find_container(container_api_client, container_name.as_str())
+        .await?
+        .and_then(|container| container.id)
+        .ok_or_else(|| eyre!("There is no container having name \"{}\"", container_name))?;
+
     container_api_client
         .client()
-        .stop_container(&container_name.as_str(), None)
+        .stop_container(container_id.as_str(), None)
         .await?;
 
     Ok(())
 }
+
+#[test]
+fn it_mounts_only_the_sources_of_the_current_project() {
+    use crate::infra::step_registry::step_registry;
+    use crate::infra::{PROGRAM_VERIFICATION, UPLOADED_SOURCES_LISTING};
+    use crate::test;
+
+    test::configure_env();
+
+    let project_id = test::upload_source();
+    let mounts = |step_name: &str| {
+        let step = Step::new(step_registry().unwrap().step(step_name).unwrap());
+        get_mounts(&StepInVerificationPlan::new(project_id.clone(), step)).unwrap()
+    };
+    let uploaded_sources = |mounts: &[Mount]| {
+        mounts
+            .iter()
+            .find(|mount| {
+                mount
+                    .target
+                    .as_ref()
+                    .is_some_and(|target| target.starts_with(TARGET_UPLOADED_SOURCES))
+            })
+            .cloned()
+            .unwrap()
+    };
+
+    let verification_mounts = mounts(PROGRAM_VERIFICATION);
+    let uploaded_source = uploaded_sources(&verification_mounts);
+    assert_eq!(
+        Some(format!("/uploaded-sources/{}.rs.b64", project_id)),
+        uploaded_source.target
+    );
+    assert_eq!(
+        Some(
+            test::uploaded_file_path(
+                project_id.as_str(),
+                crate::infra::file_system::BASE64_ENCODED_SOURCE_EXTENSION,
+            )
+            .display()
+            .to_string()
+        ),
+        uploaded_source.source
+    );
+
+    let writable_mounts: Vec<&Mount> = verification_mounts
+        .iter()
+        .filter(|mount| mount.read_only != Some(true))
+        .collect();
+    assert_eq!(1, writable_mounts.len());
+    assert_eq!(
+        Some(TARGET_SOURCE_DIRECTORY.to_string()),
+        writable_mounts[0].target
+    );
+
+    let uploaded_sources = uploaded_sources(&mounts(UPLOADED_SOURCES_LISTING));
+    assert_eq!(Some("/tmp".to_string()), uploaded_sources.source);
+    assert_eq!(Some(true), uploaded_sources.read_only);
+
+    test::remove_project(project_id.as_str());
+}
+
+#[test]
+fn it_builds_the_command_of_each_step() {
+    use crate::infra::scaffold;
+    use crate::infra::step_registry::step_registry;
+    use crate::infra::{
+        PROGRAM_FUZZING, PROGRAM_VERIFICATION, SOURCE_RESTORATION, UPLOADED_SOURCES_LISTING,
+    };
+    use crate::test;
+
+    let project_id = test::upload_source();
+    scaffold::select_project_settings(project_id.as_str(), Some("ink_contract"), None, None)
+        .unwrap();
+
+    let command = |name: &'static str, options: Option<VerificationOptions>| {
+        let step = Step::new(step_registry().unwrap().step(name).unwrap());
+        let backend = options.map(|options| {
+            BackendSelection::new(step.definition().backend().unwrap(), &options).unwrap()
+        });
+
+        step_command(&StepInVerificationPlan::new(project_id.clone(), step).with_backend(backend))
+            .unwrap()
+    };
+
+    let verification = command(
+        PROGRAM_VERIFICATION,
+        Some(VerificationOptions {
+            quiet: Some(true),
+            test: Some("tests::safe_transfer".to_string()),
+            ..Default::default()
+        }),
+    );
+    assert_eq!(
+        vec![
+            TARGET_VERIFICATION_SCRIPT.to_string(),
+            format!("safepkt_{}", project_id),
+            format!("{}.bc", project_id),
+            "multisig_plain".to_string()
+        ],
+        verification.argv()
+    );
+    assert_eq!(
+        vec![
+            "INK_DIRECTORY=/safepkt-ink",
+            "VERIFY_BACKEND=klee",
+            "VERIFY_QUIET=true",
+            "VERIFY_TEST=tests::safe_transfer"
+        ],
+        verification.env_vars()
+    );
+
+    let verification = command(PROGRAM_VERIFICATION, None);
+    assert_eq!(
+        vec!["INK_DIRECTORY=/safepkt-ink", "VERIFY_BACKEND=klee"],
+        verification.env_vars()
+    );
+
+    let fuzzing = command(PROGRAM_FUZZING, None);
+    assert_eq!(verification.argv(), fuzzing.argv());
+    assert_eq!(
+        vec![
+            "INK_DIRECTORY=/safepkt-ink",
+            "VERIFY_BACKEND=proptest",
+            "PROPTEST_CASES=5"
+        ],
+        fuzzing.env_vars()
+    );
+
+    let step = Step::new(step_registry().unwrap().step(PROGRAM_FUZZING).unwrap());
+    let options = VerificationOptions {
+        search: Some("bfs".to_string()),
+        max_time: Some(600),
+        ..Default::default()
+    };
+    let klee = step_command(
+        &StepInVerificationPlan::new(project_id.clone(), step)
+            .with_backend(Some(BackendSelection::new("klee", &options).unwrap()))
+            .with_time_budget(None),
+    )
+    .unwrap();
+    assert_eq!(
+        vec![
+            "INK_DIRECTORY=/safepkt-ink",
+            "VERIFY_BACKEND=klee",
+            "KLEE_SEARCH=bfs",
+            "KLEE_MAX_TIME=600"
+        ],
+        klee.env_vars()
+    );
+
+    let restoration = command(SOURCE_RESTORATION, None);
+    assert!(restoration.environment().is_empty());
+    assert_eq!(
+        vec!["cat", "/safepkt-ink/examples/source/src/lib.rs"],
+        restoration.argv()
+    );
+
+    let listing = command(UPLOADED_SOURCES_LISTING, None);
+    assert_eq!(vec![TARGET_UPLOADED_SOURCES_LISTING_SCRIPT], listing.argv());
+    test::remove_project(project_id.as_str());
+
+    // Projects are verified against the ink! template selected when uploading their source
+    let project_id = test::upload_source();
+    scaffold::select_project_settings(
+        project_id.as_str(),
+        None,
+        Some("3.0.0-rc4"),
+        Some("multisig"),
+    )
+    .unwrap();
+    let step = Step::new(step_registry().unwrap().step(PROGRAM_VERIFICATION).unwrap());
+    let ink_3 = step_command(&StepInVerificationPlan::new(project_id.clone(), step)).unwrap();
+    assert_eq!(Some("multisig"), ink_3.argv().last().map(String::as_str));
+    assert_eq!(
+        vec![
+            "INK_DIRECTORY=/safepkt-ink-3.0.0-rc4",
+            "VERIFY_BACKEND=klee"
+        ],
+        ink_3.env_vars()
+    );
+    test::remove_project(project_id.as_str());
+
+    // Settings of unknown projects are never made up
+    let step = Step::new(step_registry().unwrap().step(PROGRAM_VERIFICATION).unwrap());
+    assert!(step_command(&StepInVerificationPlan::new(project_id, step)).is_err());
+}
+
+#[test]
+fn it_requires_the_sources_of_mounts_declared_by_steps() {
+    use crate::infra::step_registry::step_registry;
+    use crate::infra::DEPENDENCY_AUDIT;
+    use crate::test;
+
+    test::configure_env();
+
+    let project_id = test::upload_source();
+    let step = Step::new(step_registry().unwrap().step(DEPENDENCY_AUDIT).unwrap());
+    let project_step = StepInVerificationPlan::new(project_id.clone(), step);
+
+    assert_eq!(
+        "Could not mount \"/advisory-db\" for step \"dependency_audit\" (its source \"{advisory_database}\" is not configured)",
+        get_mounts(&project_step).unwrap_err().to_string()
+    );
+
+    test::remove_project(project_id.as_str());
+}
@@ -0,0 +1,342 @@
+use crate::domain::value_object::*;
+use crate::domain::verification_runtime::*;
+use crate::infrastructure as infra;
+use anyhow::Result;
+use async_trait::async_trait;
+use color_eyre::{eyre::eyre, Report};
+use infra::program_verification::format_run_id;
+use infra::verification::{ContainerAPIClientFactory, SharedContainerAPIClient};
+use infra::verification_runtime::slice_logs_since;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+static DEFAULT_STATUSES: [&str; 2] = ["running", "exited"];
+static DEFAULT_IMAGE_DIGEST: &str = "sha256:in-memory";
+
+#[derive(Clone, Default)]
+struct Script {
+    statuses: Vec<String>,
+    logs: String,
+    start_error: Option<String>,
+    started_at: Option<u64>,
+    finished_at: Option<u64>,
+    exit_code: Option<i64>,
+}
+
+struct Container {
+    statuses: VecDeque<String>,
+    logs: String,
+    started_at: Option<u64>,
+    finished_at: Option<u64>,
+    exit_code: i64,
+}
+
+#[derive(Default)]
+struct State {
+    scripts: HashMap<String, Script>,
+    containers: HashMap<String, Container>,
+    image_digest: Option<String>,
+    calls: Vec<String>,
+}
+
+/// Container API client keeping containers in memory,
+/// which statuses and logs can be scripted per run
+/// to exercise runtimes and controllers without any daemon.
+///
+/// Clones share their state so that a test can keep a handle
+/// on a client handed over to a runtime.
+#[derive(Clone, Default)]
+pub struct InMemoryContainerAPIClient {
+    state: Arc<Mutex<State>>,
+}
+
+impl InMemoryContainerAPIClient {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Script the successive statuses reported when inspecting the container of a run.
+    /// The last status is reported indefinitely once the previous ones have been consumed.
+    pub fn script_statuses(&self, run_id: &str, statuses: Vec<&str>) -> &Self {
+        let mut state = self.state.lock().unwrap();
+        state
+            .scripts
+            .entry(run_id.to_string())
+            .or_default()
+            .statuses = statuses.into_iter().map(String::from).collect();
+
+        self
+    }
+
+    /// Script the logs tailed from the container of a run.
+    pub fn script_logs(&self, run_id: &str, logs: &str) -> &Self {
+        let mut state = self.state.lock().unwrap();
+        state.scripts.entry(run_id.to_string()).or_default().logs = logs.to_string();
+
+        self
+    }
+
+    /// Script an error to be returned when starting the container of a run.
+    pub fn script_start_error(&self, run_id: &str, error: &str) -> &Self {
+        let mut state = self.state.lock().unwrap();
+        state
+            .scripts
+            .entry(run_id.to_string())
+            .or_default()
+            .start_error = Some(error.to_string());
+
+        self
+    }
+
+    /// Script the time (seconds since the Unix epoch)
+    /// reported as the start of the run.
+    pub fn script_started_at(&self, run_id: &str, started_at: u64) -> &Self {
+        let mut state = self.state.lock().unwrap();
+        state
+            .scripts
+            .entry(run_id.to_string())
+            .or_default()
+            .started_at = Some(started_at);
+
+        self
+    }
+
+    /// Script the exit code reported once the container of a run has exited (0 by default).
+    pub fn script_exit_code(&self, run_id: &str, exit_code: i64) -> &Self {
+        let mut state = self.state.lock().unwrap();
+        state
+            .scripts
+            .entry(run_id.to_string())
+            .or_default()
+            .exit_code = Some(exit_code);
+
+        self
+    }
+
+    /// Script the time (seconds since the Unix epoch)
+    /// reported as the end of the run once its container has exited.
+    pub fn script_finished_at(&self, run_id: &str, finished_at: u64) -> &Self {
+        let mut state = self.state.lock().unwrap();
+        state
+            .scripts
+            .entry(run_id.to_string())
+            .or_default()
+            .finished_at = Some(finished_at);
+
+        self
+    }
+
+    /// Script the digest of the image steps are run from.
+    pub fn script_image_digest(&self, image_digest: &str) -> &Self {
+        self.state.lock().unwrap().image_digest = Some(image_digest.to_string());
+
+        self
+    }
+
+    /// Append logs to the container of a run, as if it was still writing them.
+    pub fn append_logs(&self, run_id: &str, logs: &str) -> &Self {
+        let mut state = self.state.lock().unwrap();
+        if let Some(container) = state.containers.get_mut(run_id) {
+            container.logs.push_str(logs);
+        }
+
+        self
+    }
+
+    pub fn container_exists(&self, run_id: &str) -> bool {
+        self.state.lock().unwrap().containers.contains_key(run_id)
+    }
+
+    /// List the calls received so far (e.g. "start_container program_verification-abc").
+    pub fn calls(&self) -> Vec<String> {
+        self.state.lock().unwrap().calls.clone()
+    }
+
+    /// Make a factory handing over clones of this client.
+    pub fn factory(&self) -> ContainerAPIClientFactory {
+        let client = self.clone();
+
+        Arc::new(move || Ok(Arc::new(client.clone()) as SharedContainerAPIClient))
+    }
+
+    fn record_call(&self, state: &mut State, call: &str, run_id: &str) {
+        state.calls.push(format!("{} {}", call, run_id));
+    }
+}
+
+fn run_id(project_step: &StepInVerificationPlan) -> String {
+    format_run_id(project_step.step().name(), project_step.project_id())
+}
+
+fn missing_container(run_id: &str) -> Report {
+    eyre!("There is no container having name \"{}\"", run_id)
+}
+
+#[async_trait]
+impl ContainerAPIClient for InMemoryContainerAPIClient {
+    type R = Result<HashMap<String, String>, Report>;
+    type P = Result<(), Report>;
+
+    async fn inspect_container_status(&self, project_step: &StepInVerificationPlan) -> Self::R {
+        let run_id = run_id(project_step);
+        let mut state = self.state.lock().unwrap();
+        self.record_call(&mut state, "inspect_container_status", run_id.as_str());
+
+        let container = state
+            .containers
+            .get_mut(run_id.as_str())
+            .ok_or_else(|| missing_container(run_id.as_str()))?;
+
+        let status = if container.statuses.len() > 1 {
+            container.statuses.pop_front().unwrap()
+        } else {
+            container.statuses.front().cloned().unwrap_or_default()
+        };
+
+        let mut message = HashMap::<String, String>::new();
+        message.insert("container_name".to_string(), run_id.clone());
+        message.insert("raw_status".to_string(), status.clone());
+        message.insert("oom_killed".to_string(), false.to_string());
+        if let Some(started_at) = container.started_at {
+            message.insert("started_at".to_string(), started_at.to_string());
+        }
+        if status != "running" {
+            if let Some(finished_at) = container.finished_at {
+                message.insert("finished_at".to_string(), finished_at.to_string());
+            }
+            message.insert("exit_code".to_string(), container.exit_code.to_string());
+        }
+        message.insert(
+            "message".to_string(),
+            format!(
+                "Status provided by inspection of container having name \"{}\" is \"{}\"",
+                run_id, status
+            ),
+        );
+
+        Ok(message)
+    }
+
+    async fn inspect_image(&self, project_step: &StepInVerificationPlan) -> Self::R {
+        let run_id = run_id(project_step);
+        let mut state = self.state.lock().unwrap();
+        self.record_call(&mut state, "inspect_image", run_id.as_str());
+
+        let mut message = HashMap::<String, String>::new();
+        message.insert("image".to_string(), "in-memory".to_string());
+        message.insert(
+            "image_digest".to_string(),
+            state
+                .image_digest
+                .clone()
+                .unwrap_or_else(|| DEFAULT_IMAGE_DIGEST.to_string()),
+        );
+
+        Ok(message)
+    }
+
+    async fn remove_existing_container(&self, project_step: &StepInVerificationPlan) -> Self::P {
+        let run_id = run_id(project_step);
+        let mut state = self.state.lock().unwrap();
+        self.record_call(&mut state, "remove_existing_container", run_id.as_str());
+
+        state.containers.remove(run_id.as_str());
+
+        Ok(())
+    }
+
+    async fn start_container(&self, project_step: &StepInVerificationPlan) -> Self::R {
+        let run_id = run_id(project_step);
+        let mut state = self.state.lock().unwrap();
+        self.record_call(&mut state, "start_container", run_id.as_str());
+
+        let script = state
+            .scripts
+            .get(run_id.as_str())
+            .cloned()
+            .unwrap_or_default();
+
+        if let Some(error) = script.start_error {
+            return Err(eyre!(error));
+        }
+
+        let statuses = if script.statuses.is_empty() {
+            DEFAULT_STATUSES.iter().map(|s| s.to_string()).collect()
+        } else {
+            script.statuses.into_iter().collect()
+        };
+
+        state.containers.insert(
+            run_id,
+            Container {
+                statuses,
+                logs: script.logs,
+                started_at: script.started_at,
+                finished_at: script.finished_at,
+                exit_code: script.exit_code.unwrap_or_default(),
+            },
+        );
+
+        Ok(HashMap::new())
+    }
+
+    async fn stop_container(&self, project_step: &StepInVerificationPlan) -> Self::P {
+        let run_id = run_id(project_step);
+        let mut state = self.state.lock().unwrap();
+        self.record_call(&mut state, "stop_container", run_id.as_str());
+
+        let container = state
+            .containers
+            .get_mut(run_id.as_str())
+            .ok_or_else(|| missing_container(run_id.as_str()))?;
+        container.statuses = VecDeque::from(vec!["exited".to_string()]);
+        // Exit code of a process terminated by SIGTERM
+        container.exit_code = 143;
+
+        Ok(())
+    }
+
+    async fn tail_container_logs(
+        &self,
+        project_step: &StepInVerificationPlan,
+        cursor: Option<&LogCursor>,
+    ) -> Self::R {
+        let run_id = run_id(project_step);
+        let mut state = self.state.lock().unwrap();
+        self.record_call(&mut state, "tail_container_logs", run_id.as_str());
+
+        let container = state
+            .containers
+            .get(run_id.as_str())
+            .ok_or_else(|| missing_container(run_id.as_str()))?;
+
+        let (new_logs, next_cursor) = slice_logs_since(container.logs.as_str(), cursor)?;
+
+        let mut message = HashMap::<String, String>::new();
+        message.insert("container_name".to_string(), run_id.clone());
+        message.insert(
+            "messages".to_string(),
+            format!(
+                "Logs tailed for container having name \"{}\":\n\n{}",
+                run_id, new_logs,
+            ),
+        );
+        message.insert("cursor".to_string(), next_cursor.to_string());
+        message.insert("raw_log".to_string(), new_logs);
+
+        Ok(message)
+    }
+
+    async fn list_runs(&self) -> Self::R {
+        let state = self.state.lock().unwrap();
+
+        Ok(state
+            .containers
+            .iter()
+            .map(|(run_id, container)| {
+                let status = container.statuses.front().cloned().unwrap_or_default();
+                (run_id.clone(), status)
+            })
+            .collect())
+    }
+}
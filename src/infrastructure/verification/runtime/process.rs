@@ -0,0 +1,570 @@
+use crate::domain::value_object::*;
+use crate::domain::verification_runtime::*;
+use crate::infra;
+use anyhow::Result;
+use async_trait::async_trait;
+use bollard::models::Mount;
+use color_eyre::{eyre::eyre, Report};
+use infra::build_cache::{BuildCache, BUILD_CACHE_VARIABLE};
+use infra::display::output;
+use infra::file_system;
+use infra::program_verification::format_run_id;
+use infra::scaffold::format_directory_path_to_scaffold;
+use infra::verification_runtime::docker::container;
+use nix::sys::signal::{self, Signal};
+use nix::unistd::{self, Pid};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::UNIX_EPOCH;
+use std::{env, fs, io};
+use tokio::process::Command;
+use tracing::debug;
+
+static LOG_FILE: &str = "output.log";
+static PID_FILE: &str = "pid";
+static EXIT_CODE_FILE: &str = "exit_code";
+static LABELS_FILE: &str = "labels.json";
+
+static STATUS_RUNNING: &str = "running";
+static STATUS_EXITED: &str = "exited";
+
+/// Image reported by the process runtime (steps running on the host)
+static PROCESS_IMAGE: &str = "host";
+
+/// Run verification steps as child processes of the backend
+/// on hosts where the RVT toolchain is installed natively.
+///
+/// Each run has its own directory (named after the run id)
+/// holding the process id, its combined output and its exit code,
+/// so that runs can be followed up across requests.
+pub struct ProcessAPIClient {
+    runs_directory: PathBuf,
+}
+
+/// Get the directory where process runs are tracked.
+/// Its path is declared as the value of the RUNS_DIRECTORY environment variable,
+/// and defaults to "safepkt-runs" in the temporary directory.
+pub fn get_runs_directory() -> PathBuf {
+    match env::var("RUNS_DIRECTORY") {
+        Ok(directory) => PathBuf::from(directory),
+        Err(_) => env::temp_dir().join("safepkt-runs"),
+    }
+}
+
+impl ProcessAPIClient {
+    pub fn new() -> Result<Self, Report> {
+        Self::with_runs_directory(get_runs_directory())
+    }
+
+    pub fn with_runs_directory(runs_directory: PathBuf) -> Result<Self, Report> {
+        fs::create_dir_all(runs_directory.as_path())?;
+
+        Ok(ProcessAPIClient { runs_directory })
+    }
+
+    pub fn run_directory(&self, project_step: &StepInVerificationPlan) -> PathBuf {
+        self.runs_directory.join(format_run_id(
+            project_step.step().name(),
+            project_step.project_id(),
+        ))
+    }
+
+    fn guard_against_missing_run(&self, run_directory: &Path) -> Result<(), Report> {
+        if !run_directory.exists() {
+            return Err(eyre!(
+                "There is no process having name \"{}\"",
+                run_directory.file_name().unwrap().to_string_lossy()
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+fn read_pid(run_directory: &Path) -> Option<Pid> {
+    fs::read_to_string(run_directory.join(PID_FILE))
+        .ok()
+        .and_then(|pid| pid.trim().parse::<i32>().ok())
+        .map(Pid::from_raw)
+}
+
+fn is_alive(pid: Pid) -> bool {
+    signal::kill(pid, None).is_ok()
+}
+
+fn get_modified_at(path: &Path) -> Option<u64> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map(|elapsed| elapsed.as_secs())
+}
+
+/// Get the time (seconds since the Unix epoch) at which a process has started
+fn get_started_at(run_directory: &Path) -> Option<u64> {
+    get_modified_at(run_directory.join(PID_FILE).as_path())
+}
+
+/// Get the time (seconds since the Unix epoch) at which a process has exited
+fn get_finished_at(run_directory: &Path) -> Option<u64> {
+    get_modified_at(run_directory.join(EXIT_CODE_FILE).as_path())
+}
+
+fn get_exit_code(run_directory: &Path) -> Option<i64> {
+    fs::read_to_string(run_directory.join(EXIT_CODE_FILE))
+        .ok()
+        .and_then(|exit_code| exit_code.trim().parse::<i64>().ok())
+}
+
+/// Labels of a run, recorded as they would be on the container of a step
+fn read_labels(run_directory: &Path) -> HashMap<String, String> {
+    fs::read_to_string(run_directory.join(LABELS_FILE))
+        .ok()
+        .and_then(|labels| serde_json::from_str(labels.as_str()).ok())
+        .unwrap_or_default()
+}
+
+fn get_status(run_directory: &Path) -> &'static str {
+    if run_directory.join(EXIT_CODE_FILE).exists() {
+        return STATUS_EXITED;
+    }
+
+    match read_pid(run_directory) {
+        Some(pid) if is_alive(pid) => STATUS_RUNNING,
+        _ => STATUS_EXITED,
+    }
+}
+
+/// Translate paths of the container layout
+/// into paths of the host file system,
+/// as mounted in the containers of a step
+/// (targets matching whole components of a path only).
+fn translate_path(path: &str, mounts: &[Mount]) -> String {
+    mounts
+        .iter()
+        .filter_map(|mount| {
+            Some((
+                Path::new(mount.target.as_deref()?),
+                mount.source.as_deref()?,
+            ))
+        })
+        .filter(|(target, _)| Path::new(path).starts_with(target))
+        .max_by_key(|(target, _)| target.components().count())
+        .and_then(|(target, source)| {
+            let rest = Path::new(path).strip_prefix(target).ok()?;
+            if rest.as_os_str().is_empty() {
+                return Some(source.to_string());
+            }
+
+            Some(Path::new(source).join(rest).to_string_lossy().into_owned())
+        })
+        .unwrap_or_else(|| path.to_string())
+}
+
+async fn start_process(
+    run_directory: &Path,
+    project_step: &StepInVerificationPlan<'_>,
+) -> Result<(), Report> {
+    let project_id = project_step.project_id();
+    let working_directory = format_directory_path_to_scaffold(project_id);
+    file_system::ensure_directory_exists(working_directory.as_str())?;
+
+    let command = container::step_command(project_step)?;
+    let mounts = container::get_mounts(project_step)?;
+    let mut arguments = command
+        .argv()
+        .iter()
+        .map(|argument| translate_path(argument, &mounts))
+        .collect::<Vec<String>>()
+        .into_iter();
+    let program = arguments.next().unwrap_or_default();
+
+    fs::create_dir_all(run_directory)?;
+    fs::write(
+        run_directory.join(LABELS_FILE),
+        serde_json::to_string(&container::labels::get_labels(project_step))?,
+    )?;
+    let log = fs::File::create(run_directory.join(LOG_FILE))?;
+
+    let mut child_command = Command::new(program.as_str());
+    child_command
+        .args(arguments)
+        .current_dir(working_directory.as_str())
+        .env_clear()
+        .env("PATH", env::var("PATH").unwrap_or_default())
+        .env("HOME", working_directory.as_str())
+        .env("UID_GID", container::get_uid_gid()?)
+        .envs(
+            command
+                .environment()
+                .iter()
+                .map(|(name, value)| (name, translate_path(value, &mounts))),
+        )
+        .stdin(Stdio::null())
+        .stdout(log.try_clone()?)
+        .stderr(log);
+
+    // Only the verification script builds projects
+    let build_cache_entry = match BuildCache::from_env()? {
+        Some(build_cache) if command.program() == container::TARGET_VERIFICATION_SCRIPT => {
+            Some(build_cache.prepare(project_id)?)
+        }
+        _ => None,
+    };
+    if let Some(build_cache_entry) = build_cache_entry.as_ref() {
+        child_command.env(BUILD_CACHE_VARIABLE, build_cache_entry.path().as_os_str());
+    }
+
+    // Run the step in its own session
+    // so that stopping it also stops the processes it has spawned.
+    unsafe {
+        child_command.pre_exec(|| {
+            unistd::setsid()
+                .map(|_| ())
+                .map_err(|errno| io::Error::from_raw_os_error(errno as i32))
+        });
+    }
+
+    output::print(
+        "About to start process with name {} running {}",
+        vec![
+            run_directory.file_name().unwrap().to_str().unwrap(),
+            program.as_str(),
+        ],
+        None,
+    );
+
+    let mut child = child_command.spawn()?;
+    let pid = child
+        .id()
+        .ok_or_else(|| eyre!("Could not get the id of process running \"{}\"", program))?;
+    fs::write(run_directory.join(PID_FILE), pid.to_string())?;
+
+    // The step locks its build cache entry itself once spawned
+    drop(build_cache_entry);
+
+    let exit_code_path = run_directory.join(EXIT_CODE_FILE);
+    tokio::spawn(async move {
+        if let Ok(status) = child.wait().await {
+            let exit_code = status.code().unwrap_or(-1);
+            debug!("Process {} exited with code {}", pid, exit_code);
+
+            fs::write(exit_code_path, exit_code.to_string()).ok();
+        }
+    });
+
+    Ok(())
+}
+
+/// Read the logs written from the offset provided by a cursor,
+/// without reading the previous ones.
+fn read_logs_since(
+    log_path: &Path,
+    cursor: Option<&LogCursor>,
+) -> Result<(String, LogCursor), Report> {
+    let mut offset = LogCursor::offset(cursor)?;
+
+    let mut log = match fs::File::open(log_path) {
+        Ok(log) => log,
+        Err(_) => return Ok((String::new(), LogCursor::Offset(offset))),
+    };
+
+    let length = log.metadata()?.len();
+    // Logs of a restarted step are read from their beginning
+    if offset > length {
+        offset = 0;
+    }
+
+    log.seek(SeekFrom::Start(offset))?;
+    let mut new_logs = vec![];
+    log.take(length - offset).read_to_end(&mut new_logs)?;
+
+    // Characters being written are left for the next read
+    if let Err(error) = std::str::from_utf8(&new_logs) {
+        if error.error_len().is_none() {
+            new_logs.truncate(error.valid_up_to());
+        }
+    }
+    let next_offset = offset + new_logs.len() as u64;
+
+    Ok((
+        String::from_utf8_lossy(&new_logs).to_string(),
+        LogCursor::Offset(next_offset),
+    ))
+}
+
+fn stop_process(run_directory: &Path) -> Result<(), Report> {
+    if let Some(pid) = read_pid(run_directory) {
+        if is_alive(pid) {
+            // Negative pid targets the whole process group
+            signal::kill(Pid::from_raw(-pid.as_raw()), Signal::SIGTERM)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[async_trait]
+impl ContainerAPIClient for ProcessAPIClient {
+    type R = Result<HashMap<String, String>, Report>;
+    type P = Result<(), Report>;
+
+    async fn inspect_container_status(&self, project_step: &StepInVerificationPlan) -> Self::R {
+        let run_directory = self.run_directory(project_step);
+        self.guard_against_missing_run(run_directory.as_path())?;
+
+        let process_name = format_run_id(project_step.step().name(), project_step.project_id());
+        let status = get_status(run_directory.as_path());
+
+        let mut message = HashMap::<String, String>::new();
+        message.insert("container_name".to_string(), process_name.clone());
+        message.insert("raw_status".to_string(), status.to_string());
+        message.insert("oom_killed".to_string(), false.to_string());
+        if let Some(started_at) = get_started_at(run_directory.as_path()) {
+            message.insert("started_at".to_string(), started_at.to_string());
+        }
+        if let Some(finished_at) = get_finished_at(run_directory.as_path()) {
+            message.insert("finished_at".to_string(), finished_at.to_string());
+        }
+        if let Some(exit_code) = get_exit_code(run_directory.as_path()) {
+            message.insert("exit_code".to_string(), exit_code.to_string());
+        }
+        message.extend(container::labels::get_backend_metadata(&read_labels(
+            run_directory.as_path(),
+        )));
+        message.insert(
+            "message".to_string(),
+            format!(
+                "Status provided by inspection of process having name \"{}\" is \"{}\"",
+                process_name, status,
+            ),
+        );
+
+        Ok(message)
+    }
+
+    async fn inspect_image(&self, _: &StepInVerificationPlan) -> Self::R {
+        let verification_script = container::get_verification_script_path()?;
+        let content = fs::read(verification_script.as_str()).map_err(|error| {
+            eyre!(
+                "Could not read verification script \"{}\": {}",
+                verification_script,
+                error
+            )
+        })?;
+
+        // Steps are run with the toolchain installed on the host,
+        // identified by the verification script running it
+        let mut message = HashMap::<String, String>::new();
+        message.insert("image".to_string(), PROCESS_IMAGE.to_string());
+        message.insert(
+            "image_digest".to_string(),
+            format!("sha256:{}", hex::encode(Sha256::digest(content.as_slice()))),
+        );
+
+        Ok(message)
+    }
+
+    async fn remove_existing_container(&self, project_step: &StepInVerificationPlan) -> Self::P {
+        let run_directory = self.run_directory(project_step);
+
+        if run_directory.exists() {
+            stop_process(run_directory.as_path())?;
+            fs::remove_dir_all(run_directory)?;
+        }
+
+        Ok(())
+    }
+
+    async fn start_container(&self, project_step: &StepInVerificationPlan) -> Self::R {
+        let run_directory = self.run_directory(project_step);
+        start_process(run_directory.as_path(), project_step).await?;
+
+        Ok(HashMap::new())
+    }
+
+    async fn stop_container(&self, project_step: &StepInVerificationPlan) -> Self::P {
+        let run_directory = self.run_directory(project_step);
+        self.guard_against_missing_run(run_directory.as_path())?;
+
+        output::print(
+            "About to stop process with name \"{}\"",
+            vec![run_directory.file_name().unwrap().to_str().unwrap()],
+            None,
+        );
+
+        stop_process(run_directory.as_path())
+    }
+
+    async fn tail_container_logs(
+        &self,
+        project_step: &StepInVerificationPlan,
+        cursor: Option<&LogCursor>,
+    ) -> Self::R {
+        let run_directory = self.run_directory(project_step);
+        self.guard_against_missing_run(run_directory.as_path())?;
+
+        let process_name = format_run_id(project_step.step().name(), project_step.project_id());
+        let (new_logs, next_cursor) =
+            read_logs_since(run_directory.join(LOG_FILE).as_path(), cursor)?;
+
+        debug!("About to tail logs for process \"{}\"", process_name);
+        output::print("{}", vec![new_logs.as_str()], Some(true));
+
+        let mut message = HashMap::<String, String>::new();
+        message.insert("container_name".to_string(), process_name.clone());
+        message.insert(
+            "messages".to_string(),
+            format!(
+                "Logs tailed for process having name \"{}\":\n\n{}",
+                process_name, new_logs,
+            ),
+        );
+        message.insert("cursor".to_string(), next_cursor.to_string());
+        message.insert("raw_log".to_string(), new_logs);
+
+        Ok(message)
+    }
+
+    async fn list_runs(&self) -> Self::R {
+        let mut runs = HashMap::<String, String>::new();
+        for entry in fs::read_dir(self.runs_directory.as_path())? {
+            let run_directory = entry?.path();
+            if !run_directory.is_dir() {
+                continue;
+            }
+
+            let run_id = run_directory.file_name().unwrap().to_string_lossy();
+            runs.insert(
+                run_id.to_string(),
+                get_status(run_directory.as_path()).to_string(),
+            );
+        }
+
+        Ok(runs)
+    }
+}
+
+#[tokio::test]
+async fn it_runs_a_step_as_a_child_process() {
+    use crate::infra::step_registry::step_registry;
+    use crate::infra::SOURCE_RESTORATION;
+    use crate::test;
+    use std::time::Duration;
+
+    test::configure_env();
+
+    let project_id = test::upload_source();
+    let project_directory = format_directory_path_to_scaffold(project_id.as_str());
+    fs::create_dir_all(format!("{}/src", project_directory)).unwrap();
+    fs::write(format!("{}/src/lib.rs", project_directory), "fn main() {}").unwrap();
+
+    let runs_directory = env::temp_dir().join(format!("{}-runs", project_id));
+    let client = ProcessAPIClient::with_runs_directory(runs_directory.clone()).unwrap();
+
+    let step = Step::new(step_registry().unwrap().step(SOURCE_RESTORATION).unwrap());
+    let project_step = StepInVerificationPlan::new(project_id.clone(), step);
+
+    assert!(client
+        .inspect_container_status(&project_step)
+        .await
+        .is_err());
+    assert!(client.start_container(&project_step).await.is_ok());
+
+    let mut status = String::new();
+    for _ in 0..50 {
+        status = client
+            .inspect_container_status(&project_step)
+            .await
+            .unwrap()["raw_status"]
+            .clone();
+        if status == STATUS_EXITED {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert_eq!(STATUS_EXITED, status);
+
+    let logs = client
+        .tail_container_logs(&project_step, None)
+        .await
+        .unwrap();
+    assert_eq!("fn main() {}", logs["raw_log"]);
+
+    let run_id = format_run_id(SOURCE_RESTORATION, project_id.as_str());
+    assert_eq!(
+        Some(&STATUS_EXITED.to_string()),
+        client.list_runs().await.unwrap().get(run_id.as_str())
+    );
+
+    assert!(client
+        .remove_existing_container(&project_step)
+        .await
+        .is_ok());
+    assert!(!client.run_directory(&project_step).exists());
+
+    test::remove_project(project_id.as_str());
+    fs::remove_dir_all(runs_directory).unwrap();
+}
+
+#[test]
+fn it_reads_logs_written_since_a_cursor() {
+    use crate::test;
+
+    let log_path = env::temp_dir().join(format!("{}.log", test::generate_random_letters()));
+    fs::write(log_path.as_path(), "Running 1 test\n").unwrap();
+
+    let (logs, cursor) = read_logs_since(log_path.as_path(), None).unwrap();
+    assert_eq!("Running 1 test\n", logs);
+    assert_eq!(LogCursor::Offset(15), cursor);
+
+    // A character being written is left for the next read
+    let mut partial = "Elapsed: 1µ".as_bytes().to_vec();
+    partial.pop();
+    fs::write(
+        log_path.as_path(),
+        [b"Running 1 test\n".to_vec(), partial].concat(),
+    )
+    .unwrap();
+
+    let (logs, cursor) = read_logs_since(log_path.as_path(), Some(&cursor)).unwrap();
+    assert_eq!("Elapsed: 1", logs);
+    assert_eq!(LogCursor::Offset(25), cursor);
+
+    fs::remove_file(log_path).ok();
+}
+
+#[test]
+fn it_translates_paths_through_the_mounts_of_steps() {
+    let mount = |target: &str, source: &str| Mount {
+        target: Some(target.to_string()),
+        source: Some(source.to_string()),
+        ..Default::default()
+    };
+    let mounts = vec![
+        mount("/source", "/var/safepkt/47a9690570"),
+        mount("/source/deps", "/var/cache/safepkt/deps"),
+    ];
+
+    assert_eq!(
+        "/var/safepkt/47a9690570/src/lib.rs",
+        translate_path("/source/src/lib.rs", &mounts)
+    );
+    assert_eq!(
+        "/var/safepkt/47a9690570",
+        translate_path("/source", &mounts)
+    );
+    assert_eq!(
+        "/var/cache/safepkt/deps/registry",
+        translate_path("/source/deps/registry", &mounts)
+    );
+    // Targets are not matched as mere prefixes of a component
+    assert_eq!(
+        "/sources/lib.rs",
+        translate_path("/sources/lib.rs", &mounts)
+    );
+    assert_eq!("multisig_plain", translate_path("multisig_plain", &mounts));
+}
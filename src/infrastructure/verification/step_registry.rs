@@ -0,0 +1,343 @@
+use crate::domain::value_object::{
+    StepDefinition, VerifierBackend, AUDIT_PARSER, CLIPPY_PARSER, KLEE_PARSER, MIRI_PARSER,
+    NO_PARSER,
+};
+use crate::infra;
+use anyhow::Result;
+use color_eyre::{eyre::eyre, Report};
+use infra::verification_runtime::docker::container::{self, ResourceLimits};
+use regex::Regex;
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::env;
+use std::fs;
+use std::sync::OnceLock;
+
+/// Registry built into the backend
+static DEFAULT_STEP_REGISTRY: &str = include_str!("../../../steps.toml.dist");
+
+/// Placeholders available in commands (argv and env)
+pub static COMMAND_PLACEHOLDERS: [&str; 6] = [
+    "project_id",
+    "project_name",
+    "bitcode",
+    "entry_point",
+    "ink_template",
+    "ink_directory",
+];
+
+/// Placeholders available in mounts
+pub static MOUNT_PLACEHOLDERS: [&str; 11] = [
+    "project_id",
+    "project_name",
+    "bitcode",
+    "entry_point",
+    "ink_template",
+    "ink_directory",
+    "source_directory",
+    "uploaded_source",
+    "project_directory",
+    "rvt_directory",
+    "advisory_database",
+];
+
+static PARSERS: [&str; 5] = [
+    KLEE_PARSER,
+    CLIPPY_PARSER,
+    MIRI_PARSER,
+    AUDIT_PARSER,
+    NO_PARSER,
+];
+
+/// Steps the backend can run, declared in a TOML registry
+/// so that analysis tools can be added without recompiling the backend
+/// (steps.toml.dist being built into the backend as the default registry).
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StepRegistry {
+    steps: Vec<StepDefinition>,
+}
+
+fn find_placeholders(template: &str) -> Vec<String> {
+    Regex::new(r"\{([^{}]*)\}")
+        .unwrap()
+        .captures_iter(template)
+        .map(|captures| captures[1].to_string())
+        .collect()
+}
+
+fn validate_template(step_name: &str, template: &str, placeholders: &[&str]) -> Result<(), Report> {
+    match find_placeholders(template)
+        .into_iter()
+        .find(|placeholder| !placeholders.contains(&placeholder.as_str()))
+    {
+        Some(placeholder) => Err(eyre!(
+            "Unknown placeholder \"{{{}}}\" in \"{}\" of step \"{}\"",
+            placeholder,
+            template,
+            step_name
+        )),
+        None => Ok(()),
+    }
+}
+
+fn validate_step(step: &StepDefinition) -> Result<(), Report> {
+    let name = step.name();
+
+    // Names end up in run ids, container names and environment variable names
+    if !Regex::new(r"^[a-z][a-z0-9_]*$").unwrap().is_match(name) {
+        return Err(eyre!(
+            "Invalid step name \"{}\" (expecting lowercase letters, digits and underscores)",
+            name
+        ));
+    }
+
+    if step.argv().first().is_none_or(|program| program.is_empty()) {
+        return Err(eyre!("Step \"{}\" has no program to run", name));
+    }
+
+    for argument in step.argv() {
+        validate_template(name, argument, &COMMAND_PLACEHOLDERS)?;
+    }
+
+    let variable_name = Regex::new(r"^[A-Za-z_][A-Za-z0-9_]*$").unwrap();
+    for (variable, value) in step.env() {
+        if !variable_name.is_match(variable) {
+            return Err(eyre!(
+                "Invalid environment variable name \"{}\" for step \"{}\"",
+                variable,
+                name
+            ));
+        }
+        validate_template(name, value, &COMMAND_PLACEHOLDERS)?;
+    }
+
+    let mut targets = HashSet::new();
+    for mount in step.mounts() {
+        validate_template(name, mount.source(), &MOUNT_PLACEHOLDERS)?;
+        validate_template(name, mount.target(), &MOUNT_PLACEHOLDERS)?;
+
+        if !mount.target().starts_with('/') {
+            return Err(eyre!(
+                "Mount target \"{}\" of step \"{}\" is not an absolute path",
+                mount.target(),
+                name
+            ));
+        }
+
+        if container::built_in_mount_targets().contains(&mount.target())
+            || !targets.insert(mount.target())
+        {
+            return Err(eyre!(
+                "Mount target \"{}\" of step \"{}\" is mounted more than once",
+                mount.target(),
+                name
+            ));
+        }
+    }
+
+    if !PARSERS.contains(&step.parser()) {
+        return Err(eyre!(
+            "Unknown parser \"{}\" for step \"{}\" (expecting one of {})",
+            step.parser(),
+            name,
+            PARSERS.join(", ")
+        ));
+    }
+
+    if let Some(backend) = step.backend() {
+        VerifierBackend::parse(backend)
+            .map_err(|error| eyre!("{} for step \"{}\"", error, name))?;
+    }
+
+    ResourceLimits::from_definition_lookup(step, |_| None)
+        .map_err(|report| eyre!("Invalid limits of step \"{}\": {}", name, report))?;
+
+    Ok(())
+}
+
+impl StepRegistry {
+    /// Parse a registry before validating the steps it declares
+    pub fn parse(content: &str) -> Result<Self, Report> {
+        let registry: StepRegistry =
+            toml::from_str(content).map_err(|error| eyre!("Invalid step registry: {}", error))?;
+
+        if registry.steps.is_empty() {
+            return Err(eyre!("Invalid step registry: no step has been declared"));
+        }
+
+        let mut names = HashSet::new();
+        for step in registry.steps.iter() {
+            validate_step(step)?;
+
+            if !names.insert(step.name()) {
+                return Err(eyre!("Step \"{}\" is declared more than once", step.name()));
+            }
+        }
+
+        Ok(registry)
+    }
+
+    pub fn from_env() -> Result<Self, Report> {
+        match env::var("STEP_REGISTRY")
+            .ok()
+            .filter(|path| !path.is_empty())
+        {
+            Some(path) => {
+                let content = fs::read_to_string(path.as_str()).map_err(|error| {
+                    eyre!("Could not read step registry \"{}\": {}", path, error)
+                })?;
+
+                Self::parse(content.as_str())
+                    .map_err(|report| eyre!("{} (declared in \"{}\")", report, path))
+            }
+            None => Self::parse(DEFAULT_STEP_REGISTRY),
+        }
+    }
+
+    pub fn steps(&self) -> &[StepDefinition] {
+        self.steps.as_slice()
+    }
+
+    pub fn step(&self, name: &str) -> Option<&StepDefinition> {
+        self.steps.iter().find(|step| step.name() == name)
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.steps.iter().map(StepDefinition::name).collect()
+    }
+}
+
+fn registry() -> &'static OnceLock<StepRegistry> {
+    static REGISTRY: OnceLock<StepRegistry> = OnceLock::new();
+
+    &REGISTRY
+}
+
+/// Get the registry declared by configuration, loaded on first use
+/// (and on startup, so that invalid registries are reported before serving any request).
+pub fn step_registry() -> Result<&'static StepRegistry, Report> {
+    if let Some(registry) = registry().get() {
+        return Ok(registry);
+    }
+
+    let loaded = StepRegistry::from_env()?;
+
+    Ok(registry().get_or_init(|| loaded))
+}
+
+#[test]
+fn it_loads_the_default_step_registry() {
+    use crate::infra::{
+        DEPENDENCY_AUDIT, PROGRAM_FUZZING, PROGRAM_LINTING, PROGRAM_MIRI, PROGRAM_VERIFICATION,
+        SOURCE_RESTORATION, UPLOADED_SOURCES_LISTING,
+    };
+
+    let registry = StepRegistry::parse(DEFAULT_STEP_REGISTRY).unwrap();
+
+    assert_eq!(
+        vec![
+            PROGRAM_FUZZING,
+            PROGRAM_VERIFICATION,
+            UPLOADED_SOURCES_LISTING,
+            SOURCE_RESTORATION,
+            PROGRAM_LINTING,
+            PROGRAM_MIRI,
+            DEPENDENCY_AUDIT
+        ],
+        registry.names()
+    );
+    assert_eq!(
+        Some("safepkt/miri:nightly"),
+        registry.step(PROGRAM_MIRI).unwrap().image()
+    );
+    assert_eq!(
+        CLIPPY_PARSER,
+        registry.step(PROGRAM_LINTING).unwrap().parser()
+    );
+    assert_eq!(
+        KLEE_PARSER,
+        registry.step(PROGRAM_VERIFICATION).unwrap().parser()
+    );
+    assert!(!registry
+        .step(UPLOADED_SOURCES_LISTING)
+        .unwrap()
+        .requires_scaffolding());
+}
+
+#[test]
+fn it_declares_steps_without_recompiling_the_backend() {
+    let registry = StepRegistry::parse(
+        r#"
+[[steps]]
+name = "program_linting"
+image = "rust:1.56"
+argv = ["cargo", "clippy", "{project_name}"]
+env = { CARGO_TARGET_DIR = "/tmp/{project_id}" }
+limits = { memory = "2g" }
+"#,
+    )
+    .unwrap();
+
+    let step = registry.step("program_linting").unwrap();
+    assert_eq!(Some("rust:1.56"), step.image());
+    assert_eq!(NO_PARSER, step.parser());
+
+    let command = step.command(&[("project_id", "47a9690570"), ("project_name", "")]);
+    assert_eq!(vec!["cargo", "clippy"], command.argv());
+    assert_eq!(vec!["CARGO_TARGET_DIR=/tmp/47a9690570"], command.env_vars());
+}
+
+#[test]
+fn it_rejects_invalid_step_registries() {
+    let error = |content: &str| StepRegistry::parse(content).unwrap_err().to_string();
+
+    assert_eq!(
+        "Invalid step registry: no step has been declared",
+        error("steps = []")
+    );
+    assert_eq!(
+        "Step \"listing\" is declared more than once",
+        error(
+            r#"
+[[steps]]
+name = "listing"
+argv = ["ls"]
+
+[[steps]]
+name = "listing"
+argv = ["ls", "-l"]
+"#
+        )
+    );
+    assert_eq!(
+        "Invalid step name \"program-linting\" (expecting lowercase letters, digits and underscores)",
+        error("[[steps]]\nname = \"program-linting\"\nargv = [\"cargo\"]")
+    );
+    assert_eq!(
+        "Unknown placeholder \"{source_directory}\" in \"{source_directory}\" of step \"listing\"",
+        error("[[steps]]\nname = \"listing\"\nargv = [\"ls\", \"{source_directory}\"]")
+    );
+    assert_eq!(
+        "Unknown parser \"rustfmt\" for step \"listing\" (expecting one of klee, clippy, miri, audit, none)",
+        error("[[steps]]\nname = \"listing\"\nargv = [\"ls\"]\nparser = \"rustfmt\"")
+    );
+    assert_eq!(
+        "Mount target \"/safepkt-ink/examples/source\" of step \"listing\" is mounted more than once",
+        error(
+            "[[steps]]\nname = \"listing\"\nargv = [\"ls\"]\nmounts = [{ source = \"/tmp\", target = \"/safepkt-ink/examples/source\" }]"
+        )
+    );
+    assert_eq!(
+        "Invalid limits of step \"listing\": Invalid value \"lots\" for CONTAINER_PIDS_LIMIT_LISTING",
+        error("[[steps]]\nname = \"listing\"\nargv = [\"ls\"]\nlimits = { pids = \"lots\" }")
+    );
+    assert!(
+        error("[[steps]]\nname = \"listing\"\nargv = [\"ls\"]\nentrypoint = \"sh\"")
+            .contains("unknown field `entrypoint`")
+    );
+    assert_eq!(
+        "Unknown backend \"cbmc\" (expecting one of klee, proptest, seahorn) for step \"listing\"",
+        error("[[steps]]\nname = \"listing\"\nargv = [\"ls\"]\nbackend = \"cbmc\"")
+    );
+}
@@ -0,0 +1,240 @@
+use crate::domain::verification_runtime::VerificationRuntime;
+use crate::infrastructure as infra;
+use anyhow::Result;
+use color_eyre::{eyre::eyre, Report};
+use infra::program_verification::{format_run_id, which_step};
+use infra::verification::SharedContainerAPIClient;
+use std::collections::HashMap;
+use std::env;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{debug, error};
+
+/// Status reported in place of "exited"
+/// when a step has been stopped for exceeding its time budget
+pub static STATUS_TIMED_OUT: &str = "timed_out";
+
+static STATUS_RUNNING: &str = "running";
+
+#[derive(Default)]
+struct Deadline {
+    watchdog: Option<JoinHandle<()>>,
+    timed_out: bool,
+}
+
+/// Deadlines of runs started by this backend instance, indexed by run id
+fn deadlines() -> &'static Mutex<HashMap<String, Deadline>> {
+    static DEADLINES: OnceLock<Mutex<HashMap<String, Deadline>>> = OnceLock::new();
+
+    DEADLINES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn parse_seconds(variable: &str, value: String) -> Result<u64, Report> {
+    value.trim().parse::<u64>().map_err(|_| {
+        eyre!(
+            "Invalid value \"{}\" for {} (expecting seconds)",
+            value,
+            variable
+        )
+    })
+}
+
+/// Get the time budget of a step from configuration,
+/// which can be lowered (or raised up to MAX_STEP_TIMEOUT) per request:
+///  - STEP_TIMEOUT - default time budget of steps in seconds
+///  - STEP_TIMEOUT_<STEP NAME> - time budget of a step (e.g. STEP_TIMEOUT_PROGRAM_VERIFICATION)
+///  - MAX_STEP_TIMEOUT - upper bound of time budgets requested per run,
+///    which defaults to the time budget of the step
+pub fn get_time_budget(
+    step_name: &str,
+    requested_seconds: Option<u64>,
+) -> Result<Option<Duration>, Report> {
+    get_time_budget_from_lookup(step_name, requested_seconds, |name| {
+        env::var(name).ok().filter(|value| !value.is_empty())
+    })
+}
+
+pub fn get_time_budget_from_lookup<L: Fn(&str) -> Option<String>>(
+    step_name: &str,
+    requested_seconds: Option<u64>,
+    lookup: L,
+) -> Result<Option<Duration>, Report> {
+    let step_variable = format!("STEP_TIMEOUT_{}", step_name.to_uppercase());
+
+    let configured_seconds = match lookup(step_variable.as_str()) {
+        Some(value) => Some(parse_seconds(step_variable.as_str(), value)?),
+        None => lookup("STEP_TIMEOUT")
+            .map(|value| parse_seconds("STEP_TIMEOUT", value))
+            .transpose()?,
+    };
+
+    let max_seconds = match lookup("MAX_STEP_TIMEOUT") {
+        Some(value) => Some(parse_seconds("MAX_STEP_TIMEOUT", value)?),
+        None => configured_seconds,
+    };
+
+    let seconds = match (requested_seconds, max_seconds) {
+        (Some(requested), Some(max)) => Some(requested.min(max)),
+        (Some(requested), None) => Some(requested),
+        (None, _) => configured_seconds,
+    };
+
+    Ok(seconds
+        .filter(|seconds| *seconds > 0)
+        .map(Duration::from_secs))
+}
+
+/// Get the time KLEE is allowed to explore paths for,
+/// so that it can write partial statistics before the step is stopped.
+/// Forwarding the time budget to KLEE is enabled by declaring
+/// KLEE_MAX_TIME_MARGIN (seconds kept for KLEE to wrap up).
+pub fn get_klee_max_time(time_budget: Option<Duration>) -> Option<u64> {
+    let margin = env::var("KLEE_MAX_TIME_MARGIN")
+        .ok()
+        .and_then(|margin| margin.trim().parse::<u64>().ok())?;
+
+    time_budget
+        .map(|time_budget| time_budget.as_secs().saturating_sub(margin))
+        .filter(|max_time| *max_time > 0)
+}
+
+pub fn has_timed_out(run_id: &str) -> bool {
+    deadlines()
+        .lock()
+        .unwrap()
+        .get(run_id)
+        .is_some_and(|deadline| deadline.timed_out)
+}
+
+/// Stop watching a run (e.g. when it is stopped on demand or restarted)
+pub fn forget(run_id: &str) {
+    if let Some(deadline) = deadlines().lock().unwrap().remove(run_id) {
+        if let Some(watchdog) = deadline.watchdog {
+            watchdog.abort();
+        }
+    }
+}
+
+async fn enforce(
+    container_api_client: SharedContainerAPIClient,
+    step_name: String,
+    project_id: String,
+) -> Result<bool, Report> {
+    let steps = VerificationRuntime::build_steps()?;
+    let step = which_step(&steps, step_name, project_id)?;
+
+    let status = container_api_client.inspect_container_status(&step).await?;
+    if status.get("raw_status").map(String::as_str) != Some(STATUS_RUNNING) {
+        return Ok(false);
+    }
+
+    container_api_client.stop_container(&step).await?;
+
+    Ok(true)
+}
+
+/// Stop a run once its time budget has been exceeded,
+/// in which case it is reported as timed out.
+pub fn watch(
+    container_api_client: SharedContainerAPIClient,
+    step_name: &str,
+    project_id: &str,
+    time_budget: Duration,
+) {
+    let run_id = format_run_id(step_name, project_id);
+    forget(run_id.as_str());
+
+    let step_name = step_name.to_string();
+    let project_id = project_id.to_string();
+    let watched_run_id = run_id.clone();
+
+    // Hold the lock while spawning so that the watchdog
+    // can not mark the run as timed out before its deadline is registered.
+    let mut registry = deadlines().lock().unwrap();
+
+    // The deadline is counted from the start of the run, not from the first poll of the watchdog
+    let deadline = tokio::time::Instant::now() + time_budget;
+    let watchdog = tokio::spawn(async move {
+        tokio::time::sleep_until(deadline).await;
+
+        match enforce(container_api_client, step_name, project_id).await {
+            Ok(true) => {
+                debug!(
+                    "Stopped run \"{}\" after {} seconds",
+                    watched_run_id,
+                    time_budget.as_secs()
+                );
+
+                if let Some(deadline) = deadlines().lock().unwrap().get_mut(watched_run_id.as_str())
+                {
+                    deadline.timed_out = true;
+                    deadline.watchdog = None;
+                }
+            }
+            Ok(false) => {}
+            Err(report) => error!(
+                "Could not enforce time budget of run \"{}\": {}",
+                watched_run_id, report
+            ),
+        }
+    });
+
+    registry.insert(
+        run_id,
+        Deadline {
+            watchdog: Some(watchdog),
+            timed_out: false,
+        },
+    );
+}
+
+#[test]
+fn it_caps_time_budgets_requested_per_run() {
+    let lookup = |name: &str| match name {
+        "STEP_TIMEOUT" => Some("600".to_string()),
+        "STEP_TIMEOUT_PROGRAM_FUZZING" => Some("120".to_string()),
+        "MAX_STEP_TIMEOUT" => Some("900".to_string()),
+        _ => None,
+    };
+
+    let budget = |step_name: &str, requested: Option<u64>| {
+        get_time_budget_from_lookup(step_name, requested, lookup).unwrap()
+    };
+
+    assert_eq!(
+        Some(Duration::from_secs(600)),
+        budget("program_verification", None)
+    );
+    assert_eq!(
+        Some(Duration::from_secs(120)),
+        budget("program_fuzzing", None)
+    );
+    assert_eq!(
+        Some(Duration::from_secs(300)),
+        budget("program_verification", Some(300))
+    );
+    assert_eq!(
+        Some(Duration::from_secs(900)),
+        budget("program_verification", Some(3600))
+    );
+
+    let without_max = |name: &str| match name {
+        "STEP_TIMEOUT" => Some("600".to_string()),
+        _ => None,
+    };
+    assert_eq!(
+        Some(Duration::from_secs(600)),
+        get_time_budget_from_lookup("program_verification", Some(3600), without_max).unwrap()
+    );
+    assert_eq!(
+        None,
+        get_time_budget_from_lookup("program_verification", None, |_| None).unwrap()
+    );
+    assert!(
+        get_time_budget_from_lookup("program_verification", None, |_| Some(
+            "forever".to_string()
+        ))
+        .is_err()
+    );
+}
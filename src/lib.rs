@@ -14,7 +14,9 @@ pub mod app {
         use super::super::domain;
 
         pub use domain::program_verification;
+        pub use domain::project::ink_template;
         pub use domain::project::manifest;
+        pub use domain::project::settings;
         pub use domain::value_object;
         pub use domain::verification_runtime;
     }
@@ -23,15 +25,28 @@ pub mod app {
 pub mod infra {
     use super::infrastructure;
 
+    pub use infrastructure::build_cache;
     pub use infrastructure::display;
+    pub use infrastructure::garbage_collection;
+    pub use infrastructure::pipeline;
     pub use infrastructure::program_verification;
+    pub use infrastructure::report;
+    pub use infrastructure::result_cache;
     pub use infrastructure::scaffold;
     pub use infrastructure::service::*;
     pub use infrastructure::signal_handling;
     pub use infrastructure::sigpipe;
+    pub use infrastructure::step_registry;
+    pub use infrastructure::time_budget;
     pub use infrastructure::verification_runtime;
 
+    pub use infrastructure::default_container_api_client_factory;
+    pub use infrastructure::ContainerAPIClientFactory;
+    pub use infrastructure::SharedContainerAPIClient;
+    pub use infrastructure::DEPENDENCY_AUDIT;
     pub use infrastructure::PROGRAM_FUZZING;
+    pub use infrastructure::PROGRAM_LINTING;
+    pub use infrastructure::PROGRAM_MIRI;
     pub use infrastructure::PROGRAM_VERIFICATION;
     pub use infrastructure::SOURCE_RESTORATION;
     pub use infrastructure::UPLOADED_SOURCES_LISTING;
@@ -39,7 +54,16 @@ pub mod infra {
 
 #[cfg(test)]
 pub mod test {
+    use crate::domain::verification_runtime::VerificationRuntime;
+    use crate::infra::file_system;
+    use crate::infra::program_verification::{format_run_id, which_step};
+    use crate::infra::scaffold::format_directory_path_to_scaffold;
+    use crate::infra::verification_runtime::in_memory::InMemoryContainerAPIClient;
+    use crate::infra::SharedContainerAPIClient;
     use rand::prelude::*;
+    use std::path::PathBuf;
+    use std::sync::Once;
+    use std::{env, fs};
 
     pub fn generate_random_letters() -> String {
         const CHARSET: &[u8] = b"abcdefghijklmnopqrstuvwxyz";
@@ -57,6 +81,100 @@ pub mod test {
         word
     }
 
+    /// Declare the configuration tests rely on,
+    /// once for all tests as they run in parallel threads of the same process
+    pub fn configure_env() {
+        static CONFIGURATION: Once = Once::new();
+
+        CONFIGURATION.call_once(|| {
+            env::set_var("SOURCE_DIRECTORY", env::temp_dir());
+            env::set_var(
+                "UID_GID",
+                format!("{}:{}", nix::unistd::getuid(), nix::unistd::getgid()),
+            );
+            env::set_var("VERIFICATION_SCRIPT", "/usr/local/bin/verify");
+            env::set_var(
+                "UPLOADED_SOURCES_LISTING_SCRIPT",
+                "/usr/local/bin/list-uploaded-sources",
+            );
+            env::set_var("RVT_DIRECTORY", "/tmp/rvt");
+            env::remove_var("ADVISORY_DATABASE");
+        });
+    }
+
+    /// Path of a file uploaded for a project (e.g. its source or its settings)
+    pub fn uploaded_file_path(project_id: &str, extension: &str) -> PathBuf {
+        configure_env();
+
+        PathBuf::from(file_system::get_uploaded_source_directory().unwrap())
+            .join(format!("{}{}", project_id, extension))
+    }
+
+    /// Upload a source before returning the id of its project
+    pub fn upload_source() -> String {
+        configure_env();
+
+        let source = format!("pub fn {}() {{}}", generate_random_letters());
+        let (_, project_id) =
+            file_system::save_content_in_file_system(base64::encode(source).as_bytes()).unwrap();
+
+        project_id
+    }
+
+    /// Remove an uploaded source and its scaffolded project
+    pub fn remove_project(project_id: &str) {
+        for extension in [
+            file_system::BASE64_ENCODED_SOURCE_EXTENSION,
+            file_system::PROJECT_SETTINGS_EXTENSION,
+            file_system::PIPELINE_PROGRESS_EXTENSION,
+        ] {
+            fs::remove_file(uploaded_file_path(project_id, extension)).ok();
+        }
+        fs::remove_dir_all(format_directory_path_to_scaffold(project_id)).ok();
+    }
+
+    /// Project uploaded for a test along with an in-memory container API client
+    /// scripting the runs of its steps, the project being removed once dropped
+    pub struct InMemoryProject {
+        pub project_id: String,
+        pub client: InMemoryContainerAPIClient,
+    }
+
+    impl InMemoryProject {
+        pub fn upload() -> Self {
+            InMemoryProject {
+                project_id: upload_source(),
+                client: InMemoryContainerAPIClient::new(),
+            }
+        }
+
+        pub fn run_id(&self, step_name: &str) -> String {
+            format_run_id(step_name, self.project_id.as_str())
+        }
+
+        /// Runtime of a step of the project backed by the in-memory client
+        pub fn runtime(
+            &self,
+            step_name: &str,
+        ) -> VerificationRuntime<'static, SharedContainerAPIClient> {
+            let steps = VerificationRuntime::build_steps().unwrap();
+            let step = which_step(&steps, step_name.to_string(), self.project_id.clone()).unwrap();
+
+            VerificationRuntime::with_container_api_client_factory(
+                &self.client.factory(),
+                step,
+                steps,
+            )
+            .unwrap()
+        }
+    }
+
+    impl Drop for InMemoryProject {
+        fn drop(&mut self) {
+            remove_project(self.project_id.as_str());
+        }
+    }
+
     #[test]
     fn it_generates_random_letters() {
         let word = generate_random_letters();